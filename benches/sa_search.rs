@@ -11,7 +11,7 @@ pub struct OwnedSA {
     pub data: Vec<u8>,
     #[covariant]
     #[borrows(data)]
-    pub sa: SuffixArray<'this>,
+    pub sa: SuffixArray<&'this [u8]>,
 }
 
 macro_rules! search_method_bench {
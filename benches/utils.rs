@@ -1,18 +1,18 @@
 #![allow(dead_code)]
+#![cfg(feature = "bench-tools")]
 
-use self::DataLength::*;
-use self::PatternLength::*;
 use criterion::Criterion;
 use flate2::read::GzDecoder;
-use rand::distributions::Uniform;
-use rand::{thread_rng, Rng};
 use std::fs;
 use std::io;
-use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::io::{Error, ErrorKind, Read, Result};
 use std::path::PathBuf;
+use suffix_array::bench_tools::{random_text, BenchRng, PATTERN_SCHEMES};
 use suffix_array::MAX_LENGTH;
 
-const SEPARATOR: &'static str = "~";
+const SEPARATOR: &str = "~";
+
+const SEED: u64 = 0x5eed;
 
 static RANDOM_DATA_STATS: &[(&str, u8, usize)] = &[
     // (sample_name, alphabet, length)
@@ -44,64 +44,6 @@ static PIZZA_CHILI_URLS: &[(&str, &str, bool)] = &[
     ),
 ];
 
-static PATTERN_SCHEMES: &[(&str, DataLength, PatternLength, PatternLength)] = &[
-    // (pattern_name, sample_length_filter, final_pattern_length, random_trailing_junk)
-    ("select-32b", Unlimited, Fixed(32), Fixed(0)),
-    ("hybrid-32b", Till(1024 * 1024), Fixed(32), Fixed(16)),
-    ("select-4k", Unlimited, Fixed(4 * 1024), Fixed(0)),
-    (
-        "hybrid-4k",
-        Till(1024 * 1024),
-        Fixed(4 * 1024),
-        Fixed(2 * 1024),
-    ),
-    ("select-5%", Since(1024 * 1024), Ratio(0.05), Fixed(0)),
-    ("hybrid-5%", Since(1024 * 1024), Ratio(0.05), Ratio(0.5)),
-];
-
-#[derive(Clone, Copy)]
-enum DataLength {
-    Unlimited,
-    Since(usize),
-    Till(usize),
-    Ranged(usize, usize),
-}
-
-impl DataLength {
-    pub fn contains(&self, dlen: usize) -> bool {
-        match self {
-            &Unlimited => true,
-            &Since(x) => dlen >= x,
-            &Till(x) => dlen <= x,
-            &Ranged(x, y) => dlen >= x && dlen <= y,
-        }
-    }
-}
-
-#[derive(Clone, Copy)]
-enum PatternLength {
-    Fixed(usize),
-    Ratio(f64),
-}
-
-impl PatternLength {
-    pub fn calculate(&self, accord: usize) -> usize {
-        match self {
-            &Fixed(n) => Ord::min(accord, n),
-            &Ratio(mut r) => {
-                if r > 1.0 {
-                    r = 1.0;
-                }
-                if r < 0.0 {
-                    r = 0.0;
-                }
-                let n = (r * accord as f64) as usize;
-                Ord::min(accord, n)
-            }
-        }
-    }
-}
-
 pub fn load_data(dir: &str, name: &str) -> Result<Vec<u8>> {
     let fpath = PathBuf::from(dir).join(name);
     let flen = fs::metadata(&fpath)?.len();
@@ -129,18 +71,11 @@ pub fn make_data(dir: &str) -> Result<(Vec<&'static str>, Vec<&'static str>)> {
     }
 
     // Generates random data if not already created.
-    for &(name, scale, mut length) in RANDOM_DATA_STATS.iter() {
+    for &(name, scale, length) in RANDOM_DATA_STATS.iter() {
         let fpath = PathBuf::from(dir).join(name);
         if fs::metadata(&fpath).is_err() {
             eprintln!("generating sample {}", name);
-            let mut file = fs::File::create(&fpath)?;
-            let mut buf = vec![0; 4096];
-            while length > 0 {
-                let n = Ord::min(length, buf.len());
-                random_bytes(scale, &mut buf[..n]);
-                file.write_all(&buf[..n])?;
-                length -= n;
-            }
+            fs::write(&fpath, random_text(scale, length, SEED))?;
         }
         samples.push(name);
     }
@@ -168,34 +103,24 @@ pub fn make_data(dir: &str) -> Result<(Vec<&'static str>, Vec<&'static str>)> {
         samples.push(name);
     }
 
-    for &(name, _, _, _) in PATTERN_SCHEMES.iter() {
-        patterns.push(name);
+    for scheme in PATTERN_SCHEMES.iter() {
+        patterns.push(scheme.name);
     }
 
     // Generate patterns for each sample if not already created.
     for &sname in samples.iter() {
         let sdata = load_data(dir, sname)?;
-        for &(pname, dlen, plen, jlen) in PATTERN_SCHEMES.iter() {
-            if !dlen.contains(sdata.len()) {
+        let mut rng = BenchRng::new(SEED);
+        for scheme in PATTERN_SCHEMES.iter() {
+            if !scheme.applies_to(sdata.len()) {
                 continue;
             }
 
-            let full_name = String::from(sname) + SEPARATOR + pname;
+            let full_name = String::from(sname) + SEPARATOR + scheme.name;
             let fpath = PathBuf::from(dir).join(full_name);
             if fs::metadata(&fpath).is_err() {
-                eprintln!("generating pattern {}~{}", sname, pname);
-                let mut file = fs::File::create(fpath)?;
-                let total = plen.calculate(sdata.len());
-                let b = jlen.calculate(total);
-                let a = total - b;
-
-                let mut pdata = Vec::with_capacity(total);
-                pdata.extend_from_slice(random_select(a, &sdata[..]));
-                let mut rng = thread_rng();
-                for _ in 0..b {
-                    pdata.push(rng.gen::<u8>());
-                }
-                file.write_all(&pdata[..])?;
+                eprintln!("generating pattern {}~{}", sname, scheme.name);
+                fs::write(fpath, scheme.generate(&sdata[..], &mut rng))?;
             }
         }
     }
@@ -203,26 +128,12 @@ pub fn make_data(dir: &str) -> Result<(Vec<&'static str>, Vec<&'static str>)> {
     Ok((samples, patterns))
 }
 
-fn random_bytes(scale: u8, buf: &mut [u8]) {
-    let uni = Uniform::from(0..=scale);
-    let mut rng = thread_rng();
-    buf.iter_mut().for_each(|c| *c = rng.sample(uni));
-}
-
-fn random_select(len: usize, buf: &[u8]) -> &[u8] {
-    let uni = Uniform::from(0..=buf.len() - len);
-    let mut rng = thread_rng();
-    let i = rng.sample(uni);
-    &buf[i..i + len]
-}
-
 // dirty hack
 pub fn set_criterion_samples(crit: &mut Criterion, mut n: usize) {
     if n < 2 {
         n = 2;
     }
 
-    let mut tmp = std::mem::replace(crit, Criterion::default());
-    tmp = tmp.sample_size(n);
-    std::mem::replace(crit, tmp);
+    let tmp = std::mem::take(crit);
+    *crit = tmp.sample_size(n);
 }
@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use suffix_array::SuffixArray;
+
+// the leading byte splits the input into the byte string and the dump.
+fuzz_target!(|data: &[u8]| {
+    if let Some((&split, data)) = data.split_first() {
+        let (s, bytes) = data.split_at(Ord::min(split as usize, data.len()));
+        if let Ok(sa) = SuffixArray::load_bytes(s, bytes) {
+            assert_eq!(sa, SuffixArray::new(s));
+        }
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use suffix_array::{OwnedSuffixArray, SuffixArray};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(sa) = OwnedSuffixArray::load_owned(data) {
+        assert_eq!(sa, SuffixArray::new(sa.text()));
+    }
+});
@@ -0,0 +1,55 @@
+/*
+ * C interface of the suffix_array crate, built with the `ffi` feature, e.g.
+ *
+ *     cargo rustc --release --features ffi --crate-type cdylib
+ *
+ * All the functions are thread-safe on distinct handles, and a handle could
+ * be queried from several threads at once, but not while being destroyed.
+ */
+
+#ifndef SUFFIX_ARRAY_H
+#define SUFFIX_ARRAY_H
+
+#include <stddef.h>
+#include <stdint.h>
+#include <sys/types.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Opaque suffix array owning a copy of its byte string. */
+typedef struct suffix_array_t suffix_array_t;
+
+/* Construct the suffix array of a copy of `data`, or return NULL if it is
+ * too long. `data` may be NULL if `len` is 0. */
+suffix_array_t *suffix_array_new(const uint8_t *data, size_t len);
+
+/* Destroy the suffix array, doing nothing if NULL. */
+void suffix_array_free(suffix_array_t *sa);
+
+/* Length of the underlying byte string, or 0 if NULL. */
+size_t suffix_array_len(const suffix_array_t *sa);
+
+/* Return 1 if the pattern occurs, 0 if not, or -1 on NULL arguments. */
+int suffix_array_contains(const suffix_array_t *sa, const uint8_t *pat,
+                          size_t pat_len);
+
+/* Write at most `cap` of the occurrences in text order into `out`, and
+ * return the total number of occurrences, or -1 on NULL arguments. */
+ssize_t suffix_array_search_all(const suffix_array_t *sa, const uint8_t *pat,
+                                size_t pat_len, uint32_t *out, size_t cap);
+
+/* Write the suffix array with its byte string into the file at the UTF-8
+ * `path`, returning 0 on success or -1 on failure. */
+int suffix_array_dump(const suffix_array_t *sa, const char *path);
+
+/* Load the file written by suffix_array_dump, or return NULL if it is
+ * unreadable or corrupt. */
+suffix_array_t *suffix_array_load(const char *path);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SUFFIX_ARRAY_H */
@@ -0,0 +1,383 @@
+use std::io::{prelude::*, Error, ErrorKind, Result, SeekFrom};
+use std::sync::{Mutex, OnceLock};
+
+use bincode::Options;
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::buckets::Buckets;
+use super::frequent::FrequentSubstring;
+use super::packed_sa::{bincode_options, error_conv, PackedSuffixArray};
+use super::sa::SuffixArray;
+use super::storage::TextStorage;
+
+// Little endian of b"SAar", i.e. the Suffix Array ARchive with sections.
+const MAGIC_ARCHIVE: u32 = 1918976339;
+
+// Kinds of sections.
+const SECTION_SA: u32 = 0;
+const SECTION_BUCKETS: u32 = 1;
+const SECTION_LCP: u32 = 2;
+const SECTION_TOP_SUBSTRINGS: u32 = 3;
+const SECTION_REVERSE: u32 = 4;
+
+/// Size of each entry of the section table.
+const ENTRY_SIZE: u64 = 24;
+
+/// Optional sections of an archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    /// The bucket pointers, see `SuffixArray::enable_buckets`.
+    Buckets,
+    /// The LCP array.
+    Lcp,
+    /// The `k` most frequent sub-strings of at least `min_len` bytes, see
+    /// `SuffixArray::top_substrings`. The parameters are ignored when
+    /// testing for the section.
+    TopSubstrings { k: usize, min_len: usize },
+    /// The suffix array of the reversed byte string, see
+    /// `SuffixArray::enable_reverse_index`.
+    ReverseIndex,
+}
+
+impl Section {
+    fn kind(self) -> u32 {
+        match self {
+            Section::Buckets => SECTION_BUCKETS,
+            Section::Lcp => SECTION_LCP,
+            Section::TopSubstrings { .. } => SECTION_TOP_SUBSTRINGS,
+            Section::ReverseIndex => SECTION_REVERSE,
+        }
+    }
+}
+
+/// Location of a section, relative to the start of archive, and the CRC-32
+/// of its bytes.
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    kind: u32,
+    offset: u64,
+    len: u64,
+    crc: u32,
+}
+
+/// Archive of suffix array with optional sections, which are read from the
+/// seekable reader only on first use.
+///
+/// Each section is checked against its own checksum when read, so a corrupt
+/// optional section could be rebuilt instead of rejecting the whole archive.
+///
+/// The byte string is not included, just like `SuffixArray::dump`.
+#[derive(Debug)]
+pub struct Archive<R> {
+    file: Mutex<R>,
+    base: u64,
+    entries: Vec<Entry>,
+    lcp: OnceLock<Vec<u32>>,
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// Read the section table of archive, leaving the sections unread.
+    pub fn open(mut file: R) -> Result<Self> {
+        let base = file.stream_position()?;
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header)?;
+        let magic =
+            u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let count =
+            u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        if magic != MAGIC_ARCHIVE {
+            return Err(Error::new(ErrorKind::InvalidData, "bad magic number"));
+        }
+
+        // the table may not be longer than the archive itself.
+        let end = file.seek(SeekFrom::End(0))?;
+        let table_end = 8 + count as u64 * ENTRY_SIZE;
+        if base + table_end > end {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "truncated archive",
+            ));
+        }
+        file.seek(SeekFrom::Start(base + 8))?;
+
+        let mut entries = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let mut buf = [0u8; ENTRY_SIZE as usize];
+            file.read_exact(&mut buf)?;
+            let mut kind = [0u8; 4];
+            let mut offset = [0u8; 8];
+            let mut len = [0u8; 8];
+            let mut crc = [0u8; 4];
+            kind.copy_from_slice(&buf[..4]);
+            offset.copy_from_slice(&buf[4..12]);
+            len.copy_from_slice(&buf[12..20]);
+            crc.copy_from_slice(&buf[20..]);
+            let entry = Entry {
+                kind: u32::from_le_bytes(kind),
+                offset: u64::from_le_bytes(offset),
+                len: u64::from_le_bytes(len),
+                crc: u32::from_le_bytes(crc),
+            };
+            let in_bounds = entry.offset >= table_end
+                && entry
+                    .offset
+                    .checked_add(entry.len)
+                    .and_then(|x| x.checked_add(base))
+                    .is_some_and(|x| x <= end);
+            if !in_bounds {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "section out of bounds",
+                ));
+            }
+            entries.push(entry);
+        }
+        if !entries.iter().any(|entry| entry.kind == SECTION_SA) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "missing suffix array section",
+            ));
+        }
+
+        Ok(Archive {
+            file: Mutex::new(file),
+            base,
+            entries,
+            lcp: OnceLock::new(),
+        })
+    }
+
+    /// Test if the archive contains the given section.
+    pub fn has_section(&self, section: Section) -> bool {
+        self.find(section.kind()).is_some()
+    }
+
+    /// Load the suffix array of `s`, without any of the optional sections.
+    pub fn load<T: TextStorage>(&self, s: T) -> Result<SuffixArray<T>> {
+        let psa: PackedSuffixArray = self.read_section(SECTION_SA)?;
+        let psa = PackedSuffixArray::validate(psa)?;
+        let sa = psa.into_sa_of(s.as_ref().len())?;
+        SuffixArray::from_parts(s, sa).ok_or_else(|| {
+            Error::new(ErrorKind::InvalidData, "inconsistent suffix array")
+        })
+    }
+
+    /// Load the bucket pointers into the suffix array loaded from archive.
+    ///
+    /// Fails with `ErrorKind::NotFound` if the section is absent.
+    pub fn load_buckets<T: TextStorage>(
+        &self,
+        sa: &mut SuffixArray<T>,
+    ) -> Result<()> {
+        let bkt: Buckets = self.read_section(SECTION_BUCKETS)?;
+        if !bkt.check_shape(sa.len() + 1) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "inconsistent buckets",
+            ));
+        }
+        sa.set_buckets(bkt);
+        Ok(())
+    }
+
+    /// Load the bucket pointers into the suffix array loaded from archive,
+    /// or enable them at depth 2 if the section is absent or corrupt.
+    ///
+    /// Returns whether the bucket pointers are loaded from archive.
+    pub fn load_buckets_or_rebuild<T: TextStorage>(
+        &self,
+        sa: &mut SuffixArray<T>,
+    ) -> Result<bool> {
+        match self.load_buckets(sa) {
+            Ok(()) => Ok(true),
+            Err(e) if is_recoverable(&e) => {
+                sa.enable_buckets();
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Load the suffix array of the reversed byte string into the suffix
+    /// array loaded from archive, checking its integrity.
+    ///
+    /// Fails with `ErrorKind::NotFound` if the section is absent.
+    pub fn load_reverse_index<T: TextStorage>(
+        &self,
+        sa: &mut SuffixArray<T>,
+    ) -> Result<()> {
+        let psa: PackedSuffixArray = self.read_section(SECTION_REVERSE)?;
+        let psa = PackedSuffixArray::validate(psa)?;
+        let rev_sa = psa.into_sa_of(sa.len())?;
+        let rev_text = sa.text().iter().rev().cloned().collect();
+        let rev =
+            SuffixArray::from_parts(rev_text, rev_sa).ok_or_else(|| {
+                Error::new(ErrorKind::InvalidData, "inconsistent reverse index")
+            })?;
+        sa.set_reverse(rev);
+        Ok(())
+    }
+
+    /// Load the suffix array of the reversed byte string like
+    /// `load_reverse_index`, or construct it if the section is absent or
+    /// corrupt.
+    ///
+    /// Returns whether it is loaded from archive.
+    pub fn load_reverse_index_or_rebuild<T: TextStorage>(
+        &self,
+        sa: &mut SuffixArray<T>,
+    ) -> Result<bool> {
+        match self.load_reverse_index(sa) {
+            Ok(()) => Ok(true),
+            Err(e) if is_recoverable(&e) => {
+                sa.enable_reverse_index();
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Get the LCP array, which is loaded on first use and then cached.
+    ///
+    /// It is not checked against the suffix array, as that takes as much
+    /// time as computing it again.
+    ///
+    /// Fails with `ErrorKind::NotFound` if the section is absent.
+    pub fn lcp(&self) -> Result<&[u32]> {
+        if let Some(lcp) = self.lcp.get() {
+            return Ok(&lcp[..]);
+        }
+
+        // concurrent callers may both read, but only one is kept.
+        let psa: PackedSuffixArray = self.read_section(SECTION_LCP)?;
+        let lcp = PackedSuffixArray::validate(psa)?.into_sa();
+        Ok(&self.lcp.get_or_init(|| lcp)[..])
+    }
+
+    /// Get the LCP array like `lcp`, or compute it from the suffix array
+    /// loaded from archive if the section is absent or corrupt.
+    pub fn lcp_or_rebuild<T: TextStorage>(
+        &self,
+        sa: &SuffixArray<T>,
+    ) -> Result<&[u32]> {
+        match self.lcp() {
+            Ok(lcp) => Ok(lcp),
+            Err(e) if is_recoverable(&e) => {
+                let lcp = sa.compute_lcp();
+                Ok(&self.lcp.get_or_init(|| lcp)[..])
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Get the frequent sub-strings recorded when the archive is dumped,
+    /// most frequent first.
+    ///
+    /// They are checked against the length of the byte string, but not
+    /// against the byte string itself.
+    ///
+    /// Fails with `ErrorKind::NotFound` if the section is absent.
+    pub fn top_substrings(
+        &self,
+        text_len: usize,
+    ) -> Result<Vec<FrequentSubstring>> {
+        let top: Vec<FrequentSubstring> =
+            self.read_section(SECTION_TOP_SUBSTRINGS)?;
+        let in_bounds = top.iter().all(|sub| {
+            let end = sub.offset as u64 + sub.len as u64;
+            sub.len > 0 && sub.count >= 2 && end <= text_len as u64
+        });
+        if !in_bounds {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "inconsistent frequent sub-strings",
+            ));
+        }
+        Ok(top)
+    }
+
+    fn find(&self, kind: u32) -> Option<Entry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.kind == kind)
+            .cloned()
+    }
+
+    fn read_section<S: DeserializeOwned>(&self, kind: u32) -> Result<S> {
+        let entry = self.find(kind).ok_or_else(|| {
+            Error::new(ErrorKind::NotFound, "section not found")
+        })?;
+
+        let mut bytes = Vec::new();
+        {
+            let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+            file.seek(SeekFrom::Start(self.base + entry.offset))?;
+            (&mut *file).take(entry.len).read_to_end(&mut bytes)?;
+        }
+        if bytes.len() as u64 != entry.len {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "truncated section",
+            ));
+        }
+        if crc32fast::hash(&bytes[..]) != entry.crc {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "section checksum mismatch",
+            ));
+        }
+        bincode_options()
+            .deserialize_from(&bytes[..])
+            .map_err(error_conv)
+    }
+}
+
+/// Test if the section is absent or corrupt, rather than unreadable.
+fn is_recoverable(e: &Error) -> bool {
+    matches!(e.kind(), ErrorKind::NotFound | ErrorKind::InvalidData)
+}
+
+/// Write the archive of suffix array with the optional sections.
+pub fn write_archive<W: Write>(
+    mut file: W,
+    sa: &[u32],
+    bkt: Option<&Buckets>,
+    lcp: Option<&[u32]>,
+    top: Option<&[FrequentSubstring]>,
+    rev: Option<&[u32]>,
+) -> Result<()> {
+    let mut sections =
+        vec![(SECTION_SA, encode(&PackedSuffixArray::from_sa(sa))?)];
+    if let Some(bkt) = bkt {
+        sections.push((SECTION_BUCKETS, encode(bkt)?));
+    }
+    if let Some(lcp) = lcp {
+        sections.push((SECTION_LCP, encode(&PackedSuffixArray::from_sa(lcp))?));
+    }
+    if let Some(top) = top {
+        sections.push((SECTION_TOP_SUBSTRINGS, encode(&top)?));
+    }
+    if let Some(rev) = rev {
+        sections
+            .push((SECTION_REVERSE, encode(&PackedSuffixArray::from_sa(rev))?));
+    }
+
+    file.write_all(&MAGIC_ARCHIVE.to_le_bytes())?;
+    file.write_all(&(sections.len() as u32).to_le_bytes())?;
+    let mut offset = 8 + sections.len() as u64 * ENTRY_SIZE;
+    for &(kind, ref bytes) in sections.iter() {
+        file.write_all(&kind.to_le_bytes())?;
+        file.write_all(&offset.to_le_bytes())?;
+        file.write_all(&(bytes.len() as u64).to_le_bytes())?;
+        file.write_all(&crc32fast::hash(&bytes[..]).to_le_bytes())?;
+        offset += bytes.len() as u64;
+    }
+    for (_, bytes) in sections.iter() {
+        file.write_all(&bytes[..])?;
+    }
+    file.flush()
+}
+
+fn encode<S: Serialize>(section: &S) -> Result<Vec<u8>> {
+    bincode_options().serialize(section).map_err(error_conv)
+}
@@ -0,0 +1,373 @@
+//! Reproducible benchmark helpers, to compare the construction algorithms
+//! and the searching methods on custom corpora.
+//!
+//! The texts and patterns are drawn from a seeded generator, so that runs
+//! with the same seed are comparable across machines and versions. The
+//! measurements are collected into a `BenchReport`, which is written as
+//! JSON.
+//!
+//! ```rust
+//! use suffix_array::bench_tools::{random_text, run, BenchConfig};
+//!
+//! let text = random_text(3, 4096, 42);
+//! let report = run(&text, &BenchConfig::default());
+//! assert_eq!(report.text_len, 4096);
+//! assert!(report.to_json().starts_with('{'));
+//! ```
+
+use std::io::{Result, Write};
+use std::time::Instant;
+
+use serde::Serialize;
+
+use super::sa::SuffixArray;
+use super::stats::{Algorithm, MemoryBreakdown};
+
+/// Lengths of the texts a pattern scheme applies to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextLength {
+    /// Any length.
+    Unlimited,
+    /// At least the given length.
+    Since(usize),
+    /// At most the given length.
+    Till(usize),
+    /// Within the given lengths, inclusively.
+    Ranged(usize, usize),
+}
+
+impl TextLength {
+    /// Test if the text length is accepted.
+    pub fn contains(&self, len: usize) -> bool {
+        match *self {
+            TextLength::Unlimited => true,
+            TextLength::Since(x) => len >= x,
+            TextLength::Till(x) => len <= x,
+            TextLength::Ranged(x, y) => len >= x && len <= y,
+        }
+    }
+}
+
+/// Length of a generated pattern, relative to a given length.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PatternLength {
+    /// Fixed length, truncated to the given length.
+    Fixed(usize),
+    /// Ratio of the given length, clamped into `0.0..=1.0`.
+    Ratio(f64),
+}
+
+impl PatternLength {
+    /// Calculate the length relative to `accord`.
+    pub fn calculate(&self, accord: usize) -> usize {
+        match *self {
+            PatternLength::Fixed(n) => Ord::min(accord, n),
+            PatternLength::Ratio(r) => {
+                let r = r.clamp(0.0, 1.0);
+                Ord::min(accord, (r * accord as f64) as usize)
+            }
+        }
+    }
+}
+
+/// Scheme of the generated patterns.
+///
+/// A pattern is a random sub-string of the text followed by random junk
+/// bytes, so that `junk` controls how likely the pattern mismatches.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PatternScheme {
+    /// Name of the scheme, shown in the reports.
+    pub name: &'static str,
+    /// Lengths of the texts the scheme applies to.
+    pub text_len: TextLength,
+    /// Total length of the pattern, relative to the text length.
+    pub len: PatternLength,
+    /// Length of the trailing junk, relative to the total length.
+    pub junk: PatternLength,
+}
+
+/// The pattern schemes used by the benches of this crate.
+pub static PATTERN_SCHEMES: &[PatternScheme] = &[
+    PatternScheme::new("select-8b", TextLength::Unlimited, 8, 0),
+    PatternScheme::new("hybrid-8b", TextLength::Till(1 << 20), 8, 4),
+    PatternScheme::new("select-32b", TextLength::Unlimited, 32, 0),
+    PatternScheme::new("hybrid-32b", TextLength::Till(1 << 20), 32, 16),
+    PatternScheme::new("select-4k", TextLength::Unlimited, 4096, 0),
+    PatternScheme::new("hybrid-4k", TextLength::Till(1 << 20), 4096, 2048),
+    PatternScheme {
+        name: "select-5%",
+        text_len: TextLength::Since(1 << 20),
+        len: PatternLength::Ratio(0.05),
+        junk: PatternLength::Fixed(0),
+    },
+    PatternScheme {
+        name: "hybrid-5%",
+        text_len: TextLength::Since(1 << 20),
+        len: PatternLength::Ratio(0.05),
+        junk: PatternLength::Ratio(0.5),
+    },
+];
+
+impl PatternScheme {
+    const fn new(
+        name: &'static str,
+        text_len: TextLength,
+        len: usize,
+        junk: usize,
+    ) -> Self {
+        PatternScheme {
+            name,
+            text_len,
+            len: PatternLength::Fixed(len),
+            junk: PatternLength::Fixed(junk),
+        }
+    }
+
+    /// Test if the scheme applies to a text of given length.
+    pub fn applies_to(&self, text_len: usize) -> bool {
+        self.text_len.contains(text_len)
+    }
+
+    /// Generate a pattern out of the text.
+    pub fn generate(&self, text: &[u8], rng: &mut BenchRng) -> Vec<u8> {
+        let total = self.len.calculate(text.len());
+        let junk = self.junk.calculate(total);
+        let selected = total - junk;
+
+        let i = rng.below(text.len() - selected + 1);
+        let mut pat = Vec::with_capacity(total);
+        pat.extend_from_slice(&text[i..i + selected]);
+        pat.extend((0..junk).map(|_| rng.next_u64() as u8));
+        pat
+    }
+}
+
+/// Seeded pseudo random generator (SplitMix64) of the benchmark inputs.
+#[derive(Debug, Clone)]
+pub struct BenchRng(u64);
+
+impl BenchRng {
+    /// Create a generator from the seed.
+    pub fn new(seed: u64) -> Self {
+        BenchRng(seed)
+    }
+
+    /// Next random word.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Random integer in `0..n`, or 0 if `n` is 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+/// Generate a random text of bytes in `0..=alphabet`.
+pub fn random_text(alphabet: u8, len: usize, seed: u64) -> Vec<u8> {
+    let mut rng = BenchRng::new(seed);
+    let scale = alphabet as usize + 1;
+    (0..len).map(|_| rng.below(scale) as u8).collect()
+}
+
+/// Configuration of a benchmark run.
+#[derive(Debug, Clone)]
+pub struct BenchConfig {
+    /// Construction algorithms to be timed.
+    pub algorithms: Vec<Algorithm>,
+    /// Pattern schemes to be queried, the inapplicable ones are skipped.
+    pub schemes: Vec<PatternScheme>,
+    /// Number of patterns generated for each scheme.
+    pub queries: usize,
+    /// Seed of the generated patterns.
+    pub seed: u64,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        BenchConfig {
+            algorithms: vec![
+                Algorithm::Divsufsort,
+                Algorithm::Sais,
+                Algorithm::PrefixDoubling,
+            ],
+            schemes: PATTERN_SCHEMES.to_vec(),
+            queries: 16,
+            seed: 0,
+        }
+    }
+}
+
+/// Measurements of a benchmark run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BenchReport {
+    /// Length of the text.
+    pub text_len: usize,
+    /// Seed of the generated patterns.
+    pub seed: u64,
+    /// Construction time of each algorithm.
+    pub constructions: Vec<ConstructionTiming>,
+    /// Query latencies of each pattern scheme and searching method.
+    pub queries: Vec<QueryTiming>,
+    /// Heap memory of the queried suffix array, with buckets enabled.
+    pub memory: MemoryReport,
+}
+
+/// Construction time of an algorithm.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConstructionTiming {
+    /// Name of the algorithm.
+    pub algorithm: String,
+    /// Elapsed time in nanoseconds.
+    pub nanos: u64,
+}
+
+/// Latencies of a searching method over the patterns of a scheme.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QueryTiming {
+    /// Name of the pattern scheme.
+    pub scheme: String,
+    /// Name of the searching method.
+    pub method: String,
+    /// Number of the queried patterns.
+    pub samples: usize,
+    /// Average length of the patterns.
+    pub mean_pattern_len: usize,
+    /// Minimum latency in nanoseconds.
+    pub min_nanos: u64,
+    /// Median latency in nanoseconds.
+    pub median_nanos: u64,
+    /// Maximum latency in nanoseconds.
+    pub max_nanos: u64,
+    /// Average latency in nanoseconds.
+    pub mean_nanos: u64,
+}
+
+/// Heap memory in bytes, see `MemoryBreakdown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MemoryReport {
+    /// The suffix array itself.
+    pub sa: usize,
+    /// The bucket pointers.
+    pub buckets: usize,
+    /// The kept LCP array.
+    pub lcp: usize,
+    /// The other auxiliary structures.
+    pub extra: usize,
+    /// Total heap memory.
+    pub total: usize,
+}
+
+impl From<MemoryBreakdown> for MemoryReport {
+    fn from(m: MemoryBreakdown) -> Self {
+        MemoryReport {
+            sa: m.sa,
+            buckets: m.buckets,
+            lcp: m.lcp,
+            extra: m.extra,
+            total: m.total(),
+        }
+    }
+}
+
+impl BenchReport {
+    /// Format the report as pretty printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("serializable report")
+    }
+
+    /// Write the report as JSON.
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<()> {
+        serde_json::to_writer_pretty(writer, self).map_err(Into::into)
+    }
+}
+
+/// Run the benchmark over the text.
+///
+/// Each algorithm constructs the suffix array once, and the patterns are
+/// then queried one by one on the last constructed suffix array, falling
+/// back to the default construction if no algorithm is given.
+pub fn run(text: &[u8], config: &BenchConfig) -> BenchReport {
+    let mut constructions = Vec::with_capacity(config.algorithms.len());
+    let mut last = None;
+    for &algorithm in config.algorithms.iter() {
+        let start = Instant::now();
+        let sa = SuffixArray::with_algorithm(text, algorithm);
+        constructions.push(ConstructionTiming {
+            algorithm: format!("{:?}", algorithm),
+            nanos: elapsed_nanos(start),
+        });
+        last = Some(sa);
+    }
+    let mut sa = last.unwrap_or_else(|| SuffixArray::new(text));
+    sa.enable_buckets();
+
+    let mut rng = BenchRng::new(config.seed);
+    let mut queries = Vec::new();
+    for scheme in config.schemes.iter() {
+        if !scheme.applies_to(text.len()) || config.queries == 0 {
+            continue;
+        }
+        let pats: Vec<_> = (0..config.queries)
+            .map(|_| scheme.generate(text, &mut rng))
+            .collect();
+        let mean_len = pats.iter().map(Vec::len).sum::<usize>() / pats.len();
+
+        let mut time = |method: &str, query: &dyn Fn(&[u8])| {
+            let nanos = pats
+                .iter()
+                .map(|pat| {
+                    let start = Instant::now();
+                    query(pat);
+                    elapsed_nanos(start)
+                })
+                .collect();
+            queries.push(summarize(scheme.name, method, mean_len, nanos));
+        };
+        time("contains", &|pat| {
+            std::hint::black_box(sa.contains(pat));
+        });
+        time("search_all", &|pat| {
+            std::hint::black_box(sa.search_all(pat));
+        });
+        time("search_lcp", &|pat| {
+            std::hint::black_box(sa.search_lcp(pat));
+        });
+    }
+
+    BenchReport {
+        text_len: text.len(),
+        seed: config.seed,
+        constructions,
+        queries,
+        memory: sa.memory_usage().into(),
+    }
+}
+
+fn elapsed_nanos(start: Instant) -> u64 {
+    start.elapsed().as_nanos().min(u64::MAX as u128) as u64
+}
+
+fn summarize(
+    scheme: &str,
+    method: &str,
+    mean_pattern_len: usize,
+    mut nanos: Vec<u64>,
+) -> QueryTiming {
+    nanos.sort_unstable();
+    let sum: u128 = nanos.iter().map(|&x| x as u128).sum();
+    QueryTiming {
+        scheme: scheme.to_owned(),
+        method: method.to_owned(),
+        samples: nanos.len(),
+        mean_pattern_len,
+        min_nanos: nanos[0],
+        median_nanos: nanos[nanos.len() / 2],
+        max_nanos: nanos[nanos.len() - 1],
+        mean_nanos: (sum / nanos.len() as u128) as u64,
+    }
+}
@@ -0,0 +1,228 @@
+//! Rank-select bit vector, which backs the compressed LCP array and is
+//! exposed for the succinct structures built on top of the suffix array.
+//!
+//! ```rust
+//! use suffix_array::bitvec::RsBitVec;
+//!
+//! let bv = RsBitVec::from_ones(10, [1, 4, 5, 9]);
+//! assert_eq!(bv.rank1(5), 2);
+//! assert_eq!(bv.select1(2), Some(5));
+//! assert_eq!(bv.select0(2), Some(3));
+//! ```
+
+use super::warm::touch;
+
+/// Number of bits between the rank samples.
+const BLOCK_BITS: usize = 512;
+
+/// Number of words between the rank samples.
+const BLOCK_WORDS: usize = BLOCK_BITS / 64;
+
+/// Immutable bit vector supporting rank in O(1) time and select in
+/// O(log n) time, with an overhead of 1/16 bits per bit.
+///
+/// The number of one bits is limited to `u32::MAX`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct RsBitVec {
+    words: Vec<u64>,
+    len: usize,
+    // number of one bits before each block.
+    ranks: Vec<u32>,
+}
+
+impl RsBitVec {
+    /// Build a bit vector of `len` bits from the little-endian words, where
+    /// the bits beyond `len` are ignored and missing words are zero.
+    pub fn from_words(mut words: Vec<u64>, len: usize) -> Self {
+        words.resize(len.div_ceil(64), 0);
+        let mut bv = RsBitVec {
+            words,
+            len,
+            ranks: Vec::new(),
+        };
+        bv.index();
+        bv
+    }
+
+    /// Build a bit vector of `len` bits with the given bits set, where
+    /// every position should be less than `len`.
+    pub fn from_ones<I>(len: usize, ones: I) -> Self
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let mut bv = RsBitVec::default();
+        bv.assign(len, ones);
+        bv
+    }
+
+    /// Refill the bit vector with the given bits set, reusing the
+    /// allocations.
+    pub(crate) fn assign<I>(&mut self, len: usize, ones: I)
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let n = len.div_ceil(64);
+        self.words.clear();
+        self.words.reserve_exact(n);
+        self.words.resize(n, 0);
+        self.len = len;
+        for i in ones {
+            assert!(i < len, "bit position out of range");
+            self.words[i / 64] |= 1 << (i % 64);
+        }
+        self.index();
+    }
+
+    /// Clear the bits beyond the length and sample the ranks.
+    fn index(&mut self) {
+        let tail = self.len % 64;
+        if tail > 0 {
+            let last = self.words.len() - 1;
+            self.words[last] &= (1 << tail) - 1;
+        }
+        self.ranks.clear();
+        self.ranks
+            .reserve_exact(self.words.len().div_ceil(BLOCK_WORDS));
+        let mut count = 0u32;
+        for block in self.words.chunks(BLOCK_WORDS) {
+            self.ranks.push(count);
+            let ones: u32 = block.iter().map(|w| w.count_ones()).sum();
+            count = count.checked_add(ones).expect("too many one bits");
+        }
+    }
+
+    /// Number of bits.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Test if there is no bit.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the bit at position `i`, or `None` if out of range.
+    pub fn get(&self, i: usize) -> Option<bool> {
+        if i < self.len {
+            Some(self.words[i / 64] >> (i % 64) & 1 == 1)
+        } else {
+            None
+        }
+    }
+
+    /// Number of one bits.
+    pub fn count_ones(&self) -> usize {
+        self.rank1(self.len)
+    }
+
+    /// Number of zero bits.
+    pub fn count_zeros(&self) -> usize {
+        self.len - self.count_ones()
+    }
+
+    /// Number of one bits before position `i`, which is at most the length.
+    pub fn rank1(&self, i: usize) -> usize {
+        assert!(i <= self.len, "bit position out of range");
+        let (w, b) = (i / 64, i % 64);
+        let block = w / BLOCK_WORDS;
+        let mut count = match self.ranks.get(block) {
+            Some(&r) => r as usize,
+            None => return self.total(),
+        };
+        for &word in &self.words[block * BLOCK_WORDS..w] {
+            count += word.count_ones() as usize;
+        }
+        if b != 0 {
+            count += (self.words[w] & ((1 << b) - 1)).count_ones() as usize;
+        }
+        count
+    }
+
+    /// Number of zero bits before position `i`, which is at most the
+    /// length.
+    pub fn rank0(&self, i: usize) -> usize {
+        i - self.rank1(i)
+    }
+
+    /// Find the position of the k-th (from 0) one bit.
+    pub fn select1(&self, k: usize) -> Option<usize> {
+        if k >= self.total() {
+            return None;
+        }
+        let block = self.ranks.partition_point(|&r| r as usize <= k) - 1;
+        let rest = k - self.ranks[block] as usize;
+        Some(self.select_in(block, rest, |w| w))
+    }
+
+    /// Find the position of the k-th (from 0) zero bit.
+    pub fn select0(&self, k: usize) -> Option<usize> {
+        if k >= self.len - self.total() {
+            return None;
+        }
+        let zeros = |b: usize| b * BLOCK_BITS - self.ranks[b] as usize;
+        let block = {
+            let (mut lo, mut hi) = (0, self.ranks.len());
+            while hi - lo > 1 {
+                let mid = lo + (hi - lo) / 2;
+                if zeros(mid) <= k {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            lo
+        };
+        Some(self.select_in(block, k - zeros(block), |w| !w))
+    }
+
+    /// Heap memory taken by the bit vector, in bytes.
+    pub fn heap_size(&self) -> usize {
+        self.words.capacity() * 8 + self.ranks.capacity() * 4
+    }
+
+    /// Touch every page of the bit vector, returning the number of pages.
+    pub(crate) fn warm(&self) -> usize {
+        touch(&self.words) + touch(&self.ranks)
+    }
+
+    /// Take out the little-endian words.
+    pub fn into_words(self) -> Vec<u64> {
+        self.words
+    }
+
+    /// Number of one bits, counted by the last rank sample.
+    fn total(&self) -> usize {
+        match self.ranks.last() {
+            Some(&r) => {
+                let block = (self.ranks.len() - 1) * BLOCK_WORDS;
+                let tail = &self.words[block..];
+                r as usize
+                    + tail
+                        .iter()
+                        .map(|w| w.count_ones() as usize)
+                        .sum::<usize>()
+            }
+            None => 0,
+        }
+    }
+
+    /// Find the `rest`-th one bit of the mapped words from the block.
+    fn select_in<F>(&self, block: usize, mut rest: usize, map: F) -> usize
+    where
+        F: Fn(u64) -> u64,
+    {
+        let start = block * BLOCK_WORDS;
+        for (j, &w) in self.words[start..].iter().enumerate() {
+            let mut w = map(w);
+            let ones = w.count_ones() as usize;
+            if rest < ones {
+                for _ in 0..rest {
+                    w &= w - 1;
+                }
+                return (start + j) * 64 + w.trailing_zeros() as usize;
+            }
+            rest -= ones;
+        }
+        unreachable!()
+    }
+}
@@ -0,0 +1,79 @@
+use super::utils::pack_prefix;
+
+/// Bits of the filter per indexed position.
+const BITS_PER_ITEM: usize = 10;
+
+/// Number of probed bits per item, the optimal one for 10 bits per item,
+/// giving about 1% false positives.
+const PROBES: u64 = 7;
+
+/// Bloom filter of all the `k`-byte sub-strings of a byte string, so that a
+/// pattern containing an absent one is rejected without binary search.
+#[derive(Debug, Clone)]
+pub struct PrefixFilter {
+    k: usize,
+    mask: u64,
+    bits: Vec<u64>,
+}
+
+impl PrefixFilter {
+    /// Build the filter of `k`-byte sub-strings, where `k` is 1 to 8.
+    pub fn new(s: &[u8], k: usize) -> Self {
+        let mut filter = PrefixFilter {
+            k,
+            mask: 0,
+            bits: Vec::new(),
+        };
+        filter.rebuild(s);
+        filter
+    }
+
+    /// Length of the filtered sub-strings.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Build the filter of another byte string, reusing the allocation.
+    pub fn rebuild(&mut self, s: &[u8]) {
+        let items = (s.len() + 1).saturating_sub(self.k);
+        let len = Ord::max(items * BITS_PER_ITEM, 64).next_power_of_two();
+        self.mask = len as u64 - 1;
+        self.bits.clear();
+        self.bits.resize(len / 64, 0);
+        for w in s.windows(self.k) {
+            let (h1, h2) = self.hashes(w);
+            for i in 0..PROBES {
+                let bit = h1.wrapping_add(i.wrapping_mul(h2)) & self.mask;
+                self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+            }
+        }
+    }
+
+    /// Test if every `k`-byte sub-string of the pattern may occur, which is
+    /// always true for the patterns shorter than `k`.
+    pub fn may_contain(&self, pat: &[u8]) -> bool {
+        pat.windows(self.k).all(|w| {
+            let (h1, h2) = self.hashes(w);
+            (0..PROBES).all(|i| {
+                let bit = h1.wrapping_add(i.wrapping_mul(h2)) & self.mask;
+                self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0
+            })
+        })
+    }
+
+    /// Heap memory taken by the filter, in bytes.
+    pub fn heap_size(&self) -> usize {
+        self.bits.capacity() * 8
+    }
+
+    /// Two independent hashes of the sub-string for double hashing, the
+    /// second one odd so that the probes are distinct.
+    #[inline]
+    fn hashes(&self, w: &[u8]) -> (u64, u64) {
+        let mut z = pack_prefix(w, self.k).0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z, (z >> 32) | 1)
+    }
+}
@@ -0,0 +1,263 @@
+use std::ops::Range;
+
+use super::warm::touch;
+
+/// Maximum number of leading bytes the bucket pointers could be indexed by.
+pub const MAX_BUCKET_DEPTH: usize = 3;
+
+/// Minimum length of byte string to be counted in parallel.
+#[cfg(feature = "rayon")]
+const PARALLEL_COUNT_LENGTH: usize = 1 << 16;
+
+/// Maximum size of histogram to be counted in parallel, as each thread
+/// keeps a copy.
+#[cfg(feature = "rayon")]
+const PARALLEL_COUNT_SIZE: usize = 1 << 17;
+
+/// Bucket pointers indexed by the leading bytes of suffixes.
+///
+/// The first byte is keyed in base 257, where $ is 0 and byte c is c + 1,
+/// so that the keys are in the suffix order. The buckets are split into two
+/// levels by the first byte, and the rows of absent first bytes are never
+/// allocated. The remaining bytes are keyed densely by the digits present
+/// in the byte string, so that the rows are small for small alphabets, e.g.
+/// 25 buckets per row rather than 66049 for DNA at depth 3.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "pack", derive(serde::Serialize, serde::Deserialize))]
+pub struct Buckets {
+    depth: usize,
+    // left boundaries of the first digits, and the total.
+    top: Vec<u32>,
+    // left boundaries of the remaining digits for each first digit, and the
+    // total, or empty if absent.
+    rows: Vec<Vec<u32>>,
+    // number of present digits before each digit, and the total, i.e. the
+    // dense digit of the present ones.
+    dense: Vec<u16>,
+}
+
+impl Buckets {
+    /// Count the suffixes of byte string by their leading `depth` bytes.
+    pub fn new(s: &[u8], depth: usize) -> Self {
+        assert!(depth > 0 && depth <= MAX_BUCKET_DEPTH);
+        let mut bkt = Buckets {
+            depth,
+            top: Vec::new(),
+            rows: Vec::new(),
+            dense: Vec::new(),
+        };
+        bkt.rebuild(s);
+        bkt
+    }
+
+    /// Recount the suffixes of another byte string at the same depth, reusing
+    /// the allocations of the rows still present.
+    pub fn rebuild(&mut self, s: &[u8]) {
+        let depth = self.depth;
+
+        // the empty suffix is the only one with first digit 0.
+        let mut counts = histogram(s.len(), 257, |i| s[i] as usize + 1);
+        counts[0] = 1;
+        left_boundaries(&counts[..], &mut self.top);
+
+        self.dense.clear();
+        self.dense.reserve_exact(258);
+        self.dense.push(0);
+        let mut radix = 0;
+        for &x in counts.iter() {
+            radix += (x > 0) as u16;
+            self.dense.push(radix);
+        }
+
+        self.rows.resize_with(257, Vec::new);
+        if depth == 1 {
+            self.rows.iter_mut().for_each(|row| *row = Vec::new());
+            return;
+        }
+
+        // map the present first digits to compact rows.
+        let width = (radix as usize).pow(depth as u32 - 1);
+        let mut compact = vec![usize::MAX; 257];
+        let mut present = 0;
+        for d in 1..257 {
+            if counts[d] > 0 {
+                compact[d] = present;
+                present += 1;
+            }
+        }
+
+        let dense = &self.dense[..];
+        let counts = histogram(s.len(), present * width, |i| {
+            let row = compact[s[i] as usize + 1];
+            row * width + bucket_key(dense, &s[i + 1..], depth - 1).0
+        });
+        for (d, bounds) in self.rows.iter_mut().enumerate() {
+            if compact[d] != usize::MAX {
+                let row = &counts[compact[d] * width..][..width];
+                let left = self.top[d];
+                left_boundaries(row, bounds);
+                bounds.iter_mut().for_each(|x| *x += left);
+            } else {
+                *bounds = Vec::new();
+            }
+        }
+    }
+
+    /// Test if the loaded buckets are well-formed for a suffix array of
+    /// given length, so that looking up never panics.
+    #[cfg(feature = "pack")]
+    pub fn check_shape(&self, sa_len: usize) -> bool {
+        let sorted = |xs: &[u32]| xs.windows(2).all(|w| w[0] <= w[1]);
+        if self.depth == 0 || self.depth > MAX_BUCKET_DEPTH {
+            return false;
+        }
+        if self.top.len() != 258
+            || self.top[0] != 0
+            || self.top[257] as usize != sa_len
+            || !sorted(&self.top[..])
+            || self.rows.len() != 257
+            || !self.rows[0].is_empty()
+        {
+            return false;
+        }
+
+        // the digit $ is always present.
+        let dense = &self.dense[..];
+        if dense.len() != 258
+            || dense[0] != 0
+            || dense[1] != 1
+            || dense.windows(2).any(|w| w[1] < w[0] || w[1] - w[0] > 1)
+        {
+            return false;
+        }
+
+        let width = (dense[257] as usize).pow(self.depth as u32 - 1);
+        (1..257).all(|d| {
+            let row = &self.rows[d];
+            row.is_empty()
+                || (self.depth > 1
+                    && row.len() == width + 1
+                    && row[0] == self.top[d]
+                    && row[width] == self.top[d + 1]
+                    && sorted(&row[..]))
+        })
+    }
+
+    /// Heap memory taken by the buckets, in bytes.
+    pub fn heap_size(&self) -> usize {
+        let row_size = std::mem::size_of::<Vec<u32>>();
+        let rows: usize = self.rows.iter().map(|row| row.capacity() * 4).sum();
+        self.top.capacity() * 4
+            + self.rows.capacity() * row_size
+            + rows
+            + self.dense.capacity() * 2
+    }
+
+    /// Touch every page of the buckets, returning the number of pages.
+    pub fn warm(&self) -> usize {
+        let rows: usize = self.rows.iter().map(|row| touch(row)).sum();
+        touch(&self.top) + rows + touch(&self.dense)
+    }
+
+    /// Number of leading bytes the buckets are indexed by.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Get the range of buckets of suffixes starting with the leading bytes
+    /// of non-empty pattern, up to the depth.
+    #[inline]
+    pub fn range(&self, pat: &[u8]) -> Range<usize> {
+        let d = pat[0] as usize + 1;
+        let row = &self.rows[d];
+        if pat.len() == 1 || row.is_empty() {
+            // empty if the first byte is absent.
+            return self.top[d] as usize..self.top[d + 1] as usize;
+        }
+
+        // patterns shorter than the depth cover a range of buckets, and those
+        // with absent bytes cover none.
+        let len = Ord::min(pat.len(), self.depth);
+        let dense = &self.dense[..];
+        let (key, exact) = bucket_key(dense, &pat[1..len], self.depth - 1);
+        if !exact {
+            return row[key] as usize..row[key] as usize;
+        }
+        let radix = dense[257] as usize;
+        let span = radix.pow((self.depth - len) as u32);
+        row[key] as usize..row[key + span] as usize
+    }
+}
+
+/// Key of the bucket of the leading `depth` bytes of a suffix in the dense
+/// digits, and whether the bytes are all present.
+///
+/// If not, the key is of the first bucket after the bytes, the one they
+/// would be inserted before.
+#[inline]
+fn bucket_key(dense: &[u16], suffix: &[u8], depth: usize) -> (usize, bool) {
+    let radix = dense[257] as usize;
+    let mut key = 0;
+    let mut exact = true;
+    for i in 0..depth {
+        let digit = match suffix.get(i) {
+            Some(&c) if exact => {
+                let d = c as usize + 1;
+                exact = dense[d + 1] > dense[d];
+                dense[d] as usize
+            }
+            _ => 0,
+        };
+        key = key * radix + digit;
+    }
+    (key, exact)
+}
+
+/// Convert counts to the left boundaries, followed by the total.
+fn left_boundaries(counts: &[u32], bounds: &mut Vec<u32>) {
+    bounds.clear();
+    bounds.reserve_exact(counts.len() + 1);
+    let mut sum = 0;
+    bounds.push(0);
+    for &x in counts {
+        sum += x;
+        bounds.push(sum);
+    }
+}
+
+/// Count the keys of `0..n` into a histogram.
+fn histogram<F>(n: usize, size: usize, key: F) -> Vec<u32>
+where
+    F: Fn(usize) -> usize + Sync,
+{
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+
+        if n >= PARALLEL_COUNT_LENGTH && size <= PARALLEL_COUNT_SIZE {
+            // per-thread histograms merged.
+            return (0..n)
+                .into_par_iter()
+                .fold(
+                    || vec![0u32; size],
+                    |mut counts, i| {
+                        counts[key(i)] += 1;
+                        counts
+                    },
+                )
+                .reduce(
+                    || vec![0u32; size],
+                    |mut xs, ys| {
+                        xs.iter_mut().zip(ys).for_each(|(x, y)| *x += y);
+                        xs
+                    },
+                );
+        }
+    }
+
+    let mut counts = vec![0u32; size];
+    for i in 0..n {
+        counts[key(i)] += 1;
+    }
+    counts
+}
@@ -0,0 +1,77 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Shared slot holding the current index, which could be swapped for a
+/// newly built one while the readers keep searching the old one.
+///
+/// Readers take a snapshot by `load`, which is only a reference count
+/// increment under a briefly held lock, and the old index is dropped once
+/// the last snapshot is gone. Since the snapshots are plain `Arc`s, the
+/// cell could be replaced by `arc_swap::ArcSwap<SuffixArray<_>>` for lock
+/// free reads without changing the readers.
+///
+/// ```rust
+/// use suffix_array::{IndexCell, SuffixArray};
+///
+/// let cell = IndexCell::new(SuffixArray::new(b"old".to_vec()));
+/// let snapshot = cell.load();
+/// cell.store(SuffixArray::new(b"new".to_vec()));
+/// assert!(snapshot.contains(b"old"));
+/// assert!(cell.load().contains(b"new"));
+/// ```
+#[derive(Debug, Default)]
+pub struct IndexCell<T> {
+    current: RwLock<Arc<T>>,
+    generation: AtomicU64,
+}
+
+impl<T> IndexCell<T> {
+    /// Create a cell holding the index.
+    pub fn new(index: T) -> Self {
+        IndexCell::from_arc(Arc::new(index))
+    }
+
+    /// Create a cell holding the shared index.
+    pub fn from_arc(index: Arc<T>) -> Self {
+        IndexCell {
+            current: RwLock::new(index),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    /// Take a snapshot of the current index.
+    pub fn load(&self) -> Arc<T> {
+        let current = self.current.read().unwrap_or_else(|e| e.into_inner());
+        Arc::clone(&current)
+    }
+
+    /// Swap in a new index, returning the old one.
+    pub fn store(&self, index: T) -> Arc<T> {
+        self.swap(Arc::new(index))
+    }
+
+    /// Swap in a new shared index, returning the old one.
+    pub fn swap(&self, index: Arc<T>) -> Arc<T> {
+        let mut current =
+            self.current.write().unwrap_or_else(|e| e.into_inner());
+        self.generation.fetch_add(1, Ordering::Release);
+        std::mem::replace(&mut current, index)
+    }
+
+    /// Number of swaps so far, so that the readers could tell if their
+    /// snapshots are stale.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Take out the current index.
+    pub fn into_inner(self) -> Arc<T> {
+        self.current.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<T> From<T> for IndexCell<T> {
+    fn from(index: T) -> Self {
+        IndexCell::new(index)
+    }
+}
@@ -0,0 +1,101 @@
+use std::ops::Range;
+
+use super::sa::SuffixArray;
+
+/// Suffix array for byte string under a user-supplied total order on bytes.
+///
+/// The order is given by a 256-entry permutation table, where `table[c]` is
+/// the rank of byte `c`. The byte string is stored translated through the
+/// table, and the patterns are translated the same way before searching, so
+/// the suffixes are ordered and searched consistently by the collation.
+#[derive(Clone)]
+pub struct CollatedSuffixArray {
+    table: [u8; 256],
+    inverse: [u8; 256],
+    sa: SuffixArray<Vec<u8>>,
+}
+
+impl CollatedSuffixArray {
+    /// Construct new suffix array for given byte string, ordering the bytes
+    /// by the permutation table.
+    pub fn new(s: &[u8], table: [u8; 256]) -> Self {
+        let mut inverse = [0; 256];
+        let mut seen = [false; 256];
+        for (c, &r) in table.iter().enumerate() {
+            assert!(!seen[r as usize], "collation table is not a permutation");
+            seen[r as usize] = true;
+            inverse[r as usize] = c as u8;
+        }
+
+        let collated = s.iter().map(|&c| table[c as usize]).collect();
+        CollatedSuffixArray {
+            table,
+            inverse,
+            sa: SuffixArray::new(collated),
+        }
+    }
+
+    /// Construct new suffix array ordering ASCII letters case-insensitively
+    /// first, and then upper case before lower case, i.e. `A < a < B < b`.
+    pub fn ascii_case_insensitive(s: &[u8]) -> Self {
+        let mut order: Vec<u8> = (0..=255).collect();
+        order.sort_by_key(|&c| (c.to_ascii_lowercase(), c));
+        let mut table = [0; 256];
+        for (r, &c) in order.iter().enumerate() {
+            table[c as usize] = r as u8;
+        }
+        CollatedSuffixArray::new(s, table)
+    }
+
+    /// The permutation table.
+    pub fn table(&self) -> &[u8; 256] {
+        &self.table
+    }
+
+    /// Length of the underlying byte string.
+    pub fn len(&self) -> usize {
+        self.sa.len()
+    }
+
+    /// Test if the underlying byte string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.sa.is_empty()
+    }
+
+    /// Translate the bytes through the permutation table.
+    pub fn collate(&self, s: &[u8]) -> Vec<u8> {
+        s.iter().map(|&c| self.table[c as usize]).collect()
+    }
+
+    /// Recover the original underlying byte string.
+    pub fn text(&self) -> Vec<u8> {
+        self.sa
+            .text()
+            .iter()
+            .map(|&c| self.inverse[c as usize])
+            .collect()
+    }
+
+    /// The suffix array over the translated byte string, for the queries
+    /// not provided here. Patterns must be translated by `collate` first.
+    pub fn suffix_array(&self) -> &SuffixArray<Vec<u8>> {
+        &self.sa
+    }
+
+    /// Test if contains given pattern.
+    pub fn contains(&self, pat: &[u8]) -> bool {
+        self.sa.contains(&self.collate(pat))
+    }
+
+    /// Search for all the occurrences of given pattern, ordered by the
+    /// collation of the suffixes they start.
+    pub fn search_all(&self, pat: &[u8]) -> &[u32] {
+        self.sa.search_all(&self.collate(pat))
+    }
+
+    /// Search for a sub-string that has the longest common prefix of the
+    /// given pattern.
+    pub fn search_lcp(&self, pat: &[u8]) -> Range<usize> {
+        self.sa.search_lcp(&self.collate(pat))
+    }
+}
@@ -0,0 +1,209 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::normalize::{Normalization, NormalizedText, OffsetMap};
+use super::sa::SuffixArray;
+
+/// Transform applied to the byte string before indexing, and to the
+/// patterns before searching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transform {
+    /// The byte string as is.
+    Raw,
+    /// ASCII letters mapped to lower case.
+    AsciiLowercase,
+    /// Normalized by the options, see `Normalization`.
+    Normalize(Normalization),
+}
+
+impl Transform {
+    /// Transform a byte string, e.g. a pattern to be searched.
+    pub fn apply(&self, s: &[u8]) -> Vec<u8> {
+        match *self {
+            Transform::Raw => s.to_vec(),
+            Transform::AsciiLowercase => s.to_ascii_lowercase(),
+            Transform::Normalize(options) => options.apply(s),
+        }
+    }
+}
+
+/// Several suffix arrays over the transforms of one byte string, e.g. the
+/// raw, lower cased and normalized ones, selected by name for each query.
+///
+/// The transformed byte strings are shared with each other whenever they
+/// are identical, e.g. the lower cased text of a text without upper case
+/// letters is the raw text itself, and so are their suffix arrays. The
+/// occurrences are reported as the ranges of the original byte string.
+///
+/// ```rust
+/// use suffix_array::{CorpusSet, Transform};
+///
+/// let mut corpus = CorpusSet::new(&b"Splendid splendor"[..]);
+/// corpus.add_index("raw", Transform::Raw);
+/// corpus.add_index("lower", Transform::AsciiLowercase);
+/// assert_eq!(corpus.search_all("raw", b"splend"), Some(vec![9..15]));
+/// assert_eq!(corpus.search_all("lower", b"SPLEND"), Some(vec![0..6, 9..15]));
+/// ```
+pub struct CorpusSet {
+    text: Arc<[u8]>,
+    indexes: Vec<Layer>,
+}
+
+struct Layer {
+    name: String,
+    transform: Transform,
+    text: Arc<[u8]>,
+    offsets: Option<OffsetMap>,
+    sa: Arc<SuffixArray<Arc<[u8]>>>,
+}
+
+impl CorpusSet {
+    /// Create a corpus of the byte string without any index.
+    pub fn new<S: Into<Arc<[u8]>>>(s: S) -> Self {
+        CorpusSet {
+            text: s.into(),
+            indexes: Vec::new(),
+        }
+    }
+
+    /// The original byte string.
+    pub fn text(&self) -> &[u8] {
+        &self.text[..]
+    }
+
+    /// Construct the suffix array of the transformed byte string, named for
+    /// the queries, replacing the index of the same name if any.
+    pub fn add_index(&mut self, name: &str, transform: Transform) {
+        self.remove_index(name);
+
+        let (text, offsets) = match transform {
+            Transform::Raw => (self.text.to_vec(), None),
+            Transform::AsciiLowercase => (self.text.to_ascii_lowercase(), None),
+            Transform::Normalize(options) => {
+                let (text, offsets) =
+                    NormalizedText::new(&self.text, options).into_parts();
+                (text, Some(offsets))
+            }
+        };
+        let text = self.share(text);
+
+        let same = self.indexes.iter().find(|l| Arc::ptr_eq(&l.text, &text));
+        let sa = match same {
+            // the suffix array of the very same byte string.
+            Some(layer) => layer.sa.clone(),
+            None => Arc::new(SuffixArray::new(text.clone())),
+        };
+        self.indexes.push(Layer {
+            name: name.to_string(),
+            transform,
+            text,
+            offsets,
+            sa,
+        });
+    }
+
+    /// Drop the named index, returning whether it exists.
+    pub fn remove_index(&mut self, name: &str) -> bool {
+        let before = self.indexes.len();
+        self.indexes.retain(|layer| layer.name != name);
+        self.indexes.len() != before
+    }
+
+    /// Names of the indexes, in the order they are added.
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.indexes.iter().map(|layer| &layer.name[..])
+    }
+
+    /// The transform of the named index.
+    pub fn transform(&self, name: &str) -> Option<Transform> {
+        self.layer(name).map(|layer| layer.transform)
+    }
+
+    /// The suffix array of the named index, over the transformed byte
+    /// string. Patterns must be transformed by `Transform::apply` first.
+    pub fn index(&self, name: &str) -> Option<&SuffixArray<Arc<[u8]>>> {
+        self.layer(name).map(|layer| &*layer.sa)
+    }
+
+    /// Test if the named index contains given pattern, after transforming
+    /// the pattern. Returns `None` if there is no such index.
+    pub fn contains(&self, name: &str, pat: &[u8]) -> Option<bool> {
+        let layer = self.layer(name)?;
+        Some(layer.sa.contains(&layer.transform.apply(pat)))
+    }
+
+    /// Search the named index for all the occurrences of given pattern,
+    /// after transforming the pattern, as the sorted ranges of the original
+    /// byte string they come from. Returns `None` if there is no such index.
+    pub fn search_all(
+        &self,
+        name: &str,
+        pat: &[u8],
+    ) -> Option<Vec<Range<usize>>> {
+        let layer = self.layer(name)?;
+        let pat = layer.transform.apply(pat);
+        let mut positions = layer.sa.search_all(&pat).to_vec();
+        positions.sort_unstable();
+        let ranges = positions.into_iter().map(|p| {
+            let range = p as usize..p as usize + pat.len();
+            match layer.offsets {
+                Some(ref offsets) => offsets.original_range(range),
+                None => range,
+            }
+        });
+        Some(ranges.collect())
+    }
+
+    /// Number of distinct byte string buffers, including the original one.
+    pub fn buffer_count(&self) -> usize {
+        self.buffers().len()
+    }
+
+    /// Heap memory taken by the byte strings and by the indexes, each shared
+    /// one counted once, in bytes.
+    pub fn heap_size(&self) -> usize {
+        let texts: usize = self.buffers().iter().map(|b| b.len()).sum();
+        let offsets: usize = self
+            .indexes
+            .iter()
+            .filter_map(|layer| layer.offsets.as_ref())
+            .map(OffsetMap::heap_size)
+            .sum();
+        let mut indexes = 0;
+        for (i, layer) in self.indexes.iter().enumerate() {
+            let seen = &self.indexes[..i];
+            if seen.iter().all(|l| !Arc::ptr_eq(&l.sa, &layer.sa)) {
+                indexes += layer.sa.memory_usage().total();
+            }
+        }
+        texts + offsets + indexes
+    }
+
+    /// The distinct byte string buffers, starting with the original one.
+    fn buffers(&self) -> Vec<&Arc<[u8]>> {
+        let mut buffers = vec![&self.text];
+        for layer in self.indexes.iter() {
+            if buffers.iter().all(|b| !Arc::ptr_eq(b, &layer.text)) {
+                buffers.push(&layer.text);
+            }
+        }
+        buffers
+    }
+
+    fn layer(&self, name: &str) -> Option<&Layer> {
+        self.indexes.iter().find(|layer| layer.name == name)
+    }
+
+    /// Reuse the buffer identical to the transformed byte string if any.
+    fn share(&self, text: Vec<u8>) -> Arc<[u8]> {
+        if text[..] == self.text[..] {
+            return self.text.clone();
+        }
+        for layer in self.indexes.iter() {
+            if text[..] == layer.text[..] {
+                return layer.text.clone();
+            }
+        }
+        text.into()
+    }
+}
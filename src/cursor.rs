@@ -0,0 +1,182 @@
+use std::ops::Range;
+
+use super::sa::SuffixArray;
+use super::storage::TextStorage;
+
+/// Cursor over the suffix array, maintaining the range of suffixes prefixed
+/// by a pattern as it grows byte by byte, see `SuffixArray::cursor`.
+///
+/// Each extension narrows the range by binary searching inside it, and
+/// could be undone by `pop`, e.g. for search-as-you-type or a depth-first
+/// traversal over the sub-strings. The pattern could also be extended at
+/// the front, e.g. for bidirectional search.
+#[derive(Clone)]
+pub struct Cursor<'a, T> {
+    sa: &'a SuffixArray<T>,
+    pattern: Vec<u8>,
+    // range after each extension and whether it is at the front, the whole
+    // suffix array if none.
+    ranges: Vec<(Range<usize>, bool)>,
+}
+
+impl<'a, T: TextStorage> Cursor<'a, T> {
+    pub(crate) fn new(sa: &'a SuffixArray<T>) -> Self {
+        Cursor {
+            sa,
+            pattern: Vec::new(),
+            ranges: Vec::new(),
+        }
+    }
+
+    /// Append a byte to the pattern, returning whether it still occurs.
+    pub fn extend(&mut self, c: u8) -> bool {
+        let range = self.range();
+        let range = self.sa.refine(range, self.pattern.len(), c);
+        self.pattern.push(c);
+        self.ranges.push((range, false));
+        self.count() > 0
+    }
+
+    /// Prepend a byte to the pattern, returning whether it still occurs.
+    ///
+    /// The suffixes starting with `c` are ordered by the ranks of the
+    /// suffixes following them, like the LF-mapping, so the new range is
+    /// binary searched by those ranks. The inverse suffix array is computed
+    /// on first use, see `SuffixArray::rank_of`.
+    pub fn extend_front(&mut self, c: u8) -> bool {
+        let range = self.range();
+        let bucket = self.sa.refine(0..self.sa.len() + 1, 0, c);
+        let sa = self.sa.suffixes(bucket.clone());
+        let next_rank = |&p: &u32| {
+            // the suffix following a non-empty one always exists.
+            self.sa.rank_of(p as usize + 1).unwrap() as usize
+        };
+        let i = sa.partition_point(|p| next_rank(p) < range.start);
+        let j = i + sa[i..].partition_point(|p| next_rank(p) < range.end);
+        self.pattern.insert(0, c);
+        self.ranges.push((bucket.start + i..bucket.start + j, true));
+        self.count() > 0
+    }
+
+    /// Remove the byte of the last extension from the pattern, undoing it.
+    pub fn pop(&mut self) -> Option<u8> {
+        let (_, front) = self.ranges.pop()?;
+        if front {
+            Some(self.pattern.remove(0))
+        } else {
+            self.pattern.pop()
+        }
+    }
+
+    /// The current pattern.
+    pub fn pattern(&self) -> &[u8] {
+        &self.pattern[..]
+    }
+
+    /// The ranks of the suffixes prefixed by the pattern.
+    pub fn range(&self) -> Range<usize> {
+        match self.ranges.last() {
+            Some((range, _)) => range.clone(),
+            None => 0..self.sa.len() + 1,
+        }
+    }
+
+    /// Number of the occurrences of the pattern.
+    pub fn count(&self) -> usize {
+        self.range().len()
+    }
+
+    /// All the unsorted occurrences of the pattern, like `search_all`.
+    pub fn positions(&self) -> &'a [u32] {
+        self.sa.suffixes(self.range())
+    }
+}
+
+/// Cursor over the suffix arrays of a byte string and of its reverse,
+/// extending the pattern at either end, see `SuffixArray::bi_cursor`.
+///
+/// The pattern is appended by narrowing the range of suffixes prefixed by
+/// it, and prepended by narrowing the range of reversed suffixes prefixed
+/// by its reverse. The range of the other direction is searched again only
+/// when the direction changes, without the inverse suffix array.
+#[derive(Clone)]
+pub struct BiCursor<'a, T> {
+    sa: &'a SuffixArray<T>,
+    rev: &'a SuffixArray<Vec<u8>>,
+    pattern: Vec<u8>,
+    // ranges of the suffixes prefixed by the pattern, and of the reversed
+    // suffixes prefixed by its reverse, at least one of which is known.
+    forward: Option<Range<usize>>,
+    backward: Option<Range<usize>>,
+}
+
+impl<'a, T: TextStorage> BiCursor<'a, T> {
+    pub(crate) fn new(
+        sa: &'a SuffixArray<T>,
+        rev: &'a SuffixArray<Vec<u8>>,
+    ) -> Self {
+        BiCursor {
+            sa,
+            rev,
+            pattern: Vec::new(),
+            forward: Some(0..sa.len() + 1),
+            backward: Some(0..rev.len() + 1),
+        }
+    }
+
+    /// Append a byte to the pattern, returning whether it still occurs.
+    pub fn extend(&mut self, c: u8) -> bool {
+        let range = match self.forward.take() {
+            Some(range) => range,
+            None => self.sa.search_full(&self.pattern[..]).sa_range,
+        };
+        let range = self.sa.refine(range, self.pattern.len(), c);
+        self.pattern.push(c);
+        self.forward = Some(range);
+        self.backward = None;
+        self.count() > 0
+    }
+
+    /// Prepend a byte to the pattern, returning whether it still occurs.
+    pub fn extend_front(&mut self, c: u8) -> bool {
+        let range = match self.backward.take() {
+            Some(range) => range,
+            None => {
+                let rev_pat: Vec<u8> =
+                    self.pattern.iter().rev().cloned().collect();
+                self.rev.search_full(&rev_pat[..]).sa_range
+            }
+        };
+        let range = self.rev.refine(range, self.pattern.len(), c);
+        self.pattern.insert(0, c);
+        self.backward = Some(range);
+        self.forward = None;
+        self.count() > 0
+    }
+
+    /// The current pattern.
+    pub fn pattern(&self) -> &[u8] {
+        &self.pattern[..]
+    }
+
+    /// Number of the occurrences of the pattern.
+    pub fn count(&self) -> usize {
+        match (&self.forward, &self.backward) {
+            (Some(range), _) | (None, Some(range)) => range.len(),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    /// All the unsorted occurrences of the pattern, mapped back from the
+    /// reversed byte string if the pattern is last extended at the front.
+    pub fn positions(&self) -> Vec<u32> {
+        if let Some(ref range) = self.forward {
+            return self.sa.suffixes(range.clone()).to_vec();
+        }
+        let n = self.sa.len();
+        let m = self.pattern.len();
+        let range = self.backward.clone().unwrap();
+        let rev = self.rev.suffixes(range).iter();
+        rev.map(|&p| (n - p as usize - m) as u32).collect()
+    }
+}
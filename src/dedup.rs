@@ -0,0 +1,71 @@
+use super::utils::lcp;
+
+/// Region of the byte string repeating an earlier one, see
+/// `SuffixArray::dedup_report`.
+///
+/// The bytes `s[offset..offset + len]` equal `s[source..source + len]`,
+/// where `source < offset`, and the two could overlap as in LZ77.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "pack", derive(serde::Serialize, serde::Deserialize))]
+pub struct DupRegion {
+    /// Start of the earlier occurrence.
+    pub source: u32,
+    /// Start of the repeated region.
+    pub offset: u32,
+    /// Length of the region.
+    pub len: u32,
+}
+
+/// No previous or next smaller position.
+const NONE: u32 = u32::MAX;
+
+/// List the factors of the LZ77 factorization that are at least `min_len`
+/// (at least 1) long, in text order.
+///
+/// The longest previous factor at each factor start is the longer common
+/// prefix with the nearest suffixes of smaller positions on both sides in
+/// the suffix array, taking O(n) time in total since the factors are
+/// skipped after being compared.
+pub fn dedup_regions(s: &[u8], sa: &[u32], min_len: usize) -> Vec<DupRegion> {
+    let n = s.len();
+    let min_len = Ord::max(min_len, 1);
+    let mut regions = Vec::new();
+
+    // nearest smaller positions before and after each suffix in rank order.
+    let mut psv = vec![NONE; n + 1];
+    let mut nsv = vec![NONE; n + 1];
+    let mut stack: Vec<u32> = Vec::new();
+    for &p in sa.iter() {
+        while let Some(&top) = stack.last() {
+            if top < p {
+                break;
+            }
+            nsv[top as usize] = p;
+            stack.pop();
+        }
+        psv[p as usize] = stack.last().cloned().unwrap_or(NONE);
+        stack.push(p);
+    }
+
+    let lce = |i: usize, j: u32| {
+        if j == NONE {
+            return 0;
+        }
+        lcp(&s[i..], &s[j as usize..])
+    };
+
+    let mut i = 0;
+    while i < n {
+        let (a, b) = (lce(i, psv[i]), lce(i, nsv[i]));
+        let (source, len) = if a >= b { (psv[i], a) } else { (nsv[i], b) };
+        if len >= min_len {
+            regions.push(DupRegion {
+                source,
+                offset: i as u32,
+                len: len as u32,
+            });
+        }
+        i += Ord::max(len, 1);
+    }
+    regions
+}
@@ -0,0 +1,168 @@
+use std::cmp::Ordering;
+
+use super::saca::MAX_LENGTH;
+use super::sais::{sais_symbols, Symbols};
+
+/// DNA nucleotides `A`, `C`, `G` and `T`, in the order of their symbols.
+const NUCLEOTIDES: [u8; 4] = *b"ACGT";
+
+/// Get the 2-bit symbol of DNA nucleotide, case insensitive.
+pub fn dna_symbol(c: u8) -> Option<u8> {
+    NUCLEOTIDES
+        .iter()
+        .position(|&x| x == c.to_ascii_uppercase())
+        .map(|i| i as u8)
+}
+
+/// String of 2-bit symbols packed 4 per byte, e.g. DNA nucleotides, taking a
+/// quarter of the memory of a byte string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Encoded2Bit {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl Encoded2Bit {
+    /// Pack the symbols, returning `None` if any of them is not less than 4.
+    pub fn from_symbols(symbols: &[u8]) -> Option<Self> {
+        let mut bytes = vec![0; symbols.len().div_ceil(4)];
+        for (i, &c) in symbols.iter().enumerate() {
+            if c >= 4 {
+                return None;
+            }
+            bytes[i / 4] |= c << shift(i);
+        }
+        Some(Encoded2Bit {
+            bytes,
+            len: symbols.len(),
+        })
+    }
+
+    /// Pack the DNA nucleotides, returning `None` if there is any byte other
+    /// than `ACGTacgt`.
+    pub fn from_dna(s: &[u8]) -> Option<Self> {
+        let symbols: Option<Vec<u8>> =
+            s.iter().map(|&c| dna_symbol(c)).collect();
+        Self::from_symbols(&symbols?[..])
+    }
+
+    /// Number of the symbols.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Test if there is no symbol.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the `i`-th symbol.
+    pub fn get(&self, i: usize) -> Option<u8> {
+        if i < self.len {
+            Some(self.bytes[i / 4] >> shift(i) & 3)
+        } else {
+            None
+        }
+    }
+
+    /// The packed bytes, the first symbol in the highest 2 bits of the first
+    /// byte.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..]
+    }
+
+    /// Unpack the DNA nucleotides in upper case.
+    pub fn to_dna(&self) -> Vec<u8> {
+        (0..self.len)
+            .map(|i| NUCLEOTIDES[self.at(i) as usize])
+            .collect()
+    }
+}
+
+impl Symbols for Encoded2Bit {
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn at(&self, i: usize) -> u32 {
+        (self.bytes[i / 4] >> shift(i) & 3) as u32
+    }
+}
+
+#[inline]
+fn shift(i: usize) -> usize {
+    6 - 2 * (i % 4)
+}
+
+/// Suffix array over the packed 2-bit symbols, constructed and searched
+/// without unpacking them, reporting the positions of symbols.
+#[derive(Debug, Clone)]
+pub struct Encoded2BitSuffixArray {
+    text: Encoded2Bit,
+    sa: Vec<u32>,
+}
+
+impl Encoded2BitSuffixArray {
+    /// Construct new suffix array for the packed symbols.
+    pub fn new(text: Encoded2Bit) -> Self {
+        assert!(text.len() <= MAX_LENGTH);
+
+        let mut sa = vec![0; text.len() + 1];
+        sais_symbols(&text, 4, &mut sa[..]);
+        Encoded2BitSuffixArray { text, sa }
+    }
+
+    /// Number of the symbols.
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    /// Test if there is no symbol.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Get the packed symbols.
+    pub fn text(&self) -> &Encoded2Bit {
+        &self.text
+    }
+
+    /// Take out the packed symbols and the suffix array.
+    pub fn into_parts(self) -> (Encoded2Bit, Vec<u32>) {
+        (self.text, self.sa)
+    }
+
+    /// Test if it contains the given pattern of symbols.
+    pub fn contains(&self, pat: &[u8]) -> bool {
+        !self.search_all(pat).is_empty()
+    }
+
+    /// Search for all the unsorted occurrences of given pattern of symbols
+    /// (can overlap).
+    pub fn search_all(&self, pat: &[u8]) -> &[u32] {
+        let i = self
+            .sa
+            .partition_point(|&p| self.cmp_prefix(p, pat) == Ordering::Less);
+        let j = i + self.sa[i..]
+            .partition_point(|&p| self.cmp_prefix(p, pat) == Ordering::Equal);
+        &self.sa[i..j]
+    }
+
+    /// Compare the suffix at `p`, truncated to the length of pattern, with
+    /// the pattern.
+    fn cmp_prefix(&self, p: u32, pat: &[u8]) -> Ordering {
+        let p = p as usize;
+        for (k, &c) in pat.iter().enumerate() {
+            if p + k == self.text.len() {
+                return Ordering::Less;
+            }
+            match self.text.at(p + k).cmp(&(c as u32)) {
+                Ordering::Equal => continue,
+                ord => return ord,
+            }
+        }
+        Ordering::Equal
+    }
+}
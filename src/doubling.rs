@@ -0,0 +1,64 @@
+/// Construct suffix array of byte string using the Larsson-Sadakane prefix
+/// doubling algorithm.
+///
+/// Each pass sorts the unsorted groups by the ranks `h` bytes ahead, and
+/// splits them by the sorted ranks, so the suffixes in a group share at
+/// least `2h` bytes after the pass. It takes O(n log n) time for each pass
+/// and O(log n) passes at most, regardless of the input.
+pub fn prefix_doubling(s: &[u8], sa: &mut [u32]) {
+    assert_eq!(s.len() + 1, sa.len());
+
+    // the sentinel at the end is ranked the smallest.
+    let n = s.len();
+    let key = |i: usize| if i < n { s[i] as u32 + 1 } else { 0 };
+    for (i, p) in sa.iter_mut().enumerate() {
+        *p = i as u32;
+    }
+    sa.sort_unstable_by_key(|&i| key(i as usize));
+
+    // group number of a suffix is the last rank of its group.
+    let mut isa = vec![0; n + 1];
+    let mut keys: Vec<u32> = sa.iter().map(|&i| key(i as usize)).collect();
+    let mut groups = Vec::new();
+    split_groups(sa, &mut isa, &keys, 0, &mut groups);
+
+    let mut h = 1;
+    while !groups.is_empty() {
+        let mut next = Vec::new();
+        for (i, j) in groups.into_iter() {
+            // the suffixes of an unsorted group are at least h bytes long,
+            // since the sentinel is unique.
+            sa[i..j].sort_unstable_by_key(|&p| isa[p as usize + h]);
+            keys.clear();
+            keys.extend(sa[i..j].iter().map(|&p| isa[p as usize + h]));
+            split_groups(&sa[i..j], &mut isa, &keys, i, &mut next);
+        }
+        groups = next;
+        h *= 2;
+    }
+}
+
+/// Split the sorted suffixes `sa` starting at rank `base` into groups of
+/// equal keys, numbering them and collecting the unsorted ones.
+fn split_groups(
+    sa: &[u32],
+    isa: &mut [u32],
+    keys: &[u32],
+    base: usize,
+    unsorted: &mut Vec<(usize, usize)>,
+) {
+    let mut i = 0;
+    while i < sa.len() {
+        let mut j = i + 1;
+        while j < sa.len() && keys[j] == keys[i] {
+            j += 1;
+        }
+        for &p in sa[i..j].iter() {
+            isa[p as usize] = (base + j - 1) as u32;
+        }
+        if j - i > 1 {
+            unsorted.push((base + i, base + j));
+        }
+        i = j;
+    }
+}
@@ -0,0 +1,100 @@
+use super::utils::lcp;
+
+/// Maximum number of suffixes re-sorted by a local repair, beyond which the
+/// suffix array is rebuilt instead.
+pub const MAX_REPAIR: usize = 4096;
+
+/// Find the leftmost position whose suffix may be reordered by an edit
+/// starting at `a`, looking back at most `limit` positions.
+///
+/// The order of the suffix at `p` is settled before the edit if it differs
+/// from both of its neighbors in the suffix array by a byte before `a`. The
+/// LCP with the neighbors drops by at most 1 per position, so `p + lcp`
+/// never decreases with `p`, and the scan stops at the first such suffix.
+pub fn affected_start(
+    s: &[u8],
+    sa: &[u32],
+    a: usize,
+    limit: usize,
+) -> Option<usize> {
+    let n = s.len();
+    let floor = a.saturating_sub(limit);
+    let mut rank = vec![0; a - floor];
+    for (r, &p) in sa.iter().enumerate() {
+        let p = p as usize;
+        if p >= floor && p < a {
+            rank[p - floor] = r;
+        }
+    }
+
+    for p in (floor..a).rev() {
+        let r = rank[p - floor];
+        let settled = |q: Option<&u32>| {
+            // the empty suffix is always the smallest.
+            q.map_or(true, |&q| {
+                let l = lcp(&s[p..a], &s[q as usize..]);
+                q as usize == n || (l < a - p && q as usize + l < n)
+            })
+        };
+        if settled(sa.get(r - 1)) && settled(sa.get(r + 1)) {
+            return Some(p + 1);
+        }
+    }
+    if floor == 0 {
+        Some(0)
+    } else {
+        None
+    }
+}
+
+/// Repair the suffix array after `a..b` of the byte string is replaced by
+/// `m` bytes, which are already in `s`, re-sorting the suffixes starting in
+/// `lo..a + m` and merging them into the others.
+///
+/// The suffixes are compared by at most their first `a + m - lo` bytes, and
+/// it gives up once two of them are equal in those bytes, or the re-sorted
+/// suffixes disagree with their neighbors, returning false and leaving the
+/// suffix array unspecified.
+pub fn repair(
+    s: &[u8],
+    sa: &mut Vec<u32>,
+    lo: usize,
+    (a, b): (usize, usize),
+    m: usize,
+) -> bool {
+    let end = a + m;
+    let key = |p: u32| {
+        let p = p as usize;
+        &s[p..s.len().min(p + end - lo)]
+    };
+    let mut fresh: Vec<u32> = (lo as u32..end as u32).collect();
+    fresh.sort_unstable_by_key(|&p| key(p));
+    if fresh.windows(2).any(|w| key(w[0]) == key(w[1])) {
+        return false;
+    }
+
+    // the other suffixes keep their order, shifted past the edit.
+    sa.retain(|&p| (p as usize) < lo || p as usize >= b);
+    sa.iter_mut()
+        .filter(|p| **p as usize >= b)
+        .for_each(|p| *p = (*p as usize - b + end) as u32);
+
+    let kept = std::mem::take(sa);
+    sa.reserve(kept.len() + fresh.len());
+    let mut rest = &kept[..];
+    let mut inserted = Vec::with_capacity(fresh.len());
+    for &p in fresh.iter() {
+        let i = rest.partition_point(|&q| key(q) < key(p));
+        sa.extend_from_slice(&rest[..i]);
+        rest = &rest[i..];
+        inserted.push(sa.len());
+        sa.push(p);
+    }
+    sa.extend_from_slice(rest);
+
+    inserted.into_iter().all(|i| {
+        let before = i == 0 || key(sa[i - 1]) < key(sa[i]);
+        let after = i + 1 == sa.len() || key(sa[i]) < key(sa[i + 1]);
+        before && after
+    })
+}
@@ -0,0 +1,61 @@
+use std::fmt;
+use std::io;
+
+use super::saca::MAX_LENGTH;
+
+/// Errors of suffix array construction and searching.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The byte string of given length is longer than `MAX_LENGTH`.
+    TooLong(usize),
+    /// The pattern of given length is longer than the configured maximum,
+    /// see `SuffixArray::set_max_pattern_len`.
+    PatternTooLong {
+        /// Length of the pattern.
+        len: usize,
+        /// The configured maximum length.
+        max: usize,
+    },
+    /// The loaded suffix array or byte string is of another length than the
+    /// one it belongs to.
+    LengthMismatch {
+        /// The length it should be.
+        expected: usize,
+        /// The declared or decoded length.
+        found: usize,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Error::TooLong(len) => write!(
+                f,
+                "byte string of length {} exceeds the maximum length {}",
+                len, MAX_LENGTH
+            ),
+            Error::PatternTooLong { len, max } => write!(
+                f,
+                "pattern of length {} exceeds the maximum length {}",
+                len, max
+            ),
+            Error::LengthMismatch { expected, found } => write!(
+                f,
+                "length {} mismatches the expected length {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> io::Error {
+        let kind = match err {
+            Error::LengthMismatch { .. } => io::ErrorKind::InvalidData,
+            _ => io::ErrorKind::InvalidInput,
+        };
+        io::Error::new(kind, err)
+    }
+}
@@ -0,0 +1,30 @@
+/// Range of the first bytes of the suffixes in a block exported by
+/// `SuffixArray::export_blocks`, see `SuffixArray::export_block_bounds`.
+///
+/// The first bytes are keyed as `c + 1` for byte `c` and 0 for the empty
+/// suffix, so that the keys are in the suffix order. The layout is fixed,
+/// so that the bounds could be uploaded as an array of pairs of `u16`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BlockBounds {
+    /// Key of the first byte of the first suffix in the block.
+    pub min_first: u16,
+    /// Key of the first byte of the last suffix in the block.
+    pub max_first: u16,
+}
+
+impl BlockBounds {
+    pub(crate) fn new(s: &[u8], block: &[u32]) -> Self {
+        let key = |p: u32| s.get(p as usize).map_or(0, |&c| c as u16 + 1);
+        BlockBounds {
+            min_first: key(block[0]),
+            max_first: key(block[block.len() - 1]),
+        }
+    }
+
+    /// Test if the block may contain suffixes starting with the byte.
+    pub fn may_start_with(&self, c: u8) -> bool {
+        let key = c as u16 + 1;
+        self.min_first <= key && key <= self.max_first
+    }
+}
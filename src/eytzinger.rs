@@ -0,0 +1,157 @@
+use std::cmp::Ordering;
+use std::ops::Range;
+
+use super::utils::{pack_prefix, trunc};
+
+/// A sampled suffix, with its leading bytes packed.
+#[derive(Debug, Clone, Copy, Default)]
+struct Node {
+    key: u64,
+    len: u32,
+    rank: u32,
+}
+
+/// Samples of every `block`-th suffix laid out in Eytzinger (BFS) order, so
+/// that a binary search over them descends a complete binary tree whose top
+/// levels share the same few cache lines.
+#[derive(Debug, Clone)]
+pub struct FastSearch {
+    block: usize,
+    sa_len: usize,
+    // 1-indexed, the children of node k are 2k and 2k + 1.
+    nodes: Vec<Node>,
+}
+
+impl FastSearch {
+    /// Sample the suffix array every `block` ranks.
+    pub fn new(s: &[u8], sa: &[u32], block: usize) -> Self {
+        let mut fast = FastSearch {
+            block,
+            sa_len: 0,
+            nodes: Vec::new(),
+        };
+        fast.rebuild(s, sa);
+        fast
+    }
+
+    /// Sample another suffix array, reusing the allocation.
+    pub fn rebuild(&mut self, s: &[u8], sa: &[u32]) {
+        let block = self.block;
+        let count = sa.len().div_ceil(block);
+        self.nodes.clear();
+        self.nodes.reserve_exact(count + 1);
+        self.nodes.resize(count + 1, Node::default());
+        let mut next = 0;
+        fill(&mut self.nodes[..], 1, &mut next, &mut |i| {
+            let rank = i * block;
+            let (key, len) = pack_prefix(&s[sa[rank] as usize..], 8);
+            Node {
+                key,
+                len: len as u32,
+                rank: rank as u32,
+            }
+        });
+        self.sa_len = sa.len();
+    }
+
+    /// Heap memory taken by the samples, in bytes.
+    pub fn heap_size(&self) -> usize {
+        self.nodes.capacity() * std::mem::size_of::<Node>()
+    }
+
+    /// Narrow down the range of ranks of the suffixes prefixed by pattern.
+    pub fn narrow(&self, s: &[u8], sa: &[u32], pat: &[u8]) -> Range<usize> {
+        let count = self.nodes.len() - 1;
+        let lower = self.partition_point(|node| {
+            self.cmp_prefix(s, sa, node, pat) == Ordering::Less
+        });
+        let upper = self.partition_point(|node| {
+            self.cmp_prefix(s, sa, node, pat) != Ordering::Greater
+        });
+
+        // the samples right before lower and at upper are excluded.
+        let start = if lower == 0 {
+            0
+        } else {
+            (lower - 1) * self.block + 1
+        };
+        let end = if upper == count {
+            self.sa_len
+        } else {
+            upper * self.block
+        };
+        start..end
+    }
+
+    /// Find the number of samples satisfying the predicate, assuming that
+    /// they are partitioned by it.
+    #[inline]
+    fn partition_point<F: Fn(&Node) -> bool>(&self, pred: F) -> usize {
+        let mut k = 1;
+        while k < self.nodes.len() {
+            // the grandchildren are adjacent, 4 * k to 4 * k + 3.
+            prefetch(&self.nodes, 4 * k);
+            k = 2 * k + pred(&self.nodes[k]) as usize;
+        }
+        // cancel the right turns after the last left turn.
+        k >>= k.trailing_ones() + 1;
+        if k == 0 {
+            self.nodes.len() - 1
+        } else {
+            self.nodes[k].rank as usize / self.block
+        }
+    }
+
+    /// Compare the sampled suffix truncated to the length of pattern with
+    /// the pattern.
+    #[inline]
+    fn cmp_prefix(
+        &self,
+        s: &[u8],
+        sa: &[u32],
+        node: &Node,
+        pat: &[u8],
+    ) -> Ordering {
+        let m = Ord::min(pat.len(), 8);
+        if m > 0 {
+            let mask = !0u64 << (64 - 8 * m);
+            let x = (node.key & mask, Ord::min(node.len as usize, m));
+            let ord = x.cmp(&pack_prefix(pat, m));
+            if ord != Ordering::Equal || pat.len() <= 8 {
+                return ord;
+            }
+        }
+        let suffix = &s[sa[node.rank as usize] as usize..];
+        trunc(suffix, pat.len()).cmp(pat)
+    }
+}
+
+/// Fill the subtree rooted at `k` by in-order traversal.
+fn fill<F: FnMut(usize) -> Node>(
+    nodes: &mut [Node],
+    k: usize,
+    next: &mut usize,
+    sample: &mut F,
+) {
+    if k < nodes.len() {
+        fill(nodes, 2 * k, next, sample);
+        nodes[k] = sample(*next);
+        *next += 1;
+        fill(nodes, 2 * k + 1, next, sample);
+    }
+}
+
+/// Hint that node `k` is about to be read.
+#[inline]
+fn prefetch(nodes: &[Node], k: usize) {
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+
+        // prefetching never faults, even if out of bounds.
+        let p = nodes.as_ptr().wrapping_add(k) as *const i8;
+        _mm_prefetch(p, _MM_HINT_T0);
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = (nodes, k);
+}
@@ -0,0 +1,186 @@
+//! C ABI of the owned suffix arrays, declared in `include/suffix_array.h`.
+//!
+//! The functions never unwind into C, and report failures by null handles
+//! or negative return values.
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::slice;
+
+use super::sa::OwnedSuffixArray;
+use super::saca::MAX_LENGTH;
+
+/// Opaque handle of an owned suffix array, `suffix_array_t` in C.
+pub struct FfiSuffixArray(OwnedSuffixArray);
+
+/// View the raw parts as a slice, where a null pointer is allowed only for
+/// the empty one.
+unsafe fn as_bytes<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        Some(&[])
+    } else if data.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(data, len))
+    }
+}
+
+unsafe fn as_path<'a>(path: *const c_char) -> Option<&'a str> {
+    if path.is_null() {
+        return None;
+    }
+    CStr::from_ptr(path).to_str().ok()
+}
+
+fn into_handle(sa: OwnedSuffixArray) -> *mut FfiSuffixArray {
+    Box::into_raw(Box::new(FfiSuffixArray(sa)))
+}
+
+/// Construct the suffix array of a copy of the byte string.
+///
+/// Returns null if the byte string is longer than `MAX_LENGTH` or null.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes, unless `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn suffix_array_new(
+    data: *const u8,
+    len: usize,
+) -> *mut FfiSuffixArray {
+    let s = match as_bytes(data, len) {
+        Some(s) if s.len() <= MAX_LENGTH => s.to_vec(),
+        _ => return std::ptr::null_mut(),
+    };
+    catch_unwind(|| into_handle(OwnedSuffixArray::new(s)))
+        .unwrap_or(std::ptr::null_mut())
+}
+
+/// Destroy the suffix array, doing nothing if null.
+///
+/// # Safety
+///
+/// `sa` must be null or a handle not yet destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn suffix_array_free(sa: *mut FfiSuffixArray) {
+    if !sa.is_null() {
+        drop(Box::from_raw(sa));
+    }
+}
+
+/// Length of the underlying byte string, or 0 if null.
+///
+/// # Safety
+///
+/// `sa` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn suffix_array_len(sa: *const FfiSuffixArray) -> usize {
+    match sa.as_ref() {
+        Some(sa) => sa.0.len(),
+        None => 0,
+    }
+}
+
+/// Test if it contains the pattern, returning 1 if it does, 0 if not, and
+/// -1 if any argument is null.
+///
+/// # Safety
+///
+/// `sa` must be null or a live handle, and `pat` must point to `pat_len`
+/// readable bytes unless `pat_len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn suffix_array_contains(
+    sa: *const FfiSuffixArray,
+    pat: *const u8,
+    pat_len: usize,
+) -> c_int {
+    match (sa.as_ref(), as_bytes(pat, pat_len)) {
+        (Some(sa), Some(pat)) => sa.0.contains(pat) as c_int,
+        _ => -1,
+    }
+}
+
+/// Search for all the occurrences of the pattern in text order, writing
+/// at most `cap` of the first ones into `out`.
+///
+/// Returns the total number of occurrences, which may exceed `cap` so that
+/// the caller could retry with a larger buffer, or -1 if any argument is
+/// null.
+///
+/// # Safety
+///
+/// `sa` must be null or a live handle, `pat` must point to `pat_len`
+/// readable bytes unless `pat_len` is 0, and `out` must point to `cap`
+/// writable `uint32_t` unless `cap` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn suffix_array_search_all(
+    sa: *const FfiSuffixArray,
+    pat: *const u8,
+    pat_len: usize,
+    out: *mut u32,
+    cap: usize,
+) -> isize {
+    let (sa, pat) = match (sa.as_ref(), as_bytes(pat, pat_len)) {
+        (Some(sa), Some(pat)) => (sa, pat),
+        _ => return -1,
+    };
+    if cap > 0 && out.is_null() {
+        return -1;
+    }
+
+    let found = sa.0.search_all_sorted(pat);
+    let n = Ord::min(found.len(), cap);
+    if n > 0 {
+        slice::from_raw_parts_mut(out, n).copy_from_slice(&found[..n]);
+    }
+    found.len() as isize
+}
+
+/// Write the suffix array together with its byte string into the file,
+/// returning 0 on success and -1 on failure.
+///
+/// # Safety
+///
+/// `sa` must be null or a live handle, and `path` must be null or a
+/// nul-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn suffix_array_dump(
+    sa: *const FfiSuffixArray,
+    path: *const c_char,
+) -> c_int {
+    let (sa, path) = match (sa.as_ref(), as_path(path)) {
+        (Some(sa), Some(path)) => (sa, path),
+        _ => return -1,
+    };
+    let dumped = catch_unwind(AssertUnwindSafe(|| {
+        sa.0.dump_with_text_file(path).is_ok()
+    }));
+    match dumped {
+        Ok(true) => 0,
+        _ => -1,
+    }
+}
+
+/// Load the suffix array written by `suffix_array_dump`, checking its
+/// integrity.
+///
+/// Returns null if the file is unreadable or corrupt, or `path` is null.
+///
+/// # Safety
+///
+/// `path` must be null or a nul-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn suffix_array_load(
+    path: *const c_char,
+) -> *mut FfiSuffixArray {
+    let path = match as_path(path) {
+        Some(path) => path,
+        None => return std::ptr::null_mut(),
+    };
+    let loaded = catch_unwind(|| OwnedSuffixArray::load_owned_file(path));
+    match loaded {
+        Ok(Ok(sa)) => into_handle(sa),
+        _ => std::ptr::null_mut(),
+    }
+}
@@ -0,0 +1,71 @@
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufReader, Read, Result};
+use std::path::Path;
+
+use super::error::Error;
+use super::sa::OwnedSuffixArray;
+use super::saca::MAX_LENGTH;
+
+/// Options of persisting the suffix array built by `index_and_save`.
+#[cfg(feature = "pack")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PackOptions {
+    /// Dump the byte string together with the suffix array, so that the
+    /// archive could be loaded by `OwnedSuffixArray::load_owned_file` on its
+    /// own. The byte string is compressed if the `zstd` feature is enabled.
+    ///
+    /// Otherwise only the suffix array is dumped, which is loaded along with
+    /// the original file by `SuffixArray::load_file`.
+    pub with_text: bool,
+}
+
+#[cfg(feature = "pack")]
+impl Default for PackOptions {
+    fn default() -> Self {
+        PackOptions { with_text: true }
+    }
+}
+
+/// Read the whole file and construct its suffix array.
+///
+/// Fails with `ErrorKind::InvalidInput` if the file is longer than
+/// `MAX_LENGTH`, before reading it.
+pub fn index_file<P: AsRef<Path>>(path: P) -> Result<OwnedSuffixArray> {
+    let file = File::open(path)?;
+    let hint = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+    if hint > MAX_LENGTH as u64 {
+        let len = usize::try_from(hint).unwrap_or(usize::MAX);
+        return Err(Error::TooLong(len).into());
+    }
+
+    // the file may grow after the length is queried.
+    let mut text = Vec::with_capacity(hint as usize);
+    let limit = MAX_LENGTH as u64 + 1;
+    BufReader::new(file).take(limit).read_to_end(&mut text)?;
+    if text.len() > MAX_LENGTH {
+        return Err(Error::TooLong(text.len()).into());
+    }
+
+    Ok(OwnedSuffixArray::new(text))
+}
+
+/// Construct the suffix array of file `path_in`, and persist it to file
+/// `path_out`.
+#[cfg(feature = "pack")]
+pub fn index_and_save<P, Q>(
+    path_in: P,
+    path_out: Q,
+    options: PackOptions,
+) -> Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let sa = index_file(path_in)?;
+    if options.with_text {
+        sa.dump_with_text_file(path_out)
+    } else {
+        sa.dump_file(path_out)
+    }
+}
@@ -0,0 +1,49 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Frequently repeated sub-string, see `SuffixArray::top_substrings`.
+///
+/// The sub-string is `s[offset..offset + len]` of the byte string, which
+/// is not kept here so that it could be stored apart from the byte string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "pack", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrequentSubstring {
+    /// The leftmost occurrence.
+    pub offset: u32,
+    /// Length of the sub-string.
+    pub len: u32,
+    /// Number of occurrences.
+    pub count: u32,
+}
+
+/// Select the `k` most frequent sub-strings out of the LCP intervals,
+/// the longer and then the lexicographically smaller first on ties.
+pub fn top_k<'a, I>(intervals: I, k: usize) -> Vec<FrequentSubstring>
+where
+    I: Iterator<Item = (&'a [u8], &'a [u32])>,
+{
+    if k == 0 {
+        return Vec::new();
+    }
+
+    // max-heap of the selected ones, keyed so that the worst is on top.
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    for (sub, occ) in intervals {
+        let key = (Reverse(occ.len()), Reverse(sub.len()), sub);
+        if heap.len() == k {
+            if heap.peek().is_some_and(|(top, _)| key >= *top) {
+                continue;
+            }
+            heap.pop();
+        }
+        heap.push((key, occ.iter().min().cloned().unwrap_or(0)));
+    }
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|((count, len, _), offset)| FrequentSubstring {
+            offset,
+            len: len.0 as u32,
+            count: count.0 as u32,
+        })
+        .collect()
+}
@@ -0,0 +1,47 @@
+use super::lcp::lcp_array;
+
+/// Longest common extension queries over a string, answered by range
+/// minimum queries on its LCP array.
+///
+/// The sparse table takes O(n log n) space.
+pub struct Lce {
+    rank: Vec<u32>,
+    // table[k][i] is the minimum of lcp[i..i + 2^k].
+    table: Vec<Vec<u32>>,
+}
+
+impl Lce {
+    /// Prepare for the queries over string `s` with suffix array `sa`.
+    pub fn new<C: Eq>(s: &[C], sa: &[u32]) -> Self {
+        let mut rank = vec![0u32; sa.len()];
+        for (i, &p) in sa.iter().enumerate() {
+            rank[p as usize] = i as u32;
+        }
+
+        let mut table = vec![lcp_array(s, sa)];
+        let mut width = 1;
+        while width * 2 <= sa.len() {
+            let prev = &table[table.len() - 1];
+            let next = (0..prev.len() - width)
+                .map(|i| Ord::min(prev[i], prev[i + width]))
+                .collect();
+            table.push(next);
+            width *= 2;
+        }
+
+        Lce { rank, table }
+    }
+
+    /// Length of the longest common prefix of the suffixes at `i` and `j`.
+    pub fn query(&self, i: usize, j: usize) -> usize {
+        if i == j {
+            return self.rank.len() - 1 - i;
+        }
+
+        let (x, y) = (self.rank[i] as usize, self.rank[j] as usize);
+        let (lo, hi) = (Ord::min(x, y) + 1, Ord::max(x, y) + 1);
+        let k = (usize::BITS - 1 - (hi - lo).leading_zeros()) as usize;
+        let level = &self.table[k];
+        Ord::min(level[lo], level[hi - (1 << k)]) as usize
+    }
+}
@@ -0,0 +1,180 @@
+use std::borrow::Cow;
+
+/// Construct the LCP array from suffix array using Kasai's algorithm.
+///
+/// `lcp[i]` is the longest common prefix length of the suffixes `sa[i - 1]`
+/// and `sa[i]`, and `lcp[0]` is always 0.
+pub fn lcp_array<C: Eq>(s: &[C], sa: &[u32]) -> Vec<u32> {
+    let mut lcp = Vec::new();
+    lcp_array_into(s, sa, &mut lcp);
+    lcp
+}
+
+/// Construct the LCP array into the given buffer, reusing its allocation.
+pub fn lcp_array_into<C: Eq>(s: &[C], sa: &[u32], lcp: &mut Vec<u32>) {
+    let n = s.len();
+    let mut rank = vec![0u32; n + 1];
+    for (i, &p) in sa.iter().enumerate() {
+        rank[p as usize] = i as u32;
+    }
+
+    lcp.clear();
+    lcp.reserve_exact(n + 1);
+    lcp.resize(n + 1, 0);
+    let mut h = 0;
+    for p in 0..n {
+        let r = rank[p] as usize;
+        let q = sa[r - 1] as usize;
+        while p + h < n && q + h < n && s[p + h] == s[q + h] {
+            h += 1;
+        }
+        lcp[r] = h as u32;
+        h = h.saturating_sub(1);
+    }
+}
+
+/// Iterator over the LCP intervals, i.e. the right-maximal repeated
+/// sub-strings, in a bottom-up traversal.
+pub struct LcpIntervals<'a, 's> {
+    lcp: Cow<'s, [u32]>,
+    sa: &'s [u32],
+    s: &'a [u8],
+    min_len: usize,
+    min_count: usize,
+    stack: Vec<(u32, usize)>,
+    i: usize,
+    lb: usize,
+}
+
+impl<'a, 's> LcpIntervals<'a, 's> {
+    pub fn new(
+        s: &'a [u8],
+        sa: &'s [u32],
+        lcp: Cow<'s, [u32]>,
+        min_len: usize,
+        min_count: usize,
+    ) -> Self {
+        LcpIntervals {
+            lcp,
+            sa,
+            s,
+            min_len: Ord::max(min_len, 1),
+            min_count,
+            stack: vec![(0, 0)],
+            i: 1,
+            lb: 0,
+        }
+    }
+}
+
+impl<'a, 's> Iterator for LcpIntervals<'a, 's> {
+    type Item = (&'a [u8], &'s [u32]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.i <= self.lcp.len() {
+            let l = self.lcp.get(self.i).cloned().unwrap_or(0);
+            let (top, _) = self.stack[self.stack.len() - 1];
+            if l < top {
+                // close the interval [lb, i - 1] with LCP value `top`.
+                let (len, lb) = self.stack.pop().unwrap();
+                self.lb = lb;
+                let len = len as usize;
+                let occ = &self.sa[lb..self.i];
+                if len >= self.min_len && occ.len() >= self.min_count {
+                    let start = occ[0] as usize;
+                    return Some((&self.s[start..start + len], occ));
+                }
+                continue;
+            }
+            if l > top {
+                self.stack.push((l, self.lb));
+            }
+            self.i += 1;
+            self.lb = self.i - 1;
+        }
+        None
+    }
+}
+
+/// Iterator over the distinct sub-strings of length `k` in lexicographic
+/// order, with the number of their occurrences.
+pub struct KmerCounts<'a, 's> {
+    lcp: Cow<'s, [u32]>,
+    sa: &'s [u32],
+    s: &'a [u8],
+    k: usize,
+    i: usize,
+}
+
+impl<'a, 's> KmerCounts<'a, 's> {
+    pub fn new(
+        s: &'a [u8],
+        sa: &'s [u32],
+        lcp: Cow<'s, [u32]>,
+        k: usize,
+    ) -> Self {
+        assert!(k > 0);
+        KmerCounts {
+            lcp,
+            sa,
+            s,
+            k,
+            i: 0,
+        }
+    }
+}
+
+impl<'a, 's> Iterator for KmerCounts<'a, 's> {
+    type Item = (&'a [u8], u32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.i < self.sa.len() {
+            let p = self.sa[self.i] as usize;
+            let start = self.i;
+            self.i += 1;
+            if self.s.len() - p < self.k {
+                continue;
+            }
+
+            // the suffixes sharing the k-mer form a run of LCP at least k.
+            while self.i < self.sa.len() && self.lcp[self.i] as usize >= self.k
+            {
+                self.i += 1;
+            }
+            let count = (self.i - start) as u32;
+            return Some((&self.s[p..p + self.k], count));
+        }
+        None
+    }
+}
+
+/// Count the occurrences of the length-`window` sub-string at each position,
+/// or 0 where fewer than `window` bytes are left.
+pub fn window_counts(
+    s: &[u8],
+    sa: &[u32],
+    lcp: &[u32],
+    window: usize,
+) -> Vec<u32> {
+    assert!(window > 0);
+    let mut counts = vec![0; s.len()];
+    let mut i = 0;
+    while i < sa.len() {
+        let start = i;
+        let p = sa[i] as usize;
+        i += 1;
+        if s.len() - p < window {
+            continue;
+        }
+
+        // the suffixes sharing the window form a run of LCP at least window.
+        while i < sa.len() && lcp[i] as usize >= window {
+            i += 1;
+        }
+        let count = (i - start) as u32;
+        for &p in sa[start..i].iter() {
+            counts[p as usize] = count;
+        }
+    }
+    counts
+}
@@ -9,6 +9,17 @@
 //! which is the fastest known suffix array construction algorithm (SACA) running in single thread
 //! that uses merely O(1) additional workspace.
 //!
+//! Tiny inputs are sorted naively, and highly repetitive inputs are handed to
+//! the linear time SA-IS algorithm instead. See `IndexStats` for the details
+//! of this decision.
+//!
+//! The suffix array of a byte string is unique, so it is identical for any
+//! construction algorithm, platform or feature set, and so are the dumped
+//! bytes, which are fixed-width little-endian. The parallel computations of
+//! the `rayon` feature only count integers, so they are reproducible as
+//! well. The only exception is the byte string dumped by `dump_with_text`,
+//! which is compressed if the `zstd` feature is enabled.
+//!
 //! # Examples
 //!
 //! Test if the data contains given pattern.
@@ -40,14 +51,116 @@
 //! assert_eq!(&s[lcp], b"spl");
 //! ```
 
+#[cfg(feature = "pack")]
+mod archive;
+#[cfg(feature = "bench-tools")]
+pub mod bench_tools;
+pub mod bitvec;
+mod bloom;
+mod buckets;
+mod cell;
+mod collate;
+mod corpus;
+mod cursor;
+mod dedup;
+mod dna;
+mod doubling;
+mod edit;
+mod error;
+mod export;
+mod eytzinger;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod file;
+mod frequent;
+mod lce;
+mod lcp;
+mod matching;
+mod normalize;
+mod overlap;
 #[cfg(feature = "pack")]
 mod packed_sa;
+mod page;
+mod palindrome;
+mod plan;
+mod plcp;
+mod position;
+#[cfg(feature = "pack")]
+mod postings;
+mod progress;
+mod provider;
+#[cfg(feature = "python")]
+pub mod python;
+mod raw;
+mod report;
+mod rlfm;
+mod rotation;
 mod sa;
 mod saca;
+mod sais;
+mod sampled;
+mod shards;
+mod similarity;
+mod sliding;
+mod sparse;
+mod splice;
+mod stats;
+mod storage;
+mod subindex;
+mod trie;
+mod unicode;
 mod utils;
+mod warm;
+mod workspace;
 
 #[cfg(test)]
 mod tests;
 
-pub use self::sa::SuffixArray;
+#[cfg(feature = "pack")]
+pub use self::archive::{Archive, Section};
+pub use self::buckets::MAX_BUCKET_DEPTH;
+pub use self::cell::IndexCell;
+pub use self::collate::CollatedSuffixArray;
+pub use self::corpus::{CorpusSet, Transform};
+pub use self::cursor::{BiCursor, Cursor};
+pub use self::dedup::DupRegion;
+pub use self::dna::{dna_symbol, Encoded2Bit, Encoded2BitSuffixArray};
+pub use self::edit::MAX_REPAIR;
+pub use self::error::Error;
+pub use self::export::BlockBounds;
+pub use self::file::index_file;
+#[cfg(feature = "pack")]
+pub use self::file::{index_and_save, PackOptions};
+pub use self::frequent::FrequentSubstring;
+pub use self::normalize::{Normalization, NormalizedText};
+pub use self::overlap::Overlaps;
+pub use self::page::Page;
+pub use self::plan::{PlanDescription, Strategy};
+pub use self::plcp::LcpKind;
+pub use self::position::{Documents, Position};
+#[cfg(feature = "pack")]
+pub use self::postings::PostingLists;
+pub use self::progress::Phase;
+#[cfg(any(unix, windows))]
+pub use self::provider::FileText;
+pub use self::provider::{ProvidedSuffixArray, TextProvider};
+pub use self::raw::RawWidth;
+pub use self::report::{Occurrences, PartialMatches, SearchHit, SearchOptions};
+pub use self::rlfm::RlfmIndex;
+pub use self::sa::{OwnedSuffixArray, SuffixArray};
 pub use self::saca::MAX_LENGTH;
+pub use self::sais::sais_ints_mut;
+pub use self::sampled::SampledIndex;
+pub use self::shards::Shards;
+pub use self::similarity::SimilarityKind;
+pub use self::sliding::SlidingWindowIndex;
+pub use self::sparse::PositionFilteredSuffixArray;
+pub use self::splice::Splice;
+pub use self::stats::{
+    Algorithm, BucketStats, IndexStats, MemoryBreakdown, TextStats,
+};
+pub use self::storage::TextStorage;
+pub use self::subindex::SubIndexView;
+pub use self::trie::PatternTrie;
+pub use self::warm::WarmLevel;
+pub use self::workspace::{SuffixArrayRef, Workspace};
@@ -0,0 +1,137 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use super::sa::SuffixArray;
+use super::storage::TextStorage;
+
+/// Range of suffixes prefixed by a sliding match, which grows at the end by
+/// binary searching inside the range, and shrinks at the front by the
+/// suffix link and the enclosing LCP interval, e.g. for the matching
+/// statistics computed in one pass.
+///
+/// The LCP array and its previous and next smaller values are prepared in
+/// linear time, and the inverse suffix array is computed on first use, see
+/// `SuffixArray::rank_of`.
+pub(crate) struct Matcher<'a, T> {
+    sa: &'a SuffixArray<T>,
+    lcp: Cow<'a, [u32]>,
+    // nearest ranks on either side with a smaller LCP value, the number of
+    // ranks standing for the end.
+    psv: Vec<u32>,
+    nsv: Vec<u32>,
+    ranks: Range<usize>,
+    len: usize,
+}
+
+impl<'a, T: TextStorage> Matcher<'a, T> {
+    pub(crate) fn new(sa: &'a SuffixArray<T>) -> Self {
+        let lcp = sa.lcp_array();
+        let n = lcp.len();
+        let mut psv = vec![0; n];
+        let mut nsv = vec![0; n];
+        let mut stack: Vec<u32> = Vec::new();
+        for i in 0..n {
+            while let Some(&top) = stack.last() {
+                if lcp[top as usize] < lcp[i] {
+                    break;
+                }
+                stack.pop();
+            }
+            psv[i] = stack.last().cloned().unwrap_or(0);
+            stack.push(i as u32);
+        }
+        stack.clear();
+        for i in (0..n).rev() {
+            while let Some(&top) = stack.last() {
+                if lcp[top as usize] < lcp[i] {
+                    break;
+                }
+                stack.pop();
+            }
+            nsv[i] = stack.last().cloned().unwrap_or(n as u32);
+            stack.push(i as u32);
+        }
+
+        Matcher {
+            sa,
+            lcp,
+            psv,
+            nsv,
+            ranks: 0..n,
+            len: 0,
+        }
+    }
+
+    /// Length of the match.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Append a byte to the match if it still occurs, returning whether it
+    /// does.
+    pub(crate) fn extend(&mut self, c: u8) -> bool {
+        let ranks = self.sa.refine(self.ranks.clone(), self.len, c);
+        if ranks.is_empty() {
+            return false;
+        }
+        self.ranks = ranks;
+        self.len += 1;
+        true
+    }
+
+    /// Drop the first byte of the non-empty match.
+    ///
+    /// The suffix following any occurrence of the match is prefixed by the
+    /// shorter match, so the new range is the widest one around its rank
+    /// where the LCP values stay no less than the new length.
+    pub(crate) fn drop_front(&mut self) {
+        debug_assert!(self.len > 0);
+        self.len -= 1;
+        let n = self.lcp.len();
+        if self.len == 0 {
+            self.ranks = 0..n;
+            return;
+        }
+
+        let p = self.sa.suffixes(self.ranks.clone())[0] as usize;
+        let rank = self.sa.rank_of(p + 1).unwrap() as usize;
+        let t = self.len as u32;
+        let lcp = |i: usize| if i < n { self.lcp[i] } else { 0 };
+        let mut start = rank;
+        while lcp(start) >= t {
+            start = self.psv[start] as usize;
+        }
+        let mut end = rank + 1;
+        while lcp(end) >= t {
+            end = self.nsv[end] as usize;
+        }
+        self.ranks = start..end;
+    }
+}
+
+/// Compute the matching statistics of query against the byte string of the
+/// suffix array in one pass, calling `f` with each position of query and
+/// the length of the longest match starting there.
+pub(crate) fn for_each_statistic<T, F>(
+    sa: &SuffixArray<T>,
+    query: &[u8],
+    mut f: F,
+) where
+    T: TextStorage,
+    F: FnMut(usize, usize),
+{
+    // the match at i is query[i..j].
+    let mut matcher = Matcher::new(sa);
+    let mut j = 0;
+    for i in 0..query.len() {
+        while j < query.len() && matcher.extend(query[j]) {
+            j += 1;
+        }
+        f(i, matcher.len());
+        if matcher.len() > 0 {
+            matcher.drop_front();
+        } else {
+            j += 1;
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::ops::Range;
+
+/// Options of normalizing the byte string before indexing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Normalization {
+    /// Map `\r\n` to `\n`.
+    pub crlf: bool,
+    /// Collapse each run of ASCII whitespaces (including line endings) into
+    /// a single space.
+    pub collapse_whitespace: bool,
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Normalization {
+            crlf: true,
+            collapse_whitespace: false,
+        }
+    }
+}
+
+impl Normalization {
+    /// Normalize a byte string, e.g. a pattern to be searched.
+    pub fn apply(&self, s: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(s.len());
+        self.normalize(s, |c, _| out.push(c));
+        out
+    }
+
+    /// Emit each normalized byte with the span of original bytes it comes
+    /// from, the spans cover the original byte string without overlapping.
+    fn normalize<F: FnMut(u8, Range<usize>)>(&self, s: &[u8], mut emit: F) {
+        let mut i = 0;
+        while i < s.len() {
+            if self.collapse_whitespace && s[i].is_ascii_whitespace() {
+                let n = s[i..].iter().take_while(|c| c.is_ascii_whitespace());
+                let j = i + n.count();
+                emit(b' ', i..j);
+                i = j;
+            } else if self.crlf && s[i..].starts_with(b"\r\n") {
+                emit(b'\n', i..i + 2);
+                i += 2;
+            } else {
+                emit(s[i], i..i + 1);
+                i += 1;
+            }
+        }
+    }
+}
+
+/// Normalized byte string, which translates the positions back to the
+/// original byte string.
+///
+/// It could be indexed by `SuffixArray` directly, then the occurrences of
+/// normalized patterns are mapped back by `original_range`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NormalizedText {
+    text: Vec<u8>,
+    offsets: OffsetMap,
+}
+
+impl NormalizedText {
+    /// Normalize the original byte string.
+    pub fn new(original: &[u8], options: Normalization) -> Self {
+        let mut text = Vec::with_capacity(original.len());
+        let mut jumps = vec![(0, 0)];
+        options.normalize(original, |c, span| {
+            let pos = text.len();
+            let (x, y) = jumps[jumps.len() - 1];
+            if y + (pos - x) != span.start {
+                jumps.push((pos, span.start));
+            }
+            text.push(c);
+        });
+        let offsets = OffsetMap {
+            len: text.len(),
+            original_len: original.len(),
+            jumps,
+        };
+        NormalizedText { text, offsets }
+    }
+
+    /// The normalized byte string.
+    pub fn text(&self) -> &[u8] {
+        &self.text[..]
+    }
+
+    /// Translate the normalized position, ranging from 0 to the normalized
+    /// length, to the original position where its source begins.
+    pub fn original_offset(&self, pos: usize) -> usize {
+        self.offsets.original_offset(pos)
+    }
+
+    /// Translate the normalized range to the range of original bytes it
+    /// comes from.
+    pub fn original_range(&self, range: Range<usize>) -> Range<usize> {
+        self.offsets.original_range(range)
+    }
+
+    /// Split into the normalized byte string and the translation of its
+    /// positions.
+    pub(crate) fn into_parts(self) -> (Vec<u8>, OffsetMap) {
+        (self.text, self.offsets)
+    }
+}
+
+impl AsRef<[u8]> for NormalizedText {
+    fn as_ref(&self) -> &[u8] {
+        self.text()
+    }
+}
+
+/// Translation of the normalized positions back to the original ones.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct OffsetMap {
+    len: usize,
+    original_len: usize,
+    // (normalized, original) positions where the offset between them
+    // changes, starting with (0, 0).
+    jumps: Vec<(usize, usize)>,
+}
+
+impl OffsetMap {
+    pub(crate) fn original_offset(&self, pos: usize) -> usize {
+        assert!(pos <= self.len);
+        if pos == self.len {
+            return self.original_len;
+        }
+        let k = self.jumps.partition_point(|&(x, _)| x <= pos);
+        let (x, y) = self.jumps[k - 1];
+        y + (pos - x)
+    }
+
+    pub(crate) fn original_range(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.original_offset(range.start);
+        if range.is_empty() {
+            start..start
+        } else {
+            start..self.original_offset(range.end)
+        }
+    }
+
+    /// Heap memory taken by the translation, in bytes.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.jumps.capacity() * std::mem::size_of::<(usize, usize)>()
+    }
+}
@@ -0,0 +1,71 @@
+use super::sa::SuffixArray;
+use super::storage::TextStorage;
+
+/// Overlaps between the underlying byte string and another string, see
+/// `SuffixArray::overlaps`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Overlaps {
+    /// Lengths of the suffixes of the byte string that are prefixes of the
+    /// other string, the longest first.
+    pub suffix_prefix: Vec<usize>,
+    /// Lengths of the prefixes of the byte string that are suffixes of the
+    /// other string, the longest first.
+    pub prefix_suffix: Vec<usize>,
+}
+
+pub fn overlaps<T: TextStorage>(
+    sa: &SuffixArray<T>,
+    other: &[u8],
+    min_len: usize,
+) -> Overlaps {
+    let n = sa.len();
+    let min_len = Ord::max(min_len, 1);
+    let suffix_prefix = suffix_prefix(sa, other, min_len);
+
+    // the whole byte string is prefixed by the pattern if its rank is in.
+    let mut prefix_suffix = Vec::new();
+    let mut cursor = sa.cursor();
+    let rank = sa.rank_of(0).unwrap() as usize;
+    for (i, &c) in other.iter().rev().enumerate().take(n) {
+        if !cursor.extend_front(c) {
+            break;
+        }
+        let len = i + 1;
+        if len >= min_len && cursor.range().contains(&rank) {
+            prefix_suffix.push(len);
+        }
+    }
+    prefix_suffix.reverse();
+
+    Overlaps {
+        suffix_prefix,
+        prefix_suffix,
+    }
+}
+
+/// Find the lengths of the suffixes of the byte string that are prefixes of
+/// the other string, the longest first.
+pub fn suffix_prefix<T: TextStorage>(
+    sa: &SuffixArray<T>,
+    other: &[u8],
+    min_len: usize,
+) -> Vec<usize> {
+    let n = sa.len();
+    let min_len = Ord::max(min_len, 1);
+
+    // the suffix equal to the pattern is the first one prefixed by it.
+    let mut lengths = Vec::new();
+    let mut cursor = sa.cursor();
+    for (i, &c) in other.iter().enumerate().take(n) {
+        if !cursor.extend(c) {
+            break;
+        }
+        let len = i + 1;
+        let first = cursor.range().start;
+        if len >= min_len && sa.pos_at_rank(first) == Some((n - len) as u32) {
+            lengths.push(len);
+        }
+    }
+    lengths.reverse();
+    lengths
+}
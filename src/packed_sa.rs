@@ -1,11 +1,23 @@
-use std::io::{prelude::*, Result};
+use std::borrow::Cow;
+use std::io::{prelude::*, Error, ErrorKind, Result};
 
+use bincode::Options;
 use bitpacking::{BitPacker, BitPacker4x as Packer};
 use serde::{Deserialize, Serialize};
 
+use super::error::Error as SaError;
+use super::saca::MAX_LENGTH;
+
 // Little endian of b"SA4x", i.e. the Packed Suffix Array using BitPacker4x.
 const MAGIC_CSA4: u32 = 2016690515;
 
+// Little endian of b"SAtx", i.e. the Packed Suffix Array with its text.
+const MAGIC_TEXT: u32 = 2020884819;
+
+// Encodings of the text.
+const TEXT_RAW: u8 = 0;
+const TEXT_ZSTD: u8 = 1;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackedSuffixArray {
     magic: u32,
@@ -15,7 +27,7 @@ pub struct PackedSuffixArray {
 
 impl PackedSuffixArray {
     pub fn from_sa(mut sa: &[u32]) -> Self {
-        assert!(sa.len() as u64 <= std::u32::MAX as u64);
+        assert!(sa.len() as u64 <= u32::MAX as u64);
 
         let magic = MAGIC_CSA4;
         let length = sa.len() as u32;
@@ -33,7 +45,7 @@ impl PackedSuffixArray {
             data.extend_from_slice(&buf[..]);
             sa = tail;
         }
-        if sa.len() > 0 {
+        if !sa.is_empty() {
             let mut chunk = vec![0; u32_chunk_size];
             chunk[..sa.len()].copy_from_slice(sa);
             packer.compress(&chunk[..], &mut buf[..], bits);
@@ -57,39 +69,47 @@ impl PackedSuffixArray {
         let bits = sa_bits(self.length);
         let u32_chunk_size = Packer::BLOCK_LEN;
         let u8_chunk_size = bits as usize * Packer::BLOCK_LEN / 8;
+        if bits == 0 {
+            // the only possible suffix array is [0].
+            return vec![0; self.length as usize];
+        }
 
+        // the trailing zero bytes of the last chunk are trimmed when dumping.
         let packer = Packer::new();
-        let mut data = &self.data[..];
         let mut remain = self.length as usize;
         let mut sa = Vec::with_capacity(remain);
+        let mut chunk = vec![0u8; u8_chunk_size];
         let mut buf = vec![0u32; u32_chunk_size];
-        while data.len() >= u8_chunk_size {
-            let n;
-            let (chunk, tail) = data.split_at(u8_chunk_size);
-            packer.decompress(chunk, &mut buf[..], bits);
-            if tail.len() == 0 {
-                n = remain;
-            } else {
-                n = u32_chunk_size;
-            }
-
+        for data in self.data.chunks(u8_chunk_size) {
+            let n = Ord::min(remain, u32_chunk_size);
+            chunk[..data.len()].copy_from_slice(data);
+            chunk[data.len()..].iter_mut().for_each(|x| *x = 0);
+            packer.decompress(&chunk[..], &mut buf[..], bits);
             sa.extend_from_slice(&buf[..n]);
-            data = tail;
             remain -= n;
         }
-        if data.len() > 0 {
-            let mut chunk = vec![0; u8_chunk_size];
-            chunk[..data.len()].copy_from_slice(data);
-            packer.decompress(&chunk[..], &mut buf[..], bits);
+        if remain > 0 {
+            // the last chunk is entirely trimmed.
+            buf.iter_mut().for_each(|x| *x = 0);
             sa.extend_from_slice(&buf[..remain]);
         }
 
         sa
     }
 
+    /// Unpack the suffix array of a byte string of given length, failing
+    /// before unpacking if the declared length mismatches.
+    pub fn into_sa_of(self, text_len: usize) -> Result<Vec<u32>> {
+        let found = self.length as usize;
+        let expected = text_len.saturating_add(1);
+        if found != expected {
+            return Err(SaError::LengthMismatch { expected, found }.into());
+        }
+        Ok(self.into_sa())
+    }
+
     pub fn dump<W: Write>(&self, file: W) -> Result<()> {
-        let mut cfg = bincode::config();
-        cfg.little_endian();
+        let cfg = bincode_options();
         match cfg.serialize_into(file, self) {
             Ok(_) => Ok(()),
             Err(e) => Err(error_conv(e)),
@@ -97,8 +117,7 @@ impl PackedSuffixArray {
     }
 
     pub fn dump_bytes(&self) -> Result<Vec<u8>> {
-        let mut cfg = bincode::config();
-        cfg.little_endian();
+        let cfg = bincode_options();
         match cfg.serialize(self) {
             Ok(bytes) => Ok(bytes),
             Err(e) => Err(error_conv(e)),
@@ -106,22 +125,139 @@ impl PackedSuffixArray {
     }
 
     pub fn load<R: Read>(file: R) -> Result<Self> {
-        let mut cfg = bincode::config();
-        cfg.little_endian();
+        let cfg = bincode_options();
         match cfg.deserialize_from(file) {
-            Ok(packed) => Ok(packed),
+            Ok(packed) => Self::validate(packed),
             Err(e) => Err(error_conv(e)),
         }
     }
 
     pub fn load_bytes(bytes: &[u8]) -> Result<Self> {
-        let mut cfg = bincode::config();
-        cfg.little_endian();
+        let cfg = bincode_options();
         match cfg.deserialize(bytes) {
-            Ok(packed) => Ok(packed),
+            Ok(packed) => Self::validate(packed),
+            Err(e) => Err(error_conv(e)),
+        }
+    }
+
+    /// Test if the size of packed data matches the declared length, i.e. all
+    /// the chunks but the last (possibly trimmed) one are complete.
+    fn check_data_size(&self) -> bool {
+        let bits = sa_bits(self.length);
+        let u8_chunk_size = bits as usize * Packer::BLOCK_LEN / 8;
+        let chunk_count = ceiling_div(self.length as usize, Packer::BLOCK_LEN);
+        let max = chunk_count.checked_mul(u8_chunk_size);
+        let min = chunk_count.saturating_sub(1).checked_mul(u8_chunk_size);
+        match (min, max) {
+            (Some(min), Some(max)) => {
+                min <= self.data.len() && self.data.len() <= max
+            }
+            _ => false,
+        }
+    }
+
+    pub fn validate(packed: Self) -> Result<Self> {
+        use std::convert::TryFrom;
+
+        let length = usize::try_from(packed.length).ok();
+        if packed.magic != MAGIC_CSA4 {
+            Err(Error::new(ErrorKind::InvalidData, "bad magic number"))
+        } else if !length.is_some_and(|n| n > 0 && n - 1 <= MAX_LENGTH) {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                "bad suffix array length",
+            ))
+        } else if !packed.check_data_size() {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                "inconsistent suffix array data size",
+            ))
+        } else {
+            Ok(packed)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackedIndex<'a> {
+    magic: u32,
+    encoding: u8,
+    text: Cow<'a, [u8]>,
+    sa: PackedSuffixArray,
+}
+
+impl<'a> PackedIndex<'a> {
+    pub fn new(s: &'a [u8], sa: &[u32]) -> Result<Self> {
+        #[cfg(feature = "zstd")]
+        let (encoding, text) = (TEXT_ZSTD, Cow::from(zstd::encode_all(s, 0)?));
+        #[cfg(not(feature = "zstd"))]
+        let (encoding, text) = (TEXT_RAW, Cow::from(s));
+
+        Ok(PackedIndex {
+            magic: MAGIC_TEXT,
+            encoding,
+            text,
+            sa: PackedSuffixArray::from_sa(sa),
+        })
+    }
+
+    pub fn into_parts(self) -> Result<(Vec<u8>, Vec<u32>)> {
+        // the validated suffix array bounds the length of the byte string,
+        // so that the decompression could not blow up.
+        let expected = self.sa.length as usize - 1;
+        let text = match self.encoding {
+            TEXT_RAW => self.text.into_owned(),
+            #[cfg(feature = "zstd")]
+            TEXT_ZSTD => {
+                let limit = expected as u64 + 1;
+                let mut text = Vec::new();
+                zstd::Decoder::new(&self.text[..])?
+                    .take(limit)
+                    .read_to_end(&mut text)?;
+                text
+            }
+            #[cfg(not(feature = "zstd"))]
+            TEXT_ZSTD => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "zstd compressed text requires the zstd feature",
+                ))
+            }
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "unknown text encoding",
+                ))
+            }
+        };
+        if text.len() != expected {
+            let found = text.len();
+            return Err(SaError::LengthMismatch { expected, found }.into());
+        }
+        let sa = self.sa.into_sa_of(text.len())?;
+        Ok((text, sa))
+    }
+
+    pub fn dump<W: Write>(&self, file: W) -> Result<()> {
+        let cfg = bincode_options();
+        match cfg.serialize_into(file, self) {
+            Ok(_) => Ok(()),
             Err(e) => Err(error_conv(e)),
         }
     }
+
+    pub fn load<R: Read>(file: R) -> Result<Self> {
+        let cfg = bincode_options();
+        let index: Self = match cfg.deserialize_from(file) {
+            Ok(index) => index,
+            Err(e) => return Err(error_conv(e)),
+        };
+        if index.magic != MAGIC_TEXT {
+            return Err(Error::new(ErrorKind::InvalidData, "bad magic number"));
+        }
+        let sa = PackedSuffixArray::validate(index.sa)?;
+        Ok(PackedIndex { sa, ..index })
+    }
 }
 
 fn sa_bits(length: u32) -> u8 {
@@ -132,16 +268,22 @@ fn ceiling_div(x: usize, y: usize) -> usize {
     x / y + usize::from(x % y != 0)
 }
 
-fn error_conv(err: bincode::Error) -> std::io::Error {
+pub fn bincode_options() -> impl Options {
+    bincode::options()
+        .with_fixint_encoding()
+        .with_little_endian()
+        .allow_trailing_bytes()
+}
+
+pub fn error_conv(err: bincode::Error) -> std::io::Error {
     use bincode::ErrorKind as BincodeErrorKind;
     use std::io::{Error, ErrorKind as IoErrorKind};
 
     match *err {
         BincodeErrorKind::Io(e) => e,
-        BincodeErrorKind::SizeLimit => {
-            Error::new(IoErrorKind::Other, BincodeErrorKind::SizeLimit)
+        BincodeErrorKind::SizeLimit | BincodeErrorKind::Custom(_) => {
+            Error::new(IoErrorKind::Other, err)
         }
-        BincodeErrorKind::Custom(e) => Error::new(IoErrorKind::Other, e),
-        e => Error::new(IoErrorKind::InvalidData, e),
+        _ => Error::new(IoErrorKind::InvalidData, err),
     }
 }
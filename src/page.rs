@@ -0,0 +1,11 @@
+/// A bounded page of the unsorted occurrences of a pattern, see
+/// `SuffixArray::search_page`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Page<'a> {
+    /// Positions of the occurrences in this page.
+    pub positions: &'a [u32],
+    /// Total number of the occurrences.
+    pub total: usize,
+    /// Offset of the next page, or `None` if this is the last page.
+    pub next: Option<usize>,
+}
@@ -0,0 +1,42 @@
+use std::ops::Range;
+
+use super::lce::Lce;
+use super::sais::sais_ints_mut;
+
+/// Find the leftmost longest palindromic sub-string.
+///
+/// The byte string and its reverse are concatenated with a unique separator,
+/// then the palindromes around each center are extended by the longest
+/// common extension of the suffix after the center and the reversed prefix
+/// before it.
+pub fn longest_palindrome(s: &[u8]) -> Range<usize> {
+    let n = s.len();
+    if n == 0 {
+        return 0..0;
+    }
+
+    // bytes are shifted by one to leave 0 for the separator.
+    let mut t = Vec::with_capacity(2 * n + 1);
+    t.extend(s.iter().map(|&c| c as u32 + 1));
+    t.push(0);
+    t.extend(s.iter().rev().map(|&c| c as u32 + 1));
+    let mut sa = vec![0; t.len() + 1];
+    sais_ints_mut(&mut t[..], 257, &mut sa[..]);
+    let lce = Lce::new(&t[..], &sa[..]);
+
+    // s[i - 1 - k] is at the reversed position n + 1 + (n - i) + k.
+    let reversed = |i: usize| 2 * n + 1 - i;
+    let mut best = 0..1;
+    for i in 0..n {
+        let odd = lce.query(i, reversed(i + 1));
+        let odd = i + 1 - odd..i + odd;
+        let even = if i > 0 { lce.query(i, reversed(i)) } else { 0 };
+        let even = i - even..i + even;
+        for range in [even, odd].iter() {
+            if range.len() > best.len() {
+                best = range.clone();
+            }
+        }
+    }
+    best
+}
@@ -0,0 +1,31 @@
+/// Strategy of searching a pattern, see `SuffixArray::explain`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Strategy {
+    /// Scan the byte string directly, as it is not longer than the naive
+    /// search length.
+    Scan,
+    /// Answer at once, as the narrowed range of suffixes is empty, or the
+    /// pattern is rejected by the prefix filter.
+    Empty,
+    /// Binary search the narrowed range of suffixes by comparing the leading
+    /// bytes packed into words, as the pattern is short.
+    PackedBinarySearch,
+    /// Binary search the narrowed range of suffixes by comparing slices.
+    BinarySearch,
+}
+
+/// Description of how a pattern is searched by `contains` and
+/// `search_all_sorted`, see `SuffixArray::explain`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlanDescription {
+    /// The chosen strategy.
+    pub strategy: Strategy,
+    /// Number of leading bytes of the pattern looked up in the bucket
+    /// pointers, or 0 if they are not used.
+    pub bucket_bytes: usize,
+    /// Whether the fast search samples narrow the range.
+    pub fast_search: bool,
+    /// Number of suffixes left after narrowing, or all of them including the
+    /// empty one if the byte string is scanned.
+    pub candidates: usize,
+}
@@ -0,0 +1,105 @@
+use super::bitvec::RsBitVec;
+use super::warm::touch;
+
+/// Kind of LCP array to keep, see `SuffixArray::enable_lcp_with`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LcpKind {
+    /// Plain LCP array, 4 bytes per byte with O(1) access.
+    Plain,
+    /// Permuted LCP array encoded in about 2.125 bits per byte with
+    /// O(log n) access.
+    Compressed,
+}
+
+/// LCP array kept by the suffix array.
+#[derive(Debug, Clone)]
+pub enum StoredLcp {
+    Plain(Vec<u32>),
+    Compressed(Plcp),
+}
+
+impl StoredLcp {
+    /// Heap memory taken by the LCP array, in bytes.
+    pub fn heap_size(&self) -> usize {
+        match self {
+            StoredLcp::Plain(lcp) => lcp.capacity() * 4,
+            StoredLcp::Compressed(plcp) => plcp.heap_size(),
+        }
+    }
+
+    /// Touch every page of the LCP array, returning the number of pages.
+    pub fn warm(&self) -> usize {
+        match self {
+            StoredLcp::Plain(lcp) => touch(lcp),
+            StoredLcp::Compressed(plcp) => plcp.bits.warm(),
+        }
+    }
+}
+
+/// Permuted LCP array in text order, encoded in 2n bits.
+///
+/// Since `plcp[i] + i` is non-decreasing, the i-th one bit is placed at
+/// `plcp[i] + 2i`, with the gaps in between filled with zero bits.
+#[derive(Debug, Clone)]
+pub struct Plcp {
+    bits: RsBitVec,
+}
+
+impl Plcp {
+    /// Encode the permuted LCP array using the Phi algorithm.
+    pub fn new(s: &[u8], sa: &[u32]) -> Self {
+        let mut plcp = Plcp {
+            bits: RsBitVec::default(),
+        };
+        plcp.rebuild(s, sa);
+        plcp
+    }
+
+    /// Encode the permuted LCP array of another suffix array, reusing the
+    /// allocations.
+    pub fn rebuild(&mut self, s: &[u8], sa: &[u32]) {
+        let n = s.len();
+
+        // phi[sa[i]] = sa[i - 1], then overwritten by plcp in text order.
+        let mut phi = vec![0u32; n + 1];
+        for i in 1..sa.len() {
+            phi[sa[i] as usize] = sa[i - 1];
+        }
+        let mut h = 0;
+        for p in 0..n {
+            let q = phi[p] as usize;
+            while p + h < n && q + h < n && s[p + h] == s[q + h] {
+                h += 1;
+            }
+            phi[p] = h as u32;
+            h = h.saturating_sub(1);
+        }
+
+        let ones = phi[..n].iter().enumerate();
+        let ones = ones.map(|(p, &h)| h as usize + 2 * p);
+        self.bits.assign(2 * n + 1, ones);
+    }
+
+    /// Get the longest common prefix length of the suffix at `pos` and the
+    /// suffix before it, where `pos` is less than the length of byte string.
+    pub fn get(&self, pos: usize) -> u32 {
+        (self.bits.select1(pos).unwrap() - 2 * pos) as u32
+    }
+
+    /// Decode the LCP array of the suffix array.
+    pub fn to_lcp(&self, sa: &[u32]) -> Vec<u32> {
+        let n = sa.len() - 1;
+        let mut lcp = vec![0; sa.len()];
+        for (r, &p) in sa.iter().enumerate() {
+            if (p as usize) < n {
+                lcp[r] = self.get(p as usize);
+            }
+        }
+        lcp
+    }
+
+    /// Heap memory taken by the encoding, in bytes.
+    pub fn heap_size(&self) -> usize {
+        self.bits.heap_size()
+    }
+}
@@ -0,0 +1,90 @@
+use std::convert::TryFrom;
+
+/// Position in one of the documents concatenated into a byte string, see
+/// `Documents`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    /// Index of the document.
+    pub doc: u32,
+    /// Offset in the document.
+    pub offset: u32,
+}
+
+/// Boundaries of the documents concatenated into a byte string, decoding
+/// the positions in the concatenation by binary search.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Documents {
+    // start of each document, followed by the total length.
+    starts: Vec<u32>,
+}
+
+impl Documents {
+    /// Boundaries of the consecutive documents of given lengths, or `None`
+    /// if the total length overflows `u32`.
+    pub fn new<I: IntoIterator<Item = usize>>(lengths: I) -> Option<Self> {
+        let mut starts = vec![0];
+        let mut total = 0u32;
+        for len in lengths {
+            total = total.checked_add(u32::try_from(len).ok()?)?;
+            starts.push(total);
+        }
+        Some(Documents { starts })
+    }
+
+    /// Concatenate the documents into a byte string together with their
+    /// boundaries, or `None` if the total length overflows `u32`.
+    pub fn concat<D: AsRef<[u8]>>(docs: &[D]) -> Option<(Vec<u8>, Self)> {
+        let bounds = Self::new(docs.iter().map(|doc| doc.as_ref().len()))?;
+        let text = docs.iter().flat_map(|doc| doc.as_ref()).cloned().collect();
+        Some((text, bounds))
+    }
+
+    /// Number of the documents.
+    pub fn len(&self) -> usize {
+        self.starts.len() - 1
+    }
+
+    /// Test if there is no document.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Total length of the documents.
+    pub fn total_len(&self) -> usize {
+        self.starts[self.len()] as usize
+    }
+
+    /// Range of the i-th document in the concatenation.
+    pub fn range(&self, doc: usize) -> Option<std::ops::Range<usize>> {
+        let start = *self.starts.get(doc)? as usize;
+        let end = *self.starts.get(doc + 1)? as usize;
+        Some(start..end)
+    }
+
+    /// Decode the position in the concatenation, or `None` if it is out of
+    /// bounds.
+    ///
+    /// The empty documents are skipped, so that the offset is always less
+    /// than the length of the document.
+    pub fn locate(&self, pos: u32) -> Option<Position> {
+        if pos >= self.starts[self.len()] {
+            return None;
+        }
+        let doc = self.starts.partition_point(|&start| start <= pos) - 1;
+        Some(Position {
+            doc: doc as u32,
+            offset: pos - self.starts[doc],
+        })
+    }
+
+    /// Decode the position of a match of given length, or `None` if it is
+    /// out of bounds or crosses the end of its document.
+    pub fn locate_match(&self, pos: u32, len: usize) -> Option<Position> {
+        let found = self.locate(pos)?;
+        let range = self.range(found.doc as usize)?;
+        if range.end - (pos as usize) < len {
+            return None;
+        }
+        Some(found)
+    }
+}
@@ -0,0 +1,178 @@
+use std::io::{prelude::*, Error, ErrorKind, Result};
+
+use bincode::Options;
+use bitpacking::{BitPacker, BitPacker4x as Packer};
+use serde::{Deserialize, Serialize};
+
+use super::packed_sa::{bincode_options, error_conv};
+
+// Little endian of b"SApl", i.e. the Posting Lists using BitPacker4x.
+const MAGIC_POSTINGS: u32 = 1819296083;
+
+/// Posting lists of the occurrences of a dictionary of patterns in text
+/// order, delta encoded and bitpacked in blocks of 128, see
+/// `SuffixArray::posting_lists`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostingLists {
+    magic: u32,
+    patterns: Vec<Vec<u8>>,
+    lists: Vec<PostingList>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct PostingList {
+    length: u32,
+    // number of bits of the deltas in each block.
+    bits: Vec<u8>,
+    data: Vec<u8>,
+}
+
+impl PostingLists {
+    /// Encode the sorted occurrences of each pattern.
+    pub(crate) fn new<I>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = (Vec<u8>, Vec<u32>)>,
+    {
+        let (patterns, lists) = entries
+            .into_iter()
+            .map(|(pat, positions)| (pat, PostingList::encode(&positions)))
+            .unzip();
+        PostingLists {
+            magic: MAGIC_POSTINGS,
+            patterns,
+            lists,
+        }
+    }
+
+    /// Number of patterns.
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Test if there is no pattern.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// The i-th pattern of the dictionary.
+    pub fn pattern(&self, i: usize) -> Option<&[u8]> {
+        self.patterns.get(i).map(|pat| &pat[..])
+    }
+
+    /// Index of the first occurrence of given pattern in the dictionary.
+    pub fn find(&self, pat: &[u8]) -> Option<usize> {
+        self.patterns.iter().position(|p| &p[..] == pat)
+    }
+
+    /// Number of occurrences of the i-th pattern.
+    pub fn count(&self, i: usize) -> Option<usize> {
+        self.lists.get(i).map(|list| list.length as usize)
+    }
+
+    /// Decode the occurrences of the i-th pattern in text order.
+    pub fn positions(&self, i: usize) -> Option<Vec<u32>> {
+        self.lists.get(i).map(PostingList::decode)
+    }
+
+    /// Heap memory taken by the encoded posting lists, in bytes.
+    pub fn heap_size(&self) -> usize {
+        let lists = self.lists.iter();
+        let patterns = self.patterns.iter();
+        lists
+            .map(|l| l.bits.capacity() + l.data.capacity())
+            .sum::<usize>()
+            + patterns.map(|p| p.capacity()).sum::<usize>()
+    }
+
+    /// Dump the posting lists to writer.
+    pub fn dump<W: Write>(&self, file: W) -> Result<()> {
+        let cfg = bincode_options();
+        match cfg.serialize_into(file, self) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(error_conv(e)),
+        }
+    }
+
+    /// Load the posting lists from reader.
+    pub fn load<R: Read>(file: R) -> Result<Self> {
+        let cfg = bincode_options();
+        let postings: Self = match cfg.deserialize_from(file) {
+            Ok(postings) => postings,
+            Err(e) => return Err(error_conv(e)),
+        };
+        if postings.magic != MAGIC_POSTINGS {
+            Err(Error::new(ErrorKind::InvalidData, "bad magic number"))
+        } else if postings.patterns.len() != postings.lists.len() {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                "inconsistent number of posting lists",
+            ))
+        } else if !postings.lists.iter().all(PostingList::check_data_size) {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                "inconsistent posting list data size",
+            ))
+        } else {
+            Ok(postings)
+        }
+    }
+}
+
+impl PostingList {
+    fn encode(positions: &[u32]) -> Self {
+        let packer = Packer::new();
+        let mut bits = Vec::new();
+        let mut data = Vec::new();
+        let mut buf = vec![0u8; 4 * Packer::BLOCK_LEN];
+        let mut initial = 0;
+        for block in positions.chunks(Packer::BLOCK_LEN) {
+            // pad the last block by repeating the last position.
+            let mut chunk = [*block.last().unwrap(); Packer::BLOCK_LEN];
+            chunk[..block.len()].copy_from_slice(block);
+            let n = packer.num_bits_sorted(initial, &chunk[..]);
+            let size = packer.compress_sorted(initial, &chunk[..], &mut buf, n);
+            bits.push(n);
+            data.extend_from_slice(&buf[..size]);
+            initial = chunk[Packer::BLOCK_LEN - 1];
+        }
+
+        PostingList {
+            length: positions.len() as u32,
+            bits,
+            data,
+        }
+    }
+
+    fn decode(&self) -> Vec<u32> {
+        let packer = Packer::new();
+        let mut positions = Vec::with_capacity(self.length as usize);
+        let mut chunk = [0u32; Packer::BLOCK_LEN];
+        let mut data = &self.data[..];
+        let mut initial = 0;
+        for &n in self.bits.iter() {
+            let size = block_size(n);
+            packer.decompress_sorted(initial, &data[..size], &mut chunk, n);
+            let remain = self.length as usize - positions.len();
+            let len = Ord::min(remain, Packer::BLOCK_LEN);
+            positions.extend_from_slice(&chunk[..len]);
+            initial = chunk[Packer::BLOCK_LEN - 1];
+            data = &data[size..];
+        }
+        positions
+    }
+
+    /// Test if the number of blocks matches the length, and the size of
+    /// packed data matches the number of bits of the blocks.
+    fn check_data_size(&self) -> bool {
+        let blocks = (self.length as usize).div_ceil(Packer::BLOCK_LEN);
+        let size = self.bits.iter().map(|&n| block_size(n)).sum::<usize>();
+        self.bits.len() == blocks
+            && self.bits.iter().all(|&n| n <= 32)
+            && self.data.len() == size
+    }
+}
+
+/// Size of a block packed in the given number of bits.
+fn block_size(bits: u8) -> usize {
+    bits as usize * Packer::BLOCK_LEN / 8
+}
@@ -0,0 +1,44 @@
+use super::stats::Algorithm;
+
+/// Phases of suffix array construction, reported to the progress callback
+/// of `SuffixArray::with_progress` as each of them begins.
+///
+/// Only the SA-IS algorithm reports the phases in between, the other
+/// algorithms are opaque.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// The construction algorithm is selected.
+    Selected(Algorithm),
+    /// Classify and count the characters of the (reduced) string of given
+    /// length, at given recursion depth.
+    BucketCounting {
+        /// Recursion depth, 0 for the input itself.
+        depth: usize,
+        /// Length of the string at this depth.
+        length: usize,
+    },
+    /// Sort the LMS sub-strings by induced sorting.
+    LmsSorting {
+        /// Recursion depth.
+        depth: usize,
+    },
+    /// Name the sorted LMS sub-strings to build the reduced string.
+    Naming {
+        /// Recursion depth.
+        depth: usize,
+    },
+    /// Sort the suffixes of the reduced string of given length recursively.
+    Recursion {
+        /// Recursion depth of the reduced string.
+        depth: usize,
+        /// Length of the reduced string.
+        length: usize,
+    },
+    /// Induce the suffix array from the sorted LMS suffixes.
+    Inducing {
+        /// Recursion depth.
+        depth: usize,
+    },
+    /// The suffix array is constructed.
+    Done,
+}
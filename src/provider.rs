@@ -0,0 +1,270 @@
+use std::cmp::Ordering;
+#[cfg(any(unix, windows))]
+use std::fs::File;
+use std::io::{Error, ErrorKind, Result};
+#[cfg(any(unix, windows))]
+use std::path::Path;
+
+#[cfg(feature = "pack")]
+use super::packed_sa::PackedSuffixArray;
+
+/// Number of bytes read at once when comparing two suffixes.
+const COMPARE_CHUNK: usize = 256;
+
+/// Random access to a byte string that is not necessarily kept in memory,
+/// e.g. a file read by `pread`.
+pub trait TextProvider {
+    /// Length of the byte string.
+    fn len(&self) -> usize;
+
+    /// Test if the byte string is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Read exactly `buf.len()` bytes starting at `pos`, which are within
+    /// the byte string.
+    fn read_at(&self, pos: usize, buf: &mut [u8]) -> Result<()>;
+}
+
+impl TextProvider for [u8] {
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+
+    fn read_at(&self, pos: usize, buf: &mut [u8]) -> Result<()> {
+        match pos
+            .checked_add(buf.len())
+            .and_then(|end| self.get(pos..end))
+        {
+            Some(bytes) => {
+                buf.copy_from_slice(bytes);
+                Ok(())
+            }
+            None => Err(Error::from(ErrorKind::UnexpectedEof)),
+        }
+    }
+}
+
+impl<P: TextProvider + ?Sized> TextProvider for &P {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn read_at(&self, pos: usize, buf: &mut [u8]) -> Result<()> {
+        (**self).read_at(pos, buf)
+    }
+}
+
+/// File read by positioned reads, without changing the file cursor.
+///
+/// The length is queried once on opening, and the file should not be
+/// modified afterwards.
+#[cfg(any(unix, windows))]
+#[derive(Debug)]
+pub struct FileText {
+    file: File,
+    len: usize,
+}
+
+#[cfg(any(unix, windows))]
+impl FileText {
+    /// Open the file for positioned reads.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new(File::open(path)?)
+    }
+
+    /// Use the opened file for positioned reads.
+    pub fn new(file: File) -> Result<Self> {
+        use std::convert::TryFrom;
+
+        let len = usize::try_from(file.metadata()?.len()).map_err(|_| {
+            Error::new(ErrorKind::InvalidInput, "file too long")
+        })?;
+        Ok(FileText { file, len })
+    }
+
+    /// Take out the file.
+    pub fn into_inner(self) -> File {
+        self.file
+    }
+}
+
+#[cfg(any(unix, windows))]
+impl TextProvider for FileText {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn read_at(&self, pos: usize, mut buf: &mut [u8]) -> Result<()> {
+        #[cfg(unix)]
+        use std::os::unix::fs::FileExt;
+        #[cfg(windows)]
+        use std::os::windows::fs::FileExt;
+
+        let mut pos = pos as u64;
+        while !buf.is_empty() {
+            #[cfg(unix)]
+            let n = self.file.read_at(buf, pos);
+            #[cfg(windows)]
+            let n = self.file.seek_read(buf, pos);
+            match n {
+                Ok(0) => return Err(Error::from(ErrorKind::UnexpectedEof)),
+                Ok(n) => {
+                    buf = &mut buf[n..];
+                    pos += n as u64;
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Suffix array whose byte string is read on demand from a `TextProvider`,
+/// so that only the bytes touched by the comparisons are read.
+///
+/// The suffix array itself is kept in memory, taking 4 bytes per byte.
+#[derive(Debug, Clone)]
+pub struct ProvidedSuffixArray<P> {
+    text: P,
+    sa: Vec<u32>,
+}
+
+impl<P: TextProvider> ProvidedSuffixArray<P> {
+    /// Compose the byte string and its suffix array, e.g. taken out by
+    /// `SuffixArray::into_parts`.
+    ///
+    /// Only the shape of the suffix array is checked, i.e. it is a
+    /// permutation of the positions starting with the empty suffix, see
+    /// `check_integrity` for the complete check.
+    pub fn new(text: P, sa: Vec<u32>) -> Result<Self> {
+        let n = text.len();
+        let mut seen = vec![0u64; (n + 1).div_ceil(64)];
+        let shaped = sa.len() == n + 1
+            && sa[0] as usize == n
+            && sa.iter().all(|&p| {
+                let p = p as usize;
+                let fresh = p <= n && seen[p / 64] & (1 << (p % 64)) == 0;
+                if fresh {
+                    seen[p / 64] |= 1 << (p % 64);
+                }
+                fresh
+            });
+        if shaped {
+            Ok(ProvidedSuffixArray { text, sa })
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                "inconsistent suffix array",
+            ))
+        }
+    }
+
+    /// Load the suffix array dumped by `SuffixArray::dump` for the byte
+    /// string, checking its shape as `new`.
+    #[cfg(feature = "pack")]
+    pub fn load<R: std::io::Read>(text: P, file: R) -> Result<Self> {
+        let sa = PackedSuffixArray::load(file)?.into_sa_of(text.len())?;
+        Self::new(text, sa)
+    }
+
+    /// Length of the byte string.
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    /// Test if the byte string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// The provider of the byte string.
+    pub fn text(&self) -> &P {
+        &self.text
+    }
+
+    /// Take out the provider and the suffix array.
+    pub fn into_parts(self) -> (P, Vec<u32>) {
+        (self.text, self.sa)
+    }
+
+    /// Check that the suffixes are in order by reading the whole byte string
+    /// through the provider, chunk by chunk.
+    pub fn check_integrity(&self) -> Result<bool> {
+        let mut x = [0; COMPARE_CHUNK];
+        let mut y = [0; COMPARE_CHUNK];
+        for w in self.sa.windows(2) {
+            let (mut p, mut q) = (w[0] as usize, w[1] as usize);
+            loop {
+                let n = self.len();
+                let len = Ord::min(n - Ord::max(p, q), COMPARE_CHUNK);
+                self.text.read_at(p, &mut x[..len])?;
+                self.text.read_at(q, &mut y[..len])?;
+                match x[..len].cmp(&y[..len]) {
+                    Ordering::Less => break,
+                    Ordering::Greater => return Ok(false),
+                    Ordering::Equal if len < COMPARE_CHUNK => {
+                        // the shorter suffix is the smaller one.
+                        if p + len < n {
+                            return Ok(false);
+                        }
+                        break;
+                    }
+                    Ordering::Equal => {
+                        p += len;
+                        q += len;
+                    }
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Test if the byte string contains the pattern.
+    pub fn contains(&self, pat: &[u8]) -> Result<bool> {
+        Ok(!self.search_all(pat)?.is_empty())
+    }
+
+    /// Search for all the unsorted occurrences of the pattern (can overlap),
+    /// in the same order as `SuffixArray::search_all`.
+    pub fn search_all(&self, pat: &[u8]) -> Result<&[u32]> {
+        let mut buf = vec![0; pat.len()];
+        let start = self.partition_point(pat, &mut buf, Ordering::Less)?;
+        let end = self.partition_point(pat, &mut buf, Ordering::Equal)?;
+        Ok(&self.sa[start..end])
+    }
+
+    /// Find the first rank whose suffix, truncated to the pattern, compares
+    /// greater than `ord` to the pattern.
+    fn partition_point(
+        &self,
+        pat: &[u8],
+        buf: &mut [u8],
+        ord: Ordering,
+    ) -> Result<usize> {
+        let (mut lo, mut hi) = (0, self.sa.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.compare(self.sa[mid] as usize, pat, buf)? <= ord {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        Ok(lo)
+    }
+
+    /// Compare the suffix at `pos` truncated to the pattern with it.
+    fn compare(
+        &self,
+        pos: usize,
+        pat: &[u8],
+        buf: &mut [u8],
+    ) -> Result<Ordering> {
+        let len = Ord::min(self.len() - pos, pat.len());
+        self.text.read_at(pos, &mut buf[..len])?;
+        Ok(buf[..len].cmp(pat))
+    }
+}
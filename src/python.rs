@@ -0,0 +1,96 @@
+//! Python bindings, exposing the owned suffix arrays as the class
+//! `suffix_array.SuffixArray`.
+//!
+//! The extension module could be built by
+//! `cargo rustc --release --crate-type cdylib --features
+//! python,pyo3/extension-module`, then renamed to `suffix_array.so` (or
+//! `suffix_array.pyd` on Windows).
+//!
+//! ```python
+//! from suffix_array import SuffixArray
+//!
+//! sa = SuffixArray(b"splendid splendor")
+//! assert b"splend" in sa
+//! assert list(sa.search_all(b"splend")) == [0, 9]
+//! ```
+
+// the wrappers generated by `pymethods` convert the errors into themselves.
+#![allow(clippy::useless_conversion)]
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use super::sa::OwnedSuffixArray;
+use super::saca::MAX_LENGTH;
+
+/// Suffix array owning a copy of its byte string.
+#[pyclass(name = "SuffixArray", module = "suffix_array", frozen)]
+pub struct PySuffixArray {
+    sa: OwnedSuffixArray,
+}
+
+#[pymethods]
+impl PySuffixArray {
+    /// Construct the suffix array of a copy of the bytes.
+    #[new]
+    fn new(data: &[u8]) -> PyResult<Self> {
+        if data.len() > MAX_LENGTH {
+            return Err(PyValueError::new_err("byte string too long"));
+        }
+        Ok(PySuffixArray {
+            sa: OwnedSuffixArray::new(data.to_vec()),
+        })
+    }
+
+    /// Load the suffix array written by `dump`, checking its integrity.
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<Self> {
+        let sa = OwnedSuffixArray::load_owned_file(path)?;
+        Ok(PySuffixArray { sa })
+    }
+
+    /// Write the suffix array together with its byte string into the file.
+    fn dump(&self, path: &str) -> PyResult<()> {
+        Ok(self.sa.dump_with_text_file(path)?)
+    }
+
+    /// Get a copy of the underlying bytes.
+    fn text<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, self.sa.text())
+    }
+
+    fn __len__(&self) -> usize {
+        self.sa.len()
+    }
+
+    fn __contains__(&self, pat: &[u8]) -> bool {
+        self.sa.contains(pat)
+    }
+
+    /// Test if it contains the pattern.
+    fn contains(&self, pat: &[u8]) -> bool {
+        self.sa.contains(pat)
+    }
+
+    /// Search for all the occurrences of the pattern in text order, as an
+    /// `array.array` of type `I`, which could be wrapped by
+    /// `numpy.frombuffer(found, dtype=numpy.uint32)` without copying.
+    fn search_all<'py>(
+        &self,
+        py: Python<'py>,
+        pat: &[u8],
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let found = self.sa.search_all_sorted(pat);
+        let bytes: Vec<u8> =
+            found.iter().flat_map(|p| p.to_ne_bytes()).collect();
+        let array = py.import_bound("array")?.getattr("array")?;
+        array.call1(("I", PyBytes::new_bound(py, &bytes[..])))
+    }
+}
+
+/// The `suffix_array` extension module.
+#[pymodule]
+fn suffix_array(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySuffixArray>()
+}
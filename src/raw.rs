@@ -0,0 +1,95 @@
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+/// Number of entries converted at a time.
+const BLOCK_LEN: usize = 4096;
+
+/// Width of the entries in a raw suffix array file, i.e. the little endian
+/// array without any header nor the sentinel, as written by libdivsufsort
+/// (`saidx_t` or `saidx64_t`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RawWidth {
+    /// 32-bit entries.
+    U32,
+    /// 64-bit entries.
+    U64,
+}
+
+impl RawWidth {
+    fn bytes(self) -> usize {
+        match self {
+            RawWidth::U32 => 4,
+            RawWidth::U64 => 8,
+        }
+    }
+}
+
+/// Write the suffix array (without the sentinel) as raw little endian array.
+pub fn write_raw<W: Write>(
+    mut file: W,
+    sa: &[u32],
+    width: RawWidth,
+) -> Result<()> {
+    let mut buf = Vec::with_capacity(BLOCK_LEN * width.bytes());
+    for block in sa.chunks(BLOCK_LEN) {
+        buf.clear();
+        for &p in block {
+            match width {
+                RawWidth::U32 => buf.extend_from_slice(&p.to_le_bytes()),
+                RawWidth::U64 => {
+                    buf.extend_from_slice(&(p as u64).to_le_bytes())
+                }
+            }
+        }
+        file.write_all(&buf[..])?;
+    }
+    file.flush()
+}
+
+/// Read the raw little endian array of `n` entries, and prepend the sentinel
+/// `n` to it.
+///
+/// The entries are not checked, except that they must fit in `u32`.
+pub fn read_raw<R: Read>(
+    mut file: R,
+    n: usize,
+    width: RawWidth,
+) -> Result<Vec<u32>> {
+    let mut sa = Vec::with_capacity(n + 1);
+    sa.push(n as u32);
+
+    let mut buf = vec![0u8; BLOCK_LEN * width.bytes()];
+    let mut remain = n;
+    while remain > 0 {
+        let len = Ord::min(remain, BLOCK_LEN);
+        let bytes = &mut buf[..len * width.bytes()];
+        file.read_exact(bytes)?;
+        for entry in bytes.chunks(width.bytes()) {
+            let p = match width {
+                RawWidth::U32 => {
+                    u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]])
+                }
+                RawWidth::U64 => {
+                    let mut x = [0u8; 8];
+                    x.copy_from_slice(entry);
+                    u32::try_from(u64::from_le_bytes(x)).map_err(|_| {
+                        Error::new(
+                            ErrorKind::InvalidData,
+                            "suffix array entry out of range",
+                        )
+                    })?
+                }
+            };
+            sa.push(p);
+        }
+        remain -= len;
+    }
+
+    if file.read(&mut buf[..1])? != 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "trailing data after raw suffix array",
+        ));
+    }
+    Ok(sa)
+}
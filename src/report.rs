@@ -0,0 +1,192 @@
+use std::ops::Range;
+use std::time::Instant;
+
+/// Number of candidates verified between the checks of the deadline.
+const DEADLINE_INTERVAL: usize = 1024;
+
+/// Options of reporting the occurrences found by
+/// `SuffixArray::search_all_with`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SearchOptions {
+    /// Base subtracted from the positions, e.g. the start of a record in a
+    /// concatenation. The occurrences starting before it are skipped.
+    pub base: usize,
+    /// Report the occurrences as ranges of bytes, rather than offsets.
+    pub spans: bool,
+}
+
+/// Occurrences of a pattern in text order, relative to the base, see
+/// `SuffixArray::search_all_with`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Occurrences {
+    /// Offsets of the first bytes.
+    Offsets(Vec<usize>),
+    /// Ranges of the bytes.
+    Spans(Vec<Range<usize>>),
+}
+
+impl Occurrences {
+    pub(crate) fn new(
+        positions: &[u32],
+        len: usize,
+        opts: SearchOptions,
+    ) -> Self {
+        let offsets = positions
+            .iter()
+            .filter_map(|&p| (p as usize).checked_sub(opts.base));
+        if opts.spans {
+            Occurrences::Spans(offsets.map(|i| i..i + len).collect())
+        } else {
+            Occurrences::Offsets(offsets.collect())
+        }
+    }
+
+    /// Number of the occurrences.
+    pub fn len(&self) -> usize {
+        match self {
+            Occurrences::Offsets(offsets) => offsets.len(),
+            Occurrences::Spans(spans) => spans.len(),
+        }
+    }
+
+    /// Test if there is no occurrence.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over the offsets of the first bytes in text order.
+    pub fn offsets(&self) -> impl Iterator<Item = usize> + '_ {
+        let (offsets, spans) = match self {
+            Occurrences::Offsets(offsets) => (&offsets[..], &[][..]),
+            Occurrences::Spans(spans) => (&[][..], &spans[..]),
+        };
+        let starts = spans.iter().map(|span| span.start);
+        offsets.iter().cloned().chain(starts)
+    }
+
+    /// Lazily keep the offsets of `a` with some offset of `b` at most
+    /// `window` bytes away, e.g. two terms occurring near each other.
+    ///
+    /// Both `a` and `b` must be in text order, like `offsets` or
+    /// `SuffixArray::search_all_sorted`.
+    pub fn and<A, B>(a: A, b: B, window: usize) -> impl Iterator<Item = usize>
+    where
+        A: IntoIterator<Item = usize>,
+        B: IntoIterator<Item = usize>,
+    {
+        let mut b = b.into_iter().peekable();
+        a.into_iter().filter(move |&x| {
+            // the skipped ones are too far behind the later ones as well.
+            while b.next_if(|&y| y.saturating_add(window) < x).is_some() {}
+            b.peek().is_some_and(|&y| y <= x.saturating_add(window))
+        })
+    }
+
+    /// Lazily merge the offsets of `a` and `b` in text order, each reported
+    /// once even if found in both.
+    ///
+    /// Both `a` and `b` must be in text order.
+    pub fn or<A, B>(a: A, b: B) -> impl Iterator<Item = usize>
+    where
+        A: IntoIterator<Item = usize>,
+        B: IntoIterator<Item = usize>,
+    {
+        let mut a = a.into_iter().peekable();
+        let mut b = b.into_iter().peekable();
+        std::iter::from_fn(move || match (a.peek(), b.peek()) {
+            (Some(&x), Some(&y)) => {
+                if x <= y {
+                    b.next_if_eq(&x);
+                    a.next()
+                } else {
+                    b.next()
+                }
+            }
+            (Some(_), None) => a.next(),
+            (None, _) => b.next(),
+        })
+    }
+
+    /// Lazily skip the offsets of `a` within the range, e.g. a region of
+    /// the byte string excluded from the query.
+    pub fn not_within<A>(
+        a: A,
+        range: Range<usize>,
+    ) -> impl Iterator<Item = usize>
+    where
+        A: IntoIterator<Item = usize>,
+    {
+        a.into_iter().filter(move |x| !range.contains(x))
+    }
+}
+
+/// Occurrences found before a deadline, see
+/// `SuffixArray::search_all_deadline`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PartialMatches {
+    /// Positions of the occurrences found.
+    pub positions: Vec<u32>,
+    /// Whether all the occurrences are found before the deadline.
+    pub complete: bool,
+}
+
+/// Occurrences of a pattern together with the ranks of the matched
+/// suffixes, see `SuffixArray::search_full`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SearchHit<'a> {
+    /// Ranks of the matched suffixes, an empty range at the insertion point
+    /// if there is no occurrence.
+    pub sa_range: Range<usize>,
+    /// Unsorted positions of the occurrences, borrowed from the suffix
+    /// array.
+    pub positions: &'a [u32],
+}
+
+impl SearchHit<'_> {
+    /// Number of the occurrences.
+    pub fn count(&self) -> usize {
+        self.sa_range.len()
+    }
+
+    /// Test if there is no occurrence.
+    pub fn is_empty(&self) -> bool {
+        self.sa_range.is_empty()
+    }
+
+    /// Get the occurrences in text order.
+    pub fn sorted(&self) -> Vec<u32> {
+        let mut positions = Vec::from(self.positions);
+        positions.sort_unstable();
+        positions
+    }
+}
+
+/// Verify the candidates until the deadline passes, which is checked before
+/// the first candidate and then periodically.
+pub fn collect_until<I, F>(
+    candidates: I,
+    mut verify: F,
+    deadline: Option<Instant>,
+) -> PartialMatches
+where
+    I: Iterator<Item = usize>,
+    F: FnMut(usize) -> bool,
+{
+    let mut positions = Vec::new();
+    for (i, p) in candidates.enumerate() {
+        let expired = || deadline.is_some_and(|d| Instant::now() >= d);
+        if i % DEADLINE_INTERVAL == 0 && expired() {
+            return PartialMatches {
+                positions,
+                complete: false,
+            };
+        }
+        if verify(p) {
+            positions.push(p as u32);
+        }
+    }
+    PartialMatches {
+        positions,
+        complete: true,
+    }
+}
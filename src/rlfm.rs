@@ -0,0 +1,205 @@
+use super::saca::saca;
+
+/// The BWT symbol standing for the sentinel.
+const SENTINEL: u16 = 256;
+
+/// Run-length compressed FM-index for highly repetitive byte string.
+///
+/// Only the runs of the Burrows-Wheeler transform and the suffix array
+/// samples at the run boundaries are kept, so the memory usage is
+/// proportional to the number of runs rather than the length of the byte
+/// string. The byte string itself is not needed for searching.
+#[derive(Debug, Clone)]
+pub struct RlfmIndex {
+    len: usize,
+    // first row of each run, terminated by the total number of rows.
+    run_starts: Vec<u32>,
+    run_chars: Vec<u16>,
+    // for each byte, the runs of that byte and the occurrences before them.
+    char_runs: Vec<Vec<u32>>,
+    char_ranks: Vec<Vec<u32>>,
+    // number of rows starting with a smaller symbol, for each byte.
+    lower: Vec<u32>,
+    // suffix array sample at the last row of each run.
+    run_ends_sa: Vec<u32>,
+    // suffix array sample at the last row.
+    last_sa: u32,
+    // (SA[i], SA[i - 1]) for the first row i > 0 of each run, sorted.
+    phi: Vec<(u32, u32)>,
+}
+
+impl RlfmIndex {
+    /// Construct run-length compressed FM-index for given byte string.
+    pub fn new(s: &[u8]) -> Self {
+        let mut sa = vec![0; s.len() + 1];
+        saca(s, &mut sa[..]);
+        Self::from_parts(s, &sa[..])
+    }
+
+    /// Construct from byte string and its suffix array.
+    pub(crate) fn from_parts(s: &[u8], sa: &[u32]) -> Self {
+        let bwt = |i: usize| {
+            if sa[i] == 0 {
+                SENTINEL
+            } else {
+                s[sa[i] as usize - 1] as u16
+            }
+        };
+
+        let mut run_starts = Vec::new();
+        let mut run_chars = Vec::new();
+        let mut run_ends_sa = Vec::new();
+        let mut phi = Vec::new();
+        for i in 0..sa.len() {
+            let c = bwt(i);
+            if i == 0 || c != bwt(i - 1) {
+                if i > 0 {
+                    run_ends_sa.push(sa[i - 1]);
+                    phi.push((sa[i], sa[i - 1]));
+                }
+                run_starts.push(i as u32);
+                run_chars.push(c);
+            }
+        }
+        run_ends_sa.push(sa[sa.len() - 1]);
+        run_starts.push(sa.len() as u32);
+        phi.sort_unstable();
+
+        let mut char_runs = vec![Vec::new(); 256];
+        let mut char_ranks = vec![Vec::new(); 256];
+        let mut counts = vec![0u32; 256];
+        for r in 0..run_chars.len() {
+            let c = run_chars[r];
+            if c != SENTINEL {
+                let c = c as usize;
+                char_runs[c].push(r as u32);
+                char_ranks[c].push(counts[c]);
+                counts[c] += run_starts[r + 1] - run_starts[r];
+            }
+        }
+
+        let mut lower = vec![0; 256];
+        let mut sum = 1;
+        for c in 0..256 {
+            lower[c] = sum;
+            sum += counts[c];
+        }
+
+        RlfmIndex {
+            len: s.len(),
+            run_starts,
+            run_chars,
+            char_runs,
+            char_ranks,
+            lower,
+            run_ends_sa,
+            last_sa: sa[sa.len() - 1],
+            phi,
+        }
+    }
+
+    /// Length of the indexed byte string.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Test if the indexed byte string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of runs in the Burrows-Wheeler transform.
+    pub fn runs(&self) -> usize {
+        self.run_chars.len()
+    }
+
+    /// Count the occurrences of given pattern (can overlap).
+    pub fn count(&self, pat: &[u8]) -> usize {
+        match self.backward_search(pat) {
+            Some((sp, ep, _)) => ep - sp,
+            None => 0,
+        }
+    }
+
+    /// Locate all the occurrences of given pattern (can overlap), in the
+    /// order of the corresponding suffixes.
+    pub fn locate(&self, pat: &[u8]) -> Vec<u32> {
+        let (sp, ep, mut p) = match self.backward_search(pat) {
+            Some(found) => found,
+            None => return Vec::new(),
+        };
+
+        let mut result = Vec::with_capacity(ep - sp);
+        result.push(p);
+        for _ in sp + 1..ep {
+            p = self.phi(p);
+            result.push(p);
+        }
+        result.reverse();
+        result
+    }
+
+    /// Find the rows `sp..ep` prefixed by the pattern, together with the
+    /// suffix array sample at row `ep - 1`.
+    fn backward_search(&self, pat: &[u8]) -> Option<(usize, usize, u32)> {
+        let mut sp = 0;
+        let mut ep = self.len + 1;
+        let mut p = self.last_sa;
+        for &c in pat.iter().rev() {
+            let r = self.run_of(ep - 1);
+            if self.run_chars[r] == c as u16 {
+                // the last row is preceded by c, just step backward.
+                p -= 1;
+            } else {
+                // jump to the end of the last run of c within the range.
+                let runs = &self.char_runs[c as usize];
+                let k = runs.partition_point(|&x| x as usize <= r);
+                if k == 0 {
+                    return None;
+                }
+                let last = runs[k - 1] as usize;
+                if (self.run_starts[last + 1] as usize) <= sp {
+                    return None;
+                }
+                p = self.run_ends_sa[last] - 1;
+            }
+
+            let lower = self.lower[c as usize] as usize;
+            sp = lower + self.rank(c, sp);
+            ep = lower + self.rank(c, ep);
+            if sp >= ep {
+                return None;
+            }
+        }
+        Some((sp, ep, p))
+    }
+
+    /// Number of occurrences of `c` in the rows before `i`.
+    fn rank(&self, c: u8, i: usize) -> usize {
+        if i == 0 {
+            return 0;
+        }
+        let r = self.run_of(i - 1);
+        let runs = &self.char_runs[c as usize];
+        let k = runs.partition_point(|&x| x as usize <= r);
+        if k == 0 {
+            return 0;
+        }
+        let last = runs[k - 1] as usize;
+        let before = self.char_ranks[c as usize][k - 1] as usize;
+        let end = Ord::min(i, self.run_starts[last + 1] as usize);
+        before + end - self.run_starts[last] as usize
+    }
+
+    /// Find the run containing row `i`.
+    fn run_of(&self, i: usize) -> usize {
+        self.run_starts.partition_point(|&x| x as usize <= i) - 1
+    }
+
+    /// Map SA[i] to SA[i - 1].
+    fn phi(&self, p: u32) -> u32 {
+        let k = self.phi.partition_point(|&(x, _)| x <= p);
+        let (x, y) = self.phi[k - 1];
+        y + (p - x)
+    }
+}
@@ -0,0 +1,64 @@
+use super::overlap::suffix_prefix;
+use super::sa::SuffixArray;
+use super::storage::TextStorage;
+
+/// Find the starting position of the lexicographically smallest rotation,
+/// the leftmost one if there are ties.
+///
+/// Two candidate starts are compared along the doubled byte string, and
+/// the loser skips past the compared bytes, since no start among them
+/// could be smaller, taking O(n) time and O(1) space.
+pub fn min_rotation(s: &[u8]) -> usize {
+    let n = s.len();
+    let (mut i, mut j, mut k) = (0, 1, 0);
+    while i < n && j < n && k < n {
+        let (a, b) = (s[(i + k) % n], s[(j + k) % n]);
+        if a == b {
+            k += 1;
+            continue;
+        }
+        if a > b {
+            i += k + 1;
+        } else {
+            j += k + 1;
+        }
+        if i == j {
+            j += 1;
+        }
+        k = 0;
+    }
+    Ord::min(i, j) % Ord::max(n, 1)
+}
+
+/// Search for all the occurrences of pattern in the circular byte string
+/// in text order, including those wrapping around the end.
+pub fn search_circular<T: TextStorage>(
+    sa: &SuffixArray<T>,
+    pat: &[u8],
+) -> Vec<u32> {
+    let s = sa.text();
+    let n = s.len();
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let m = pat.len();
+    let mut result: Vec<u32> = sa
+        .search_all(pat)
+        .iter()
+        .cloned()
+        .filter(|&p| (p as usize) < n)
+        .collect();
+
+    // the wrapping ones start at the suffixes which are prefixes of pattern.
+    for len in suffix_prefix(sa, pat, 1) {
+        if len < m {
+            let mut rest = pat[len..].iter().enumerate();
+            if rest.all(|(i, &c)| s[i % n] == c) {
+                result.push((n - len) as u32);
+            }
+        }
+    }
+    result.sort_unstable();
+    result
+}
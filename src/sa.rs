@@ -1,257 +1,2002 @@
-use std::ops::Range;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read, Result, Write};
+use std::ops::ControlFlow;
+use std::ops::{Range, RangeInclusive};
+#[cfg(any(feature = "pack", feature = "mmap"))]
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+#[cfg(feature = "pack")]
+use super::archive::{write_archive, Section};
+use super::bloom::PrefixFilter;
+use super::buckets::{Buckets, MAX_BUCKET_DEPTH};
+use super::cursor::{BiCursor, Cursor};
+use super::dedup::{dedup_regions, DupRegion};
+use super::edit::{affected_start, repair, MAX_REPAIR};
+use super::error::Error;
+use super::export::BlockBounds;
+use super::eytzinger::FastSearch;
+use super::frequent::{top_k, FrequentSubstring};
+use super::lcp::{
+    lcp_array, lcp_array_into, window_counts, KmerCounts, LcpIntervals,
+};
+use super::matching::{for_each_statistic, Matcher};
+use super::overlap::{overlaps, Overlaps};
+#[cfg(feature = "pack")]
+use super::packed_sa::{PackedIndex, PackedSuffixArray};
+use super::page::Page;
+use super::palindrome::longest_palindrome;
+use super::plan::{PlanDescription, Strategy};
+use super::plcp::{LcpKind, Plcp, StoredLcp};
+use super::position::{Documents, Position};
 #[cfg(feature = "pack")]
-use std::{
-    io::{Read, Result, Write},
-    path::Path,
+use super::postings::PostingLists;
+use super::progress::Phase;
+use super::raw::{read_raw, write_raw, RawWidth};
+use super::report::{
+    collect_until, Occurrences, PartialMatches, SearchHit, SearchOptions,
 };
+use super::rlfm::RlfmIndex;
+use super::rotation::{min_rotation, search_circular};
+use super::saca::{saca, saca_with, saca_with_progress, MAX_LENGTH};
+use super::sampled::SampledIndex;
+use super::similarity::{distinct_qgrams, query_qgrams, SimilarityKind};
+use super::splice::{apply_splices, Splice};
+use super::stats::{Algorithm, BucketStats, IndexStats, MemoryBreakdown};
+use super::storage::TextStorage;
+use super::subindex::SubIndexView;
+use super::trie::{search_trie, PatternTrie};
+use super::unicode::{char_positions, snap_to_char_boundary};
+use super::utils::*;
+use super::warm::{touch, touch_samples, WarmLevel};
+
+/// Minimum length of suffix array to be checked in parallel.
+#[cfg(feature = "rayon")]
+const PARALLEL_CHECK_LENGTH: usize = 1 << 16;
+
+/// Number of ranks between the samples for fast searching.
+const FAST_SEARCH_BLOCK: usize = 32;
+
+/// Maximum length of pattern to be searched by comparing packed words.
+const SHORT_PATTERN: usize = 8;
+
+/// Default maximum length of byte string to be searched by direct scan.
+const NAIVE_SEARCH_LENGTH: usize = 1024;
+
+/// Suffix array for byte string.
+///
+/// The byte string could be stored in any `TextStorage`, e.g. borrowed as
+/// `&[u8]`, owned as `Vec<u8>`, or shared as `Arc<[u8]>`.
+#[derive(Clone)]
+pub struct SuffixArray<T> {
+    s: T,
+    sa: Vec<u32>,
+    bkt: Option<Buckets>,
+    lazy_bkt: Option<Vec<OnceLock<Vec<u32>>>>,
+    fast: Option<FastSearch>,
+    prefix: Option<PrefixFilter>,
+    lcp: Option<StoredLcp>,
+    isa: OnceLock<Vec<u32>>,
+    rev: OnceLock<Box<SuffixArray<Vec<u8>>>>,
+    naive_len: usize,
+    max_pat: usize,
+}
+
+impl<T: TextStorage> SuffixArray<T> {
+    // Construct new suffix array for given byte string.
+    //
+    // Panics if the byte string is longer than `MAX_LENGTH`, see `try_new`.
+    pub fn new(s: T) -> Self {
+        let mut sa = vec![0; s.as_ref().len() + 1];
+        saca(s.as_ref(), &mut sa[..]);
+        Self::compose(s, sa)
+    }
+
+    /// Construct new suffix array for given byte string, or fail if it is
+    /// longer than `MAX_LENGTH`.
+    pub fn try_new(s: T) -> std::result::Result<Self, Error> {
+        let len = s.as_ref().len();
+        if len > MAX_LENGTH {
+            return Err(Error::TooLong(len));
+        }
+        Ok(Self::new(s))
+    }
+
+    /// Construct new suffix array for given byte string using the given
+    /// construction algorithm.
+    pub fn with_algorithm(s: T, algorithm: Algorithm) -> Self {
+        let mut sa = vec![0; s.as_ref().len() + 1];
+        saca_with(s.as_ref(), &mut sa[..], algorithm);
+        Self::compose(s, sa)
+    }
+
+    /// Construct new suffix array for given byte string, calling `progress`
+    /// as each phase of the construction begins.
+    pub fn with_progress<F: FnMut(Phase)>(s: T, mut progress: F) -> Self {
+        let algorithm = IndexStats::sample(s.as_ref()).algorithm;
+        let mut sa = vec![0; s.as_ref().len() + 1];
+        saca_with_progress(s.as_ref(), &mut sa[..], algorithm, &mut progress);
+        Self::compose(s, sa)
+    }
+
+    // Construct suffix array in place.
+    //
+    // The enabled buckets, fast search samples and kept LCP array are rebuilt
+    // for the new byte string as well, reusing their allocations.
+    pub fn set(&mut self, s: T) {
+        self.s = s;
+        self.rebuild();
+    }
+
+    /// Replace the byte string with another one, possibly in another storage,
+    /// and rebuild the suffix array together with the enabled structures,
+    /// reusing their allocations.
+    pub fn reindex<U: TextStorage>(self, s: U) -> SuffixArray<U> {
+        let mut sa = self.with_text(s);
+        sa.rebuild();
+        sa
+    }
+
+    /// Convert the storage of the byte string, e.g. from borrowed to owned,
+    /// or from a file re-read into a new buffer.
+    ///
+    /// The suffix array and the other structures are kept as is if the
+    /// contents are unchanged, otherwise they are rebuilt as by `reindex`.
+    pub fn map_text<U, F>(self, f: F) -> SuffixArray<U>
+    where
+        U: TextStorage,
+        F: FnOnce(&T) -> U,
+    {
+        let s = f(&self.s);
+        if s.as_ref() == self.text() {
+            self.with_text(s)
+        } else {
+            self.reindex(s)
+        }
+    }
+
+    /// Move everything but the byte string into a suffix array of another
+    /// byte string, without rebuilding.
+    fn with_text<U: TextStorage>(self, s: U) -> SuffixArray<U> {
+        SuffixArray {
+            s,
+            sa: self.sa,
+            bkt: self.bkt,
+            lazy_bkt: self.lazy_bkt,
+            fast: self.fast,
+            prefix: self.prefix,
+            lcp: self.lcp,
+            isa: self.isa,
+            rev: self.rev,
+            naive_len: self.naive_len,
+            max_pat: self.max_pat,
+        }
+    }
+
+    /// Rebuild the suffix array and the enabled structures for the current
+    /// byte string.
+    fn rebuild(&mut self) {
+        let text = self.s.as_ref();
+        self.sa.resize(text.len() + 1, 0);
+        saca(text, &mut self.sa[..]);
+        self.rebuild_structures();
+    }
+
+    /// Rebuild the enabled structures for the current suffix array, and
+    /// drop the cached ones.
+    fn rebuild_structures(&mut self) {
+        let text = self.s.as_ref();
+        if let Some(ref mut bkt) = self.bkt {
+            bkt.rebuild(text);
+        }
+        if let Some(ref mut lazy) = self.lazy_bkt {
+            lazy.iter_mut().for_each(|bkt| drop(bkt.take()));
+        }
+        if let Some(ref mut fast) = self.fast {
+            fast.rebuild(text, &self.sa[..]);
+        }
+        if let Some(ref mut prefix) = self.prefix {
+            prefix.rebuild(text);
+        }
+        match self.lcp {
+            Some(StoredLcp::Plain(ref mut lcp)) => {
+                lcp_array_into(text, &self.sa[..], lcp)
+            }
+            Some(StoredLcp::Compressed(ref mut plcp)) => {
+                plcp.rebuild(text, &self.sa[..])
+            }
+            None => {}
+        }
+        self.isa = OnceLock::new();
+        self.rev = OnceLock::new();
+    }
+
+    /// Compose the byte string and its suffix array, with none of the
+    /// auxiliary structures enabled.
+    fn compose(s: T, sa: Vec<u32>) -> Self {
+        SuffixArray {
+            s,
+            sa,
+            bkt: None,
+            lazy_bkt: None,
+            fast: None,
+            prefix: None,
+            lcp: None,
+            isa: OnceLock::new(),
+            rev: OnceLock::new(),
+            naive_len: NAIVE_SEARCH_LENGTH,
+            max_pat: usize::MAX,
+        }
+    }
+
+    // Release the unused memory of suffix array.
+    pub fn fit(&mut self) {
+        self.sa.shrink_to_fit()
+    }
+
+    /// Length of the underlying byte string.
+    pub fn len(&self) -> usize {
+        self.text().len()
+    }
+
+    /// The underlying byte string.
+    pub fn text(&self) -> &[u8] {
+        self.s.as_ref()
+    }
+
+    /// Test if the underlying byte string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over all the suffixes in lexicographic order, yielding the
+    /// rank, the position and the suffix itself.
+    ///
+    /// The first one is always the empty suffix at the end.
+    pub fn iter_suffixes(
+        &self,
+    ) -> impl Iterator<Item = (usize, u32, &[u8])> + '_ {
+        self.iter_suffixes_in(0..self.sa.len())
+    }
+
+    /// Iterate over the suffixes whose ranks are in the given range, in
+    /// lexicographic order.
+    pub fn iter_suffixes_in(
+        &self,
+        ranks: Range<usize>,
+    ) -> impl Iterator<Item = (usize, u32, &[u8])> + '_ {
+        let s = self.text();
+        let start = ranks.start;
+        self.sa[ranks]
+            .iter()
+            .enumerate()
+            .map(move |(i, &p)| (start + i, p, &s[p as usize..]))
+    }
+
+    /// Get the rank of the suffix at position `pos`, where `pos` ranges from
+    /// 0 to `len()` (the empty suffix, whose rank is always 0).
+    ///
+    /// The inverse suffix array is computed on first use and then cached,
+    /// taking additional 4 bytes per byte.
+    pub fn rank_of(&self, pos: usize) -> Option<u32> {
+        let isa = self.isa.get_or_init(|| {
+            let mut isa = vec![0; self.sa.len()];
+            for (i, &p) in self.sa.iter().enumerate() {
+                isa[p as usize] = i as u32;
+            }
+            isa
+        });
+        isa.get(pos).cloned()
+    }
+
+    /// Get the positions of the suffixes of the given ranks.
+    pub(crate) fn suffixes(&self, ranks: Range<usize>) -> &[u32] {
+        &self.sa[ranks]
+    }
+
+    /// Get the position of the suffix of given rank, where `rank` ranges
+    /// from 0 to `len()`.
+    pub fn pos_at_rank(&self, rank: usize) -> Option<u32> {
+        self.sa.get(rank).cloned()
+    }
+
+    /// Get the position of the lexicographically i-th smallest suffix
+    /// together with the suffix itself, where `rank` ranges from 0 to
+    /// `len()`.
+    pub fn suffix_at_rank(&self, rank: usize) -> Option<(u32, &[u8])> {
+        let p = self.pos_at_rank(rank)?;
+        Some((p, &self.text()[p as usize..]))
+    }
+
+    /// Iterate over the suffixes whose ranks are at most `w` away from the
+    /// given rank, in lexicographic order, yielding the rank, the position
+    /// and the suffix itself.
+    ///
+    /// The rank could also be an insertion point, e.g. `len() + 1` or the
+    /// start of the range found by `search_lcp`, and the window is clipped
+    /// to the existing ranks.
+    pub fn neighbors_of_rank(
+        &self,
+        rank: usize,
+        w: usize,
+    ) -> impl Iterator<Item = (usize, u32, &[u8])> + '_ {
+        let end =
+            Ord::min(rank.saturating_add(w).saturating_add(1), self.sa.len());
+        let start = Ord::min(rank.saturating_sub(w), end);
+        self.iter_suffixes_in(start..end)
+    }
+
+    /// Split the suffix array, including the empty suffix at rank 0, into
+    /// blocks of `block_len` (at least 1) ranks, the last one possibly
+    /// shorter, e.g. to upload them to an accelerator without copying.
+    pub fn export_blocks(
+        &self,
+        block_len: usize,
+    ) -> impl Iterator<Item = &[u32]> + '_ {
+        self.sa.chunks(block_len)
+    }
+
+    /// Get the range of the first bytes of the suffixes in each block
+    /// exported by `export_blocks`, so that a batch search could skip the
+    /// blocks by the first byte of pattern.
+    pub fn export_block_bounds(&self, block_len: usize) -> Vec<BlockBounds> {
+        let s = self.text();
+        let blocks = self.export_blocks(block_len);
+        blocks.map(|block| BlockBounds::new(s, block)).collect()
+    }
+
+    /// Take out the suffix array and its corresponding byte string.
+    pub fn into_parts(self) -> (T, Vec<u32>) {
+        (self.s, self.sa)
+    }
+
+    /// Compose existed suffix array and its corresponding byte string
+    /// together, and checks the integrity.
+    pub fn from_parts(s: T, sa: Vec<u32>) -> Option<Self> {
+        let compose = Self::compose(s, sa);
+        if compose.check_integrity() {
+            Some(compose)
+        } else {
+            None
+        }
+    }
+
+    /// Compose the byte string and its suffix array without the sentinel, as
+    /// used by the `suffix` crate and libdivsufsort, and checks the
+    /// integrity.
+    pub fn from_raw_sa(s: T, raw: &[u32]) -> Option<Self> {
+        let mut sa = Vec::with_capacity(raw.len() + 1);
+        sa.push(u32::try_from(raw.len()).ok()?);
+        sa.extend_from_slice(raw);
+        Self::from_parts(s, sa)
+    }
+
+    /// Compose the byte string and its suffix array without the sentinel,
+    /// having 64-bit entries, and checks the integrity.
+    pub fn from_raw_sa64(s: T, raw: &[u64]) -> Option<Self> {
+        let mut sa = Vec::with_capacity(raw.len() + 1);
+        sa.push(u32::try_from(raw.len()).ok()?);
+        for &p in raw {
+            sa.push(u32::try_from(p).ok()?);
+        }
+        Self::from_parts(s, sa)
+    }
+
+    /// The suffix array without the sentinel, as used by the `suffix` crate
+    /// and libdivsufsort.
+    pub fn raw_sa(&self) -> &[u32] {
+        &self.sa[1..]
+    }
+
+    /// The suffix array without the sentinel, having 64-bit entries.
+    pub fn to_raw_sa64(&self) -> Vec<u64> {
+        self.raw_sa().iter().map(|&p| p as u64).collect()
+    }
+
+    /// Compose existed suffix array and its corresponding byte string
+    /// together without integrity check.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `sa` is exactly the suffix array of `s`.
+    pub unsafe fn unchecked_from_parts(s: T, sa: Vec<u32>) -> Self {
+        Self::compose(s, sa)
+    }
+
+    fn check_integrity(&self) -> bool {
+        let s = self.text();
+        if s.len() + 1 != self.sa.len() {
+            return false;
+        }
+
+        #[cfg(feature = "rayon")]
+        {
+            if self.sa.len() >= PARALLEL_CHECK_LENGTH {
+                return self.par_check_order();
+            }
+        }
+
+        if self.sa.iter().any(|&p| checked_pos(p, s.len()).is_none()) {
+            return false;
+        }
+        for i in 1..self.sa.len() {
+            let x = &s[self.sa[i - 1] as usize..];
+            let y = &s[self.sa[i] as usize..];
+            if x >= y {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Check the range of entries and the order of adjacent suffixes in
+    /// parallel. The windows overlap, so the pairs across the boundaries of
+    /// chunks are checked as well.
+    #[cfg(feature = "rayon")]
+    fn par_check_order(&self) -> bool {
+        use rayon::prelude::*;
+
+        let s = self.text();
+        if self
+            .sa
+            .par_iter()
+            .any(|&p| checked_pos(p, s.len()).is_none())
+        {
+            return false;
+        }
+        self.sa.par_windows(2).all(|pair| {
+            let x = &s[pair[0] as usize..];
+            let y = &s[pair[1] as usize..];
+            x < y
+        })
+    }
+
+    /// Set the maximum length of byte string (1 KiB by default) to be
+    /// searched by direct scan instead of binary search, as scanning a tiny
+    /// byte string is faster. It applies only to `contains`,
+    /// `search_all_sorted` and `nth_occurrence`, and is disabled by 0.
+    pub fn set_naive_search_length(&mut self, len: usize) {
+        self.naive_len = len;
+    }
+
+    /// Set the maximum length of pattern accepted by `try_contains` and
+    /// `try_search_all`, which is unlimited by default.
+    pub fn set_max_pattern_len(&mut self, len: usize) {
+        self.max_pat = len;
+    }
+
+    /// Get the maximum length of pattern.
+    pub fn max_pattern_len(&self) -> usize {
+        self.max_pat
+    }
+
+    /// Truncate the pattern to the maximum length of pattern.
+    pub fn truncate_pattern<'p>(&self, pat: &'p [u8]) -> &'p [u8] {
+        trunc(pat, self.max_pat)
+    }
+
+    /// Enable bucket pointers to speed up large amount of pattern searching.
+    ///
+    /// The overhead is at most about 257 KiB, and only a few KiB for small
+    /// alphabets.
+    pub fn enable_buckets(&mut self) {
+        self.enable_buckets_with_depth(2)
+    }
+
+    /// Enable bucket pointers indexed by the leading `depth` bytes, where
+    /// `depth` ranges from 1 to `MAX_BUCKET_DEPTH`.
+    ///
+    /// The overhead is at most about `4 * k * (k + 1)^(depth - 1)` bytes for
+    /// the `k` distinct bytes present in the byte string, i.e. at most 1 KiB,
+    /// 257 KiB and 64 MiB respectively, e.g. about 8 KiB for DNA at depth 3.
+    /// The suffixes are counted in parallel if the `rayon` feature is
+    /// enabled.
+    pub fn enable_buckets_with_depth(&mut self, depth: usize) {
+        assert!(depth > 0 && depth <= MAX_BUCKET_DEPTH);
+        if self.bucket_depth() == depth && self.bkt.is_some() {
+            return;
+        }
+
+        self.bkt = Some(Buckets::new(self.text(), depth));
+        self.lazy_bkt = None;
+    }
+
+    /// Number of leading bytes the bucket pointers are indexed by, or 0 if
+    /// buckets are not enabled.
+    pub fn bucket_depth(&self) -> usize {
+        if let Some(ref bkt) = self.bkt {
+            bkt.depth()
+        } else if self.lazy_bkt.is_some() {
+            2
+        } else {
+            0
+        }
+    }
+
+    /// Enable bucket pointers that are computed for each leading byte on its
+    /// first use and then cached.
+    ///
+    /// Unlike `enable_buckets`, there is no upfront scan of the whole byte
+    /// string, and the buckets could be populated concurrently by queries
+    /// from multiple threads.
+    pub fn enable_lazy_buckets(&mut self) {
+        if self.bkt.is_some() || self.lazy_bkt.is_some() {
+            return;
+        }
+        self.lazy_bkt = Some((0..256).map(|_| OnceLock::new()).collect());
+    }
+
+    /// Enable a cache friendly copy of samples to speed up large amount of
+    /// random pattern searching over huge suffix array by `contains` and
+    /// `search_all`.
+    ///
+    /// Every 32nd suffix is sampled with its leading 8 bytes, and laid out
+    /// in Eytzinger order, so that most of the binary search touches neither
+    /// the suffix array nor the byte string. The suffix array itself is kept
+    /// in the standard layout. The overhead is about 0.5 byte per byte.
+    pub fn enable_fast_search(&mut self) {
+        if self.fast.is_none() {
+            let fast =
+                FastSearch::new(self.text(), &self.sa[..], FAST_SEARCH_BLOCK);
+            self.fast = Some(fast);
+        }
+    }
+
+    /// Disable the bucket pointers, either eager or lazy, releasing their
+    /// memory.
+    pub fn disable_buckets(&mut self) {
+        self.bkt = None;
+        self.lazy_bkt = None;
+    }
+
+    /// Enable a Bloom filter of all the `k`-byte sub-strings, where `k` is
+    /// clamped to 1 to 8, e.g. 4 or 8, so that `contains`, `search_all` and
+    /// `prefilter` reject most of the absent patterns without binary search,
+    /// which pays off when most of the searched patterns are absent.
+    ///
+    /// The patterns shorter than `k` are never rejected. About 1% of the
+    /// absent sub-strings pass the filter, and the overhead is about 1.25
+    /// to 2.5 bytes per byte. It replaces the enabled one of another `k`.
+    ///
+    /// The filter is opt-in: it is built by this call in one pass over the
+    /// byte string, never during the construction of the suffix array.
+    pub fn enable_prefix_filter(&mut self, k: usize) {
+        let k = k.clamp(1, 8);
+        if self.prefix.as_ref().map(PrefixFilter::k) != Some(k) {
+            self.prefix = Some(PrefixFilter::new(self.text(), k));
+        }
+    }
+
+    /// Disable the prefix filter, releasing its memory.
+    pub fn disable_prefix_filter(&mut self) {
+        self.prefix = None;
+    }
+
+    /// Test if the pattern is rejected by the prefix filter.
+    #[inline]
+    fn filtered_out(&self, pat: &[u8]) -> bool {
+        self.prefix.as_ref().is_some_and(|f| !f.may_contain(pat))
+    }
+
+    /// Compute the LCP array upfront and keep it, so that it is reused by
+    /// `lcp_at`, `substrings_with_min_count`, `kmer_counts` and
+    /// `frequency_transform`.
+    ///
+    /// The overhead is 4 bytes per byte.
+    pub fn enable_lcp(&mut self) {
+        self.enable_lcp_with(LcpKind::Plain)
+    }
+
+    /// Compute the LCP array upfront and keep it in the given kind of
+    /// representation, replacing the kept one of another kind.
+    ///
+    /// The compressed representation takes about 2.125 bits per byte, and
+    /// is decoded on the fly by the iterating queries, which then take 4
+    /// additional bytes per byte while iterating.
+    pub fn enable_lcp_with(&mut self, kind: LcpKind) {
+        if self.lcp_kind() == Some(kind) {
+            return;
+        }
+        self.lcp = Some(match kind {
+            LcpKind::Plain => {
+                StoredLcp::Plain(lcp_array(self.text(), &self.sa[..]))
+            }
+            LcpKind::Compressed => {
+                StoredLcp::Compressed(Plcp::new(self.text(), &self.sa[..]))
+            }
+        });
+    }
+
+    /// Kind of the kept LCP array, if any.
+    pub fn lcp_kind(&self) -> Option<LcpKind> {
+        match self.lcp {
+            Some(StoredLcp::Plain(_)) => Some(LcpKind::Plain),
+            Some(StoredLcp::Compressed(_)) => Some(LcpKind::Compressed),
+            None => None,
+        }
+    }
+
+    /// Get the longest common prefix length of the suffix of given rank and
+    /// the suffix before it, or 0 for rank 0.
+    ///
+    /// If no LCP array is kept, the suffixes are compared directly.
+    pub fn lcp_at(&self, rank: usize) -> Option<u32> {
+        let p = *self.sa.get(rank)? as usize;
+        let len = match self.lcp {
+            Some(StoredLcp::Plain(ref lcp)) => lcp[rank],
+            _ if rank == 0 => 0,
+            Some(StoredLcp::Compressed(ref plcp)) => plcp.get(p),
+            None => {
+                let q = self.sa[rank - 1] as usize;
+                lcp(&self.text()[p..], &self.text()[q..]) as u32
+            }
+        };
+        Some(len)
+    }
+
+    /// Get the LCP array, borrowed if kept in plain.
+    pub(crate) fn lcp_array(&self) -> Cow<'_, [u32]> {
+        match self.lcp {
+            Some(StoredLcp::Plain(ref lcp)) => Cow::Borrowed(&lcp[..]),
+            Some(StoredLcp::Compressed(ref plcp)) => {
+                Cow::Owned(plcp.to_lcp(&self.sa[..]))
+            }
+            None => Cow::Owned(lcp_array(self.text(), &self.sa[..])),
+        }
+    }
+
+    /// Drop the kept LCP array, releasing its memory.
+    pub fn drop_lcp(&mut self) {
+        self.lcp = None;
+    }
+
+    /// Construct the suffix array of the reversed byte string now, instead
+    /// of on first use by the suffix-oriented queries, e.g. `search_ends`.
+    ///
+    /// It takes additional 5 bytes per byte, and is dumped by
+    /// `dump_archive` as `Section::ReverseIndex`.
+    pub fn enable_reverse_index(&mut self) {
+        self.reverse();
+    }
+
+    /// Test if the suffix array of the reversed byte string is constructed.
+    pub fn has_reverse_index(&self) -> bool {
+        self.rev.get().is_some()
+    }
+
+    /// Drop the suffix array of the reversed byte string, releasing its
+    /// memory.
+    pub fn drop_reverse_index(&mut self) {
+        self.rev = OnceLock::new();
+    }
+
+    /// The suffix array of the reversed byte string, constructed on first
+    /// use and then cached.
+    fn reverse(&self) -> &SuffixArray<Vec<u8>> {
+        self.rev.get_or_init(|| {
+            Box::new(SuffixArray::new(
+                self.text().iter().rev().cloned().collect(),
+            ))
+        })
+    }
+
+    /// Hash the byte string together with the suffix array by 64-bit FNV-1a,
+    /// which is stable across platforms, feature sets and releases.
+    ///
+    /// Equal suffix arrays always have the same content hash.
+    pub fn content_hash(&self) -> u64 {
+        let mut h = fnv1a(FNV_OFFSET, &(self.len() as u64).to_le_bytes());
+        h = fnv1a(h, self.text());
+        for &p in self.sa.iter() {
+            h = fnv1a(h, &p.to_le_bytes());
+        }
+        h
+    }
+
+    /// Report the heap memory taken by the suffix array and its auxiliary
+    /// structures, excluding the underlying byte string.
+    pub fn memory_usage(&self) -> MemoryBreakdown {
+        let words = |v: &Vec<u32>| v.capacity() * 4;
+        let mut buckets = self.bkt.as_ref().map_or(0, Buckets::heap_size);
+        if let Some(ref lazy) = self.lazy_bkt {
+            buckets +=
+                lazy.capacity() * std::mem::size_of::<OnceLock<Vec<u32>>>();
+            buckets += lazy
+                .iter()
+                .filter_map(OnceLock::get)
+                .map(words)
+                .sum::<usize>();
+        }
+
+        let mut extra = self.fast.as_ref().map_or(0, FastSearch::heap_size);
+        extra += self.prefix.as_ref().map_or(0, PrefixFilter::heap_size);
+        extra += self.isa.get().map_or(0, words);
+        if let Some(rev) = self.rev.get() {
+            extra += rev.text().len() + rev.memory_usage().total();
+        }
+
+        MemoryBreakdown {
+            sa: words(&self.sa),
+            buckets,
+            lcp: self.lcp.as_ref().map_or(0, StoredLcp::heap_size),
+            extra,
+        }
+    }
+
+    /// Touch the pages of the index, and of the byte string depending on the
+    /// level, in the order the queries would, so that the first queries
+    /// after opening a large index do not wait for the pages to be read in.
+    ///
+    /// Returns the number of pages and sampled suffixes touched. The lazy
+    /// bucket pointers and the inverse suffix array are not computed.
+    pub fn warm(&self, level: WarmLevel) -> usize {
+        let mut touched = touch(&self.sa[..]);
+        touched += self.bkt.as_ref().map_or(0, Buckets::warm);
+        touched += self.lcp.as_ref().map_or(0, StoredLcp::warm);
+        if let Some(ref lazy) = self.lazy_bkt {
+            touched += lazy
+                .iter()
+                .filter_map(OnceLock::get)
+                .map(|v| touch(v))
+                .sum::<usize>();
+        }
+        touched += match level {
+            WarmLevel::Index => 0,
+            WarmLevel::SearchPaths => {
+                touch_samples(self.text(), &self.sa[..], FAST_SEARCH_BLOCK)
+            }
+            WarmLevel::Full => touch(self.text()),
+        };
+        touched
+    }
+
+    /// Count the suffixes starting with each byte and each pair of bytes,
+    /// e.g. to detect skew or to split the work by buckets.
+    ///
+    /// The lazy bucket pointers are computed and reused if enabled, and
+    /// otherwise the boundaries are binary searched, taking O(log n) time
+    /// for each pair.
+    pub fn bucket_stats(&self) -> BucketStats {
+        BucketStats::from_bounds(|c0| match self.get_sub_buckets(c0) {
+            Some(bounds) => Cow::Borrowed(bounds),
+            None => Cow::Owned(self.compute_sub_buckets(c0)),
+        })
+    }
+
+    /// Get the range of suffixes possibly prefixed by pattern, narrowed by
+    /// both the buckets and the fast search samples.
+    #[inline]
+    fn get_range(&self, pat: &[u8]) -> Range<usize> {
+        let bucket = self.get_bucket(pat);
+        match self.fast {
+            Some(ref fast) => {
+                let range = fast.narrow(self.text(), &self.sa[..], pat);
+                let start = Ord::max(bucket.start, range.start);
+                let end = Ord::min(bucket.end, range.end);
+                start..Ord::max(start, end)
+            }
+            None => bucket,
+        }
+    }
+
+    /// Get the boundaries of the sub-buckets `(c0, $), (c0, 0), ...,
+    /// (c0, 255)`, if lazy buckets are enabled.
+    #[inline]
+    fn get_sub_buckets(&self, c0: u8) -> Option<&[u32]> {
+        if let Some(ref lazy_bkt) = self.lazy_bkt {
+            let cell = &lazy_bkt[c0 as usize];
+            Some(&cell.get_or_init(|| self.compute_sub_buckets(c0))[..])
+        } else {
+            None
+        }
+    }
+
+    /// Compute the boundaries of the sub-buckets by binary searching.
+    fn compute_sub_buckets(&self, c0: u8) -> Vec<u32> {
+        let s = self.text();
+        let sa = &self.sa[..];
+        let first = |&p: &u32| s.get(p as usize).cloned();
+        let second = |&p: &u32| s.get(p as usize + 1).cloned();
+
+        let start = sa.partition_point(|p| first(p) < Some(c0));
+        let end = sa.partition_point(|p| first(p) <= Some(c0));
+        let top = &sa[start..end];
+
+        let mut bounds = Vec::with_capacity(258);
+        bounds.push(start as u32);
+        bounds.push(
+            (start + top.partition_point(|p| second(p).is_none())) as u32,
+        );
+        for c1 in 0..=255 {
+            let n = top.partition_point(|p| second(p) <= Some(c1));
+            bounds.push((start + n) as u32);
+        }
+        bounds
+    }
+
+    /// Get the bucket of suffixes starting with the leading bytes of pattern,
+    /// up to the bucket depth.
+    #[inline]
+    fn get_bucket(&self, pat: &[u8]) -> Range<usize> {
+        if pat.is_empty() {
+            return 0..self.sa.len();
+        }
+        if let Some(ref bkt) = self.bkt {
+            bkt.range(pat)
+        } else if let Some(bkt) = self.get_sub_buckets(pat[0]) {
+            if pat.len() > 1 {
+                // sub-bucket (c0, c1).
+                let idx = pat[1] as usize + 2;
+                bkt[idx - 1] as usize..bkt[idx] as usize
+            } else {
+                // top-level bucket (c0, $)..=(c0, 255).
+                bkt[0] as usize..bkt[257] as usize
+            }
+        } else {
+            0..self.sa.len()
+        }
+    }
+
+    /// Test if the pattern may occur by looking up its leading bytes in the
+    /// bucket pointers, which takes O(1) time once the bucket is computed.
+    ///
+    /// Returns `false` only if the pattern does not occur for sure, and is
+    /// always `true` if neither the bucket pointers nor the prefix filter
+    /// are enabled.
+    pub fn prefilter(&self, pat: &[u8]) -> bool {
+        !self.filtered_out(pat) && !self.get_bucket(pat).is_empty()
+    }
+
+    /// Test if it contains the given pattern, rejecting the pattern longer
+    /// than the maximum length.
+    pub fn try_contains(&self, pat: &[u8]) -> std::result::Result<bool, Error> {
+        self.check_pattern(pat)?;
+        Ok(self.prefilter(pat) && self.contains(pat))
+    }
+
+    /// Search for all the unsorted occurrence of given pattern like
+    /// `search_all`, rejecting the pattern longer than the maximum length.
+    pub fn try_search_all(
+        &self,
+        pat: &[u8],
+    ) -> std::result::Result<&[u32], Error> {
+        self.check_pattern(pat)?;
+        if !self.prefilter(pat) {
+            return Ok(&[]);
+        }
+        Ok(self.search_all(pat))
+    }
+
+    fn check_pattern(&self, pat: &[u8]) -> std::result::Result<(), Error> {
+        if pat.len() > self.max_pat {
+            return Err(Error::PatternTooLong {
+                len: pat.len(),
+                max: self.max_pat,
+            });
+        }
+        Ok(())
+    }
+
+    /// Choose the strategy of searching the pattern, together with the range
+    /// of suffixes to be searched.
+    #[inline]
+    fn plan(&self, pat: &[u8], scan: bool) -> (Strategy, Range<usize>) {
+        if scan && self.len() <= self.naive_len {
+            return (Strategy::Scan, 0..0);
+        }
+        if pat.is_empty() {
+            return (Strategy::BinarySearch, 0..self.sa.len());
+        }
+
+        let range = self.get_range(pat);
+        let strategy = if range.is_empty() {
+            Strategy::Empty
+        } else if pat.len() <= SHORT_PATTERN {
+            Strategy::PackedBinarySearch
+        } else {
+            Strategy::BinarySearch
+        };
+        (strategy, range)
+    }
+
+    /// Describe how the pattern would be searched by `contains` and
+    /// `search_all_sorted`, for understanding and tuning the configuration
+    /// of buckets, fast search samples and naive search length.
+    ///
+    /// The bucket pointers are looked up, so the lazy ones may be computed.
+    pub fn explain(&self, pat: &[u8]) -> PlanDescription {
+        if self.filtered_out(pat) {
+            return PlanDescription {
+                strategy: Strategy::Empty,
+                bucket_bytes: 0,
+                fast_search: false,
+                candidates: 0,
+            };
+        }
+        let (strategy, range) = self.plan(pat, true);
+        let searched = strategy != Strategy::Scan && !pat.is_empty();
+        let bucket_bytes = if searched {
+            Ord::min(self.bucket_depth(), pat.len())
+        } else {
+            0
+        };
+        let candidates = match strategy {
+            Strategy::Scan => self.sa.len(),
+            _ => range.len(),
+        };
+        PlanDescription {
+            strategy,
+            bucket_bytes,
+            fast_search: searched && self.fast.is_some(),
+            candidates,
+        }
+    }
+
+    /// Test if it contains the given pattern.
+    pub fn contains(&self, pat: &[u8]) -> bool {
+        if self.filtered_out(pat) {
+            return false;
+        }
+        let s = self.text();
+        let (strategy, range) = self.plan(pat, true);
+        let sa = &self.sa[range];
+        match strategy {
+            Strategy::Scan => scan(s, pat).next().is_some(),
+            Strategy::Empty => false,
+            Strategy::PackedBinarySearch => {
+                // compare packed words instead of slices for short pattern.
+                let key = pack_prefix(pat, pat.len());
+                sa.binary_search_by_key(&key, |&i| {
+                    pack_prefix(&s[i as usize..], pat.len())
+                })
+                .is_ok()
+            }
+            Strategy::BinarySearch => sa
+                .binary_search_by_key(&pat, |&i| {
+                    trunc(&s[i as usize..], pat.len())
+                })
+                .is_ok(),
+        }
+    }
+
+    /// Search for all the unsorted occurrence of given pattern (can overlap).
+    ///
+    /// The occurrences are ordered by the suffixes they start, which are
+    /// distinct, so the order is determined by the byte string alone and is
+    /// the same for any construction algorithm. Use `search_all_sorted` for
+    /// the occurrences in text order.
+    ///
+    /// The occurrences are borrowed from the suffix array, so it binary
+    /// searches even for a tiny byte string, unlike the queries listed in
+    /// `set_naive_search_length`.
+    pub fn search_all(&self, pat: &[u8]) -> &[u32] {
+        if self.filtered_out(pat) {
+            return &[];
+        }
+        &self.sa[self.search_ranks(pat)]
+    }
+
+    /// Search for the occurrences of given pattern like `search_all`,
+    /// together with the ranks of the matched suffixes.
+    ///
+    /// The ranks are enough to count the occurrences, e.g. for document
+    /// frequencies, and the positions are borrowed from the suffix array,
+    /// so that only the ones read are located, without searching again.
+    pub fn search_full(&self, pat: &[u8]) -> SearchHit<'_> {
+        let ranks = self.search_ranks(pat);
+        SearchHit {
+            positions: &self.sa[ranks.clone()],
+            sa_range: ranks,
+        }
+    }
+
+    /// Search for the ranks of the suffixes starting with given pattern.
+    fn search_ranks(&self, pat: &[u8]) -> Range<usize> {
+        let s = self.text();
+        let (strategy, range) = self.plan(pat, false);
+        let base = range.start;
+        let sa = &self.sa[range];
+
+        if strategy == Strategy::PackedBinarySearch {
+            // compare packed words instead of slices for short pattern.
+            let key = pack_prefix(pat, pat.len());
+            let i = sa.partition_point(|&i| {
+                pack_prefix(&s[i as usize..], pat.len()) < key
+            });
+            let j = i + sa[i..].partition_point(|&i| {
+                pack_prefix(&s[i as usize..], pat.len()) == key
+            });
+            return base + i..base + j;
+        }
+
+        let mut i = 0;
+        let mut k = sa.len();
+        while i < k {
+            let m = i + (k - i) / 2;
+            if pat > &s[sa[m] as usize..] {
+                i = m + 1;
+            } else {
+                k = m;
+            }
+        }
+
+        let mut j = i;
+        let mut k = sa.len();
+        while j < k {
+            let m = j + (k - j) / 2;
+            if s[sa[m] as usize..].starts_with(pat) {
+                j = m + 1;
+            } else {
+                k = m;
+            }
+        }
+
+        base + i..base + j
+    }
+
+    /// Search for all the occurrences of given pattern like `search_all`,
+    /// followed by the suffixes that are proper prefixes of the pattern, as
+    /// if the pattern ran past the end, yielding the position and the length
+    /// matched.
+    ///
+    /// The partial matches are ordered by descending length, and the empty
+    /// suffix is not reported.
+    pub fn search_all_partial(
+        &self,
+        pat: &[u8],
+    ) -> impl Iterator<Item = (u32, usize)> + '_ {
+        let (n, m) = (self.len(), pat.len());
+        let full = self.search_all(pat).iter().map(move |&p| (p, m));
+        let lens = self.overlaps(pat, 1).suffix_prefix;
+        let partial = lens
+            .into_iter()
+            .filter(move |&len| len < m)
+            .map(move |len| ((n - len) as u32, len));
+        full.chain(partial)
+    }
+
+    /// Search for all the unsorted occurrences of given pattern like
+    /// `search_all`, in the byte string concatenated from the documents,
+    /// decoding them into positions in the documents.
+    ///
+    /// The matches crossing the end of a document are skipped.
+    pub fn search_all_docs<'a>(
+        &'a self,
+        pat: &[u8],
+        docs: &'a Documents,
+    ) -> impl Iterator<Item = Position> + 'a {
+        let len = pat.len();
+        let found = self.search_all(pat).iter();
+        found.filter_map(move |&p| docs.locate_match(p, len))
+    }
+
+    /// Search for all the occurrences of given pattern in text order.
+    pub fn search_all_sorted(&self, pat: &[u8]) -> Vec<u32> {
+        if self.len() <= self.naive_len {
+            return scan(self.text(), pat).collect();
+        }
+        let mut result = Vec::from(self.search_all(pat));
+        result.sort_unstable();
+        result
+    }
+
+    /// Search for all the occurrences of given pattern in text order, where
+    /// the one bits of `mask` are don't-care bits of the corresponding
+    /// bytes of pattern, as in the binary signatures.
+    ///
+    /// The longest run of bytes without don't-care bits is searched, and the
+    /// candidates are verified against the rest of pattern. The whole byte
+    /// string is scanned if every byte has don't-care bits.
+    ///
+    /// The bytes of pattern past the end of `mask` have no don't-care bits,
+    /// and the extra bytes of `mask` are ignored.
+    pub fn search_masked(&self, pat: &[u8], mask: &[u8]) -> Vec<u32> {
+        self.search_masked_until(pat, mask, None).positions
+    }
+
+    /// Search for all the unsorted occurrences of given pattern like
+    /// `search_all`, stopping once the deadline passes.
+    ///
+    /// The range of suffixes is found at once, so the deadline only bounds
+    /// copying out the occurrences of a frequent pattern.
+    pub fn search_all_deadline(
+        &self,
+        pat: &[u8],
+        deadline: Instant,
+    ) -> PartialMatches {
+        let found = self.search_all(pat).iter().map(|&p| p as usize);
+        collect_until(found, |_| true, Some(deadline))
+    }
+
+    /// Search for the occurrences of given pattern with don't-care bits like
+    /// `search_masked`, stopping once the deadline passes, as verifying the
+    /// candidates may take long for a pattern with few fixed bytes.
+    ///
+    /// The occurrences found are in text order, but not necessarily the
+    /// leftmost ones if incomplete.
+    pub fn search_masked_deadline(
+        &self,
+        pat: &[u8],
+        mask: &[u8],
+        deadline: Instant,
+    ) -> PartialMatches {
+        self.search_masked_until(pat, mask, Some(deadline))
+    }
+
+    fn search_masked_until(
+        &self,
+        pat: &[u8],
+        mask: &[u8],
+        deadline: Option<Instant>,
+    ) -> PartialMatches {
+        let s = self.text();
+        let m = pat.len();
+        if m > s.len() {
+            return PartialMatches {
+                positions: Vec::new(),
+                complete: true,
+            };
+        }
+
+        let bits = |i: usize| mask.get(i).cloned().unwrap_or(0);
+        let mut anchor = 0..0;
+        let mut start = 0;
+        for i in 0..m {
+            if bits(i) != 0 {
+                start = i + 1;
+            } else if i + 1 - start > anchor.len() {
+                anchor = start..i + 1;
+            }
+        }
+        let verify = |p: usize| {
+            if p + m > s.len() {
+                return false;
+            }
+            let window = &s[p..p + m];
+            let mut masked = window.iter().zip(pat).enumerate();
+            masked.all(|(i, (&c, &d))| (c ^ d) & !bits(i) == 0)
+        };
+
+        let mut result = if anchor.is_empty() {
+            collect_until(0..=s.len() - m, verify, deadline)
+        } else {
+            let found = self.search_all(&pat[anchor.clone()]).iter();
+            let found =
+                found.filter_map(|&p| (p as usize).checked_sub(anchor.start));
+            collect_until(found, verify, deadline)
+        };
+        result.positions.sort_unstable();
+        result
+    }
+
+    /// Search for all the occurrences of given pattern in text order, and
+    /// report them as specified by the options.
+    pub fn search_all_with(
+        &self,
+        pat: &[u8],
+        options: SearchOptions,
+    ) -> Occurrences {
+        let positions = self.search_all_sorted(pat);
+        Occurrences::new(&positions[..], pat.len(), options)
+    }
+
+    /// Call `f` on the occurrences of given pattern in the order of
+    /// `search_all`, until it breaks, returning the break value.
+    pub fn search_all_until<B, F>(&self, pat: &[u8], mut f: F) -> ControlFlow<B>
+    where
+        F: FnMut(u32) -> ControlFlow<B>,
+    {
+        self.search_all(pat).iter().try_for_each(|&p| f(p))
+    }
+
+    /// Iterate over the bytes following the occurrences of given pattern in
+    /// ascending order, yielding each byte with the number of occurrences it
+    /// follows.
+    ///
+    /// Each byte takes a binary search over the occurrences.
+    pub fn extensions(
+        &self,
+        pat: &[u8],
+    ) -> impl Iterator<Item = (u8, usize)> + '_ {
+        let s = self.text();
+        let depth = pat.len();
+
+        // the occurrence at the end, if any, is the first one.
+        let mut sa = self.search_all(pat);
+        if sa.first().is_some_and(|&p| p as usize + depth == s.len()) {
+            sa = &sa[1..];
+        }
+        std::iter::from_fn(move || {
+            let c = s[*sa.first()? as usize + depth];
+            let count = sa.partition_point(|&p| s[p as usize + depth] == c);
+            sa = &sa[count..];
+            Some((c, count))
+        })
+    }
+
+    /// Create a cursor with an empty pattern, which is extended byte by
+    /// byte.
+    pub fn cursor(&self) -> Cursor<'_, T> {
+        Cursor::new(self)
+    }
+
+    /// Create a cursor with an empty pattern, which is extended at either
+    /// end by binary searching the suffix array of the byte string or of
+    /// its reverse, see `enable_reverse_index`.
+    pub fn bi_cursor(&self) -> BiCursor<'_, T> {
+        BiCursor::new(self, self.reverse())
+    }
+
+    /// Search for all the unsorted occurrences of each pattern compiled into
+    /// the trie, in the order of patterns, like `search_all` for each.
+    ///
+    /// The range of suffixes prefixed by each distinct prefix is narrowed
+    /// once by a cursor, and the patterns extending an absent prefix are
+    /// not searched at all.
+    pub fn search_trie(&self, trie: &PatternTrie) -> Vec<&[u32]> {
+        search_trie(self, trie)
+    }
+
+    /// Search for all the unsorted occurrences of each pattern, sharding the
+    /// patterns across `threads` scoped threads, and return the results in
+    /// the order of patterns.
+    ///
+    /// The queries are read-only, so the suffix array could be shared among
+    /// threads freely, including the lazily computed structures such as
+    /// `enable_lazy_buckets`.
+    pub fn concurrent_search_all<P>(
+        &self,
+        pats: &[P],
+        threads: usize,
+    ) -> Vec<&[u32]>
+    where
+        T: Sync,
+        P: AsRef<[u8]> + Sync,
+    {
+        let threads = Ord::max(threads, 1);
+        let shard = Ord::max(pats.len().div_ceil(threads), 1);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = pats
+                .chunks(shard)
+                .map(|pats| {
+                    scope.spawn(move || {
+                        pats.iter()
+                            .map(|pat| self.search_all(pat.as_ref()))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    }
+
+    /// Get the `n`-th (0-based) occurrence of given pattern in text order.
+    ///
+    /// The occurrence is selected from the matched interval without sorting
+    /// it, but each call still takes time linear in the number of the
+    /// occurrences. To walk through many of them in text order, sort them
+    /// once by `search_all_sorted` instead. See `nth_occurrence_into` for
+    /// reusing the selection buffer across calls.
+    pub fn nth_occurrence(&self, pat: &[u8], n: usize) -> Option<u32> {
+        self.nth_occurrence_into(pat, n, &mut Vec::new())
+    }
+
+    /// Get the `n`-th (0-based) occurrence of given pattern in text order,
+    /// selecting within `buf`, whose allocation is reused by repeated calls.
+    ///
+    /// The first and the last occurrences are found without the buffer.
+    pub fn nth_occurrence_into(
+        &self,
+        pat: &[u8],
+        n: usize,
+        buf: &mut Vec<u32>,
+    ) -> Option<u32> {
+        if self.len() <= self.naive_len {
+            return scan(self.text(), pat).nth(n);
+        }
+        let all = self.search_all(pat);
+        if n >= all.len() {
+            return None;
+        }
+        if n == 0 {
+            return all.iter().copied().min();
+        }
+        if n + 1 == all.len() {
+            return all.iter().copied().max();
+        }
+        buf.clear();
+        buf.extend_from_slice(all);
+        Some(*buf.select_nth_unstable(n).1)
+    }
+
+    /// Sample `k` distinct occurrences of given pattern uniformly, or all of
+    /// them if there are no more than `k`, in no particular order.
+    ///
+    /// The ranks are drawn from the matched interval by Floyd's algorithm,
+    /// taking O(k) random words from `rng` regardless of the number of the
+    /// occurrences.
+    pub fn sample_occurrences<R: FnMut() -> u64>(
+        &self,
+        pat: &[u8],
+        k: usize,
+        rng: R,
+    ) -> Vec<u32> {
+        let mut result = Vec::new();
+        self.sample_occurrences_into(pat, k, rng, &mut result);
+        result
+    }
+
+    /// Sample `k` distinct occurrences of given pattern like
+    /// `sample_occurrences`, into `result`, whose allocation is reused by
+    /// repeated calls.
+    pub fn sample_occurrences_into<R: FnMut() -> u64>(
+        &self,
+        pat: &[u8],
+        k: usize,
+        mut rng: R,
+        result: &mut Vec<u32>,
+    ) {
+        result.clear();
+        let all = self.search_all(pat);
+        let m = all.len();
+        if k >= m {
+            result.extend_from_slice(all);
+            return;
+        }
+
+        let mut chosen = HashSet::with_capacity(k);
+        for j in m - k..m {
+            // uniform in 0..=j by widening multiplication.
+            let r = ((rng() as u128 * (j as u128 + 1)) >> 64) as usize;
+            let r = if chosen.insert(r) {
+                r
+            } else {
+                chosen.insert(j);
+                j
+            };
+            result.push(all[r]);
+        }
+    }
+
+    /// Search for at most `limit` of the unsorted occurrences of given
+    /// pattern, skipping the first `offset` ones in the order of
+    /// `search_all`.
+    ///
+    /// The page is sliced from the matched interval directly, so paging
+    /// through all the occurrences by the returned `next` offset costs no
+    /// more than a search per page.
+    pub fn search_page(
+        &self,
+        pat: &[u8],
+        offset: usize,
+        limit: usize,
+    ) -> Page<'_> {
+        let all = self.search_all(pat);
+        let i = Ord::min(offset, all.len());
+        let j = i + Ord::min(limit, all.len() - i);
+        Page {
+            positions: &all[i..j],
+            total: all.len(),
+            next: if j < all.len() { Some(j) } else { None },
+        }
+    }
+
+    /// Search for the unsorted occurrences of given pattern that are whole
+    /// tokens, i.e. preceded by a boundary byte or the start, and followed by
+    /// a boundary byte or the end.
+    ///
+    /// The occurrences are filtered lazily while iterating over the matched
+    /// interval.
+    pub fn search_words<'a>(
+        &'a self,
+        pat: &[u8],
+        boundary: fn(u8) -> bool,
+    ) -> impl Iterator<Item = u32> + 'a {
+        let s = self.text();
+        let len = pat.len();
+        self.search_all(pat).iter().cloned().filter(move |&i| {
+            let i = i as usize;
+            (i == 0 || boundary(s[i - 1]))
+                && (i + len == s.len() || boundary(s[i + len]))
+        })
+    }
+
+    /// Search for the sorted positions of `a` that are followed by `b` after
+    /// a gap of `gap` bytes, i.e. `a` occurs at `i` and `b` occurs at `j`
+    /// where `j - (i + a.len())` is within `gap`.
+    pub fn search_gapped(
+        &self,
+        a: &[u8],
+        b: &[u8],
+        gap: RangeInclusive<usize>,
+    ) -> Vec<u32> {
+        let mut xs = Vec::from(self.search_all(a));
+        let mut ys = Vec::from(self.search_all(b));
+        xs.sort_unstable();
+        ys.sort_unstable();
+
+        // the windows of b slide forward along with the positions of a.
+        let mut k = 0;
+        let mut result = Vec::new();
+        for x in xs {
+            let lo = (x as usize + a.len()).saturating_add(*gap.start());
+            let hi = (x as usize + a.len()).saturating_add(*gap.end());
+            while k < ys.len() && (ys[k] as usize) < lo {
+                k += 1;
+            }
+            if k < ys.len() && ys[k] as usize <= hi {
+                result.push(x);
+            }
+        }
+        result
+    }
+
+    /// Search for all the occurrences of given pattern (can overlap) as a
+    /// bitset, where bit `i % 64` of the `i / 64`-th word is set if the
+    /// pattern occurs at position `i`.
+    ///
+    /// The bitset has `ceil((len + 1) / 64)` words, so bitsets from the same
+    /// suffix array could be combined by bitwise operations word by word.
+    pub fn search_bitset(&self, pat: &[u8]) -> Vec<u64> {
+        let mut bitset = vec![0u64; self.sa.len().div_ceil(64)];
+        for &p in self.search_all(pat) {
+            bitset[p as usize / 64] |= 1 << (p % 64);
+        }
+        bitset
+    }
+
+    /// Search for all the occurrences of given pattern (can overlap) as a
+    /// roaring bitmap.
+    #[cfg(feature = "roaring")]
+    pub fn search_bitmap(&self, pat: &[u8]) -> roaring::RoaringBitmap {
+        let mut hits = Vec::from(self.search_all(pat));
+        hits.sort_unstable();
+        roaring::RoaringBitmap::from_sorted_iter(hits).unwrap()
+    }
+
+    /// Search for a sub-string that has the longest common prefix of the given pattern.
+    pub fn search_lcp(&self, pat: &[u8]) -> Range<usize> {
+        let (len, hit) = self.best_prefix_match(pat);
+        let start = hit.positions[0] as usize;
+        start..start + len
+    }
+
+    /// Search for the longest prefix of the given pattern that occurs
+    /// somewhere, returning its length and all of its occurrences, like
+    /// `search_full`.
+    ///
+    /// If none of the bytes occurs, the length is 0 and every suffix is an
+    /// occurrence, starting with the empty one at the end.
+    pub fn best_prefix_match(&self, pat: &[u8]) -> (usize, SearchHit<'_>) {
+        let len = self.longest_prefix_len(pat);
+        let ranks = self.search_ranks(&pat[..len]);
+        let hit = SearchHit {
+            positions: &self.sa[ranks.clone()],
+            sa_range: ranks,
+        };
+        (len, hit)
+    }
+
+    /// Length of the longest prefix of the given pattern that occurs
+    /// somewhere.
+    fn longest_prefix_len(&self, pat: &[u8]) -> usize {
+        let s = self.text();
+        let sa = &self.sa[self.get_bucket(pat)];
 
-#[cfg(feature = "pack")]
-use super::packed_sa::PackedSuffixArray;
-use super::saca::saca;
-use super::utils::*;
+        if sa.is_empty() {
+            // lcp(pat, s[i..]) < k for any i, where k is the number of bytes
+            // the bucket is indexed by, so find the longest prefix that
+            // has a non-empty bucket.
+            let k = Ord::min(pat.len(), self.bucket_depth());
+            return (1..k)
+                .rev()
+                .find(|&len| !self.get_bucket(&pat[..len]).is_empty())
+                .unwrap_or(0);
+        }
 
-/// Suffix array for byte string.
-#[derive(Clone)]
-pub struct SuffixArray<'a> {
-    s: &'a [u8],
-    sa: Vec<u32>,
-    bkt: Option<Vec<u32>>,
-}
+        // the longest common prefix is shared with either of the suffixes
+        // around the position to insert the pattern.
+        let i = sa.partition_point(|&i| &s[i as usize..] < pat);
+        let before = match i {
+            0 => 0,
+            _ => lcp(pat, &s[sa[i - 1] as usize..]),
+        };
+        let after = match sa.get(i) {
+            Some(&p) => lcp(pat, &s[p as usize..]),
+            None => 0,
+        };
+        Ord::max(before, after)
+    }
 
-impl<'a> SuffixArray<'a> {
-    // Construct new suffix array for given byte string.
-    pub fn new(s: &'a [u8]) -> Self {
-        let mut sa = vec![0; s.len() + 1];
-        saca(s, &mut sa[..]);
-        SuffixArray { s, sa, bkt: None }
+    /// Search for a sub-string that is the longest suffix of the given
+    /// pattern, which is empty at the end if none of the bytes occurs.
+    ///
+    /// The suffix array of the reversed byte string is constructed on first
+    /// use and then cached, taking additional 5 bytes per byte.
+    pub fn search_lcs_suffix(&self, pat: &[u8]) -> Range<usize> {
+        let n = self.len();
+        let rev_pat: Vec<u8> = pat.iter().rev().cloned().collect();
+        let found = self.reverse().search_lcp(&rev_pat[..]);
+        if found.is_empty() {
+            return n..n;
+        }
+        n - found.end..n - found.start
     }
 
-    // Construct suffix array in place.
-    pub fn set(&mut self, s: &'a [u8]) {
-        self.sa.resize(s.len() + 1, 0);
-        saca(s, &mut self.sa[..]);
+    /// Search for all the positions where an occurrence of given pattern
+    /// ends, i.e. the prefixes of the byte string ending with the pattern.
+    ///
+    /// The positions are ordered by the reversed prefixes they end, which
+    /// are searched in the suffix array of the reversed byte string, see
+    /// `enable_reverse_index`.
+    pub fn search_ends(&self, pat: &[u8]) -> Vec<u32> {
+        let n = self.len();
+        let rev_pat: Vec<u8> = pat.iter().rev().cloned().collect();
+        let rev = self.reverse().search_all(&rev_pat[..]);
+        rev.iter().map(|&p| (n - p as usize) as u32).collect()
     }
 
-    // Release the unused memory of suffix array.
-    pub fn fit(&mut self) {
-        self.sa.shrink_to_fit()
+    /// Search for the longest suffix of the given pattern that occurs
+    /// somewhere, returning its length and the unsorted positions where it
+    /// starts, like `search_lcp_all` in the other direction.
+    ///
+    /// The suffix array of the reversed byte string is constructed on first
+    /// use, see `enable_reverse_index`.
+    pub fn search_suffixes_of(&self, pat: &[u8]) -> (usize, Vec<u32>) {
+        let n = self.len();
+        let rev_pat: Vec<u8> = pat.iter().rev().cloned().collect();
+        let (len, hit) = self.reverse().best_prefix_match(&rev_pat[..]);
+        let positions = hit.positions.iter();
+        let positions = positions.map(|&p| (n - p as usize - len) as u32);
+        (len, positions.collect())
     }
 
-    /// Length of the underlying byte string.
-    pub fn len(&self) -> usize {
-        self.s.len()
+    /// Find the overlaps of at least `min_len` bytes between the underlying
+    /// byte string and `other`, in both directions.
+    ///
+    /// Each direction walks a cursor along `other`, taking O(m log n) time,
+    /// and the inverse suffix array is computed on first use.
+    pub fn overlaps(&self, other: &[u8], min_len: usize) -> Overlaps {
+        overlaps(self, other, min_len)
     }
 
-    /// Test if the underlying byte string is empty.
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
+    /// Search for the longest common prefix length of the given pattern that
+    /// matches somewhere, together with all the positions achieving it.
+    ///
+    /// The positions are yielded unsorted, like `search_all`.
+    pub fn search_lcp_all(
+        &self,
+        pat: &[u8],
+    ) -> (usize, impl Iterator<Item = u32> + '_) {
+        let (len, hit) = self.best_prefix_match(pat);
+        let positions = hit.positions.iter().cloned();
+        (len, positions)
     }
 
-    /// Take out the suffix array and its corresponding byte string.
-    pub fn into_parts(self) -> (&'a [u8], Vec<u32>) {
-        (self.s, self.sa)
+    /// Iterate over the right-maximal sub-strings of at least `min_len`
+    /// bytes, that occur at least `min_count` (and at least twice) times,
+    /// yielding the sub-string and its unsorted occurrences.
+    ///
+    /// The LCP array is computed upfront unless kept in plain, taking
+    /// additional 8 bytes per byte of the underlying byte string.
+    pub fn substrings_with_min_count(
+        &self,
+        min_len: usize,
+        min_count: usize,
+    ) -> impl Iterator<Item = (&[u8], &[u32])> + '_ {
+        let lcp = self.lcp_array();
+        LcpIntervals::new(self.text(), &self.sa[..], lcp, min_len, min_count)
     }
 
-    /// Compose existed suffix array and its corresponding byte string
-    /// together, and checks the integrity.
-    pub fn from_parts(s: &'a [u8], sa: Vec<u32>) -> Option<Self> {
-        let compose = SuffixArray { s, sa, bkt: None };
-        if compose.check_integrity() {
-            Some(compose)
-        } else {
-            None
-        }
+    /// Find the `k` most frequent right-maximal sub-strings of at least
+    /// `min_len` bytes that occur at least twice, the longer and then the
+    /// lexicographically smaller first on ties.
+    ///
+    /// The LCP array is computed upfront unless kept in plain, taking
+    /// additional 8 bytes per byte of the underlying byte string.
+    pub fn top_substrings(
+        &self,
+        k: usize,
+        min_len: usize,
+    ) -> Vec<FrequentSubstring> {
+        top_k(self.substrings_with_min_count(min_len, 2), k)
     }
 
-    /// Compose existed suffix array and its corresponding byte string
-    /// together without integrity check.
-    pub unsafe fn unchecked_from_parts(s: &'a [u8], sa: Vec<u32>) -> Self {
-        SuffixArray { s, sa, bkt: None }
+    /// Iterate over the distinct sub-strings of length `k` (at least 1) in
+    /// lexicographic order, yielding the sub-string and its number of
+    /// occurrences.
+    ///
+    /// The LCP array is computed upfront unless kept in plain, taking
+    /// additional 8 bytes per byte of the underlying byte string.
+    pub fn kmer_counts(
+        &self,
+        k: usize,
+    ) -> impl Iterator<Item = (&[u8], u32)> + '_ {
+        let lcp = self.lcp_array();
+        KmerCounts::new(self.text(), &self.sa[..], lcp, k)
     }
 
-    fn check_integrity(&self) -> bool {
-        if self.s.len() + 1 != self.sa.len() {
-            return false;
-        }
-        for i in 1..self.sa.len() {
-            let x = &self.s[self.sa[i - 1] as usize..];
-            let y = &self.s[self.sa[i] as usize..];
-            if x >= y {
-                return false;
+    /// Count the distinct sub-strings of each length from 1 to `max_k`, where
+    /// the i-th entry is of length i + 1.
+    ///
+    /// The LCP array is computed upfront unless kept in plain, taking
+    /// additional 8 bytes per byte of the underlying byte string.
+    pub fn complexity_profile(&self, max_k: usize) -> Vec<u64> {
+        let n = self.len();
+        let lcp = self.lcp_array();
+
+        // each suffix starts the distinct sub-strings longer than its LCP.
+        let mut diff = vec![0i64; max_k + 2];
+        for (&p, &h) in self.sa.iter().zip(lcp.iter()) {
+            let lo = h as usize + 1;
+            let hi = Ord::min(n - p as usize, max_k);
+            if lo <= hi {
+                diff[lo] += 1;
+                diff[hi + 1] -= 1;
             }
         }
-        true
+
+        let mut count = 0;
+        diff[1..=max_k]
+            .iter()
+            .map(|&d| {
+                count += d;
+                count as u64
+            })
+            .collect()
     }
 
-    /// Enable bucket pointers to speed up large amount of pattern searching.
+    /// Map each position to the number of occurrences of the sub-string of
+    /// length `window` (at least 1) starting there, or 0 if fewer than
+    /// `window` bytes are left, so that rare regions score low.
     ///
-    /// The overhead is about 257 KiB.
-    pub fn enable_buckets(&mut self) {
-        if self.bkt.is_some() {
-            return;
-        }
-
-        // the layout is [$; (0, $), (0, 0), ..., (0, 255); ...; (255, $), (255, 0), ..., (255, 255)]
-        let mut bkt = vec![0; 256 * 257 + 1];
+    /// The LCP array is computed upfront unless kept in plain, taking
+    /// additional 8 bytes per byte of the underlying byte string.
+    pub fn frequency_transform(&self, window: usize) -> Vec<u32> {
+        window_counts(self.text(), &self.sa[..], &self.lcp_array(), window)
+    }
 
-        // count occurrences.
-        bkt[0] = 1;
-        if self.s.len() > 0 {
-            for i in 0..self.s.len() - 1 {
-                let c0 = unsafe { *self.s.get_unchecked(i) };
-                let c1 = unsafe { *self.s.get_unchecked(i + 1) };
-                let idx = (c0 as usize * 257) + (c1 as usize + 1) + 1;
-                bkt[idx] += 1;
-            }
-            let c0 = unsafe { *self.s.get_unchecked(self.s.len() - 1) };
-            let idx = (c0 as usize * 257) + 1;
-            bkt[idx] += 1;
-        }
+    /// Find the leftmost longest palindromic sub-string of the underlying
+    /// byte string, which is empty only if the byte string is empty.
+    ///
+    /// A suffix array of the byte string and its reverse is constructed,
+    /// taking O(n log n) additional space for the LCE queries.
+    pub fn longest_palindrome(&self) -> Range<usize> {
+        longest_palindrome(self.text())
+    }
 
-        // store the right boundaries of each bucket.
-        let mut sum = 0;
-        for p in bkt.iter_mut() {
-            sum += *p;
-            *p = sum;
-        }
+    /// Get a view answering the queries restricted to the given range of the
+    /// underlying byte string, without constructing another suffix array
+    /// until `SubIndexView::to_suffix_array` is called.
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn subindex(&self, range: Range<usize>) -> SubIndexView<'_, T> {
+        SubIndexView::new(self, range)
+    }
 
-        self.bkt = Some(bkt);
+    /// List the regions repeating an earlier part of the underlying byte
+    /// string, at least `min_len` (at least 1) bytes long, in text order,
+    /// e.g. to estimate the space saved by deduplication.
+    ///
+    /// The regions are the long enough factors of the LZ77 factorization,
+    /// so they never overlap each other, and the bytes not covered by them
+    /// are unique or repeated in shorter pieces.
+    pub fn dedup_report(&self, min_len: usize) -> Vec<DupRegion> {
+        dedup_regions(self.text(), &self.sa[..], min_len)
     }
 
-    /// Get the bucket of pattern.
-    #[inline]
-    fn get_bucket(&self, pat: &[u8]) -> Range<usize> {
-        if let Some(ref bkt) = self.bkt {
-            if pat.len() > 1 {
-                // sub-bucket (c0, c1).
-                let c0 = pat[0];
-                let c1 = pat[1];
-                let idx = (c0 as usize * 257) + (c1 as usize + 1) + 1;
-                bkt[idx - 1] as usize..bkt[idx] as usize
-            } else if pat.len() == 1 {
-                // top-level bucket (c0, $)..=(c0, 255).
-                let c0 = pat[0];
-                let start_idx = c0 as usize * 257;
-                let end_idx = start_idx + 257;
-                bkt[start_idx] as usize..bkt[end_idx] as usize
-            } else {
-                // the sentinel bucket.
-                0..1
-            }
+    /// Find the longest sub-string starting at position `pos`, up to
+    /// `max_len` bytes, that occurs elsewhere, returning its range and the
+    /// position of another occurrence, e.g. for dedup chunkers anchoring on
+    /// given offsets. Returns `None` if not even the byte at `pos` repeats,
+    /// or if `pos` is out of range.
+    ///
+    /// The longest one is shared with either of the suffixes next to the
+    /// one at `pos` in the suffix array, the earlier of them on ties. The
+    /// inverse suffix array is computed on first use, see `rank_of`, and
+    /// the kept LCP array is used if any, see `lcp_at`.
+    pub fn longest_match_at(
+        &self,
+        pos: usize,
+        max_len: usize,
+    ) -> Option<(Range<usize>, u32)> {
+        if pos >= self.len() {
+            return None;
+        }
+        let rank = self.rank_of(pos).unwrap() as usize;
+        let before = (self.lcp_at(rank).unwrap(), self.sa[rank - 1]);
+        let after = match self.lcp_at(rank + 1) {
+            Some(len) => (len, self.sa[rank + 1]),
+            None => (0, 0),
+        };
+        let capped =
+            |(len, p): (u32, u32)| (Ord::min(len as usize, max_len), p);
+        let (before, after) = (capped(before), capped(after));
+        let (len, other) = if (after.0, before.1) > (before.0, after.1) {
+            after
         } else {
-            0..self.sa.len()
+            before
+        };
+        if len == 0 {
+            return None;
         }
+        Some((pos..pos + len, other))
     }
 
-    /// Get the top-level bucket.
-    #[inline]
-    fn get_top_bucket(&self, pat: &[u8]) -> Range<usize> {
-        if let Some(ref bkt) = self.bkt {
-            if pat.len() > 0 {
-                let c0 = pat[0];
-                let start_idx = c0 as usize * 257;
-                let end_idx = start_idx + 257;
-                bkt[start_idx] as usize..bkt[end_idx] as usize
-            } else {
-                0..1
-            }
-        } else {
-            0..self.sa.len()
-        }
+    /// Find the starting position of the lexicographically smallest rotation
+    /// of the underlying byte string, the leftmost one if there are ties,
+    /// e.g. to canonicalize circular sequences. It is 0 for the empty one.
+    ///
+    /// The rotations are compared directly in O(n) time, without the suffix
+    /// array of the doubled byte string.
+    pub fn min_rotation(&self) -> usize {
+        min_rotation(self.text())
     }
 
-    /// Test if it contains the given pattern.
-    pub fn contains(&self, pat: &[u8]) -> bool {
-        let s = self.s;
-        let sa = &self.sa[self.get_bucket(pat)];
+    /// Search for all the occurrences of given pattern in text order, with
+    /// the underlying byte string treated as circular, so that the
+    /// occurrences could wrap around the end, even several times if the
+    /// pattern is longer than the byte string.
+    pub fn search_circular(&self, pat: &[u8]) -> Vec<u32> {
+        search_circular(self, pat)
+    }
 
-        sa.binary_search_by_key(&pat, |&i| trunc(&s[i as usize..], pat.len()))
-            .is_ok()
+    /// Snap the position down to the nearest character boundary of the
+    /// underlying byte string as UTF-8, e.g. a `&str`, clamped to `len()`.
+    pub fn snap_to_char_boundary(&self, pos: usize) -> usize {
+        snap_to_char_boundary(self.text(), pos)
     }
 
-    /// Search for all the unsorted occurrence of given pattern (can overlap).
-    pub fn search_all(&self, pat: &[u8]) -> &[u32] {
-        let s = self.s;
-        let sa = if pat.len() > 0 {
-            &self.sa[self.get_bucket(pat)]
-        } else {
-            &self.sa[..]
-        };
+    /// Search for all the occurrences of given pattern in text order like
+    /// `search_all_sorted`, yielding the character index and the byte
+    /// position of each occurrence in the underlying byte string as UTF-8,
+    /// e.g. to map the matches back to the characters shown to users.
+    ///
+    /// The occurrences not starting at a character boundary are skipped,
+    /// which are possible only for the empty pattern or invalid UTF-8.
+    pub fn search_all_chars(
+        &self,
+        pat: &[u8],
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let mut found = self.search_all_sorted(pat);
+        found.retain(|&p| self.snap_to_char_boundary(p as usize) == p as usize);
+        self.char_positions(found.into_iter().map(|p| p as usize))
+    }
 
-        let mut i = 0;
-        let mut k = sa.len();
-        while i < k {
-            let m = i + (k - i) / 2;
-            if pat > &s[sa[m] as usize..] {
-                i = m + 1;
-            } else {
-                k = m;
-            }
-        }
+    /// Map the byte positions in ascending order, e.g. the results of
+    /// `search_all_sorted`, to the pairs of character index and byte
+    /// position in the underlying byte string as UTF-8.
+    ///
+    /// Panics if the positions are not in ascending order.
+    pub fn char_positions<'a, I>(
+        &'a self,
+        positions: I,
+    ) -> impl Iterator<Item = (usize, usize)> + 'a
+    where
+        I: IntoIterator<Item = usize>,
+        I::IntoIter: 'a,
+    {
+        char_positions(self.text(), positions)
+    }
 
-        let mut j = i;
-        let mut k = sa.len();
-        while j < k {
-            let m = j + (k - j) / 2;
-            if s[sa[m] as usize..].starts_with(pat) {
-                j = m + 1;
-            } else {
-                k = m;
+    /// Compute the matching statistics of query, i.e. the length of the
+    /// longest prefix of `query[i..]` that occurs in the underlying byte
+    /// string, for each position `i` of query.
+    ///
+    /// The query is matched in one pass, extending the match at the end and
+    /// dropping its first byte by the suffix link. It takes the LCP array
+    /// with its previous and next smaller values, prepared in linear time,
+    /// and the inverse suffix array, see `SuffixArray::rank_of`.
+    pub fn matching_statistics(&self, query: &[u8]) -> Vec<usize> {
+        let mut ms = Vec::with_capacity(query.len());
+        for_each_statistic(self, query, |_, len| ms.push(len));
+        ms
+    }
+
+    /// Find the regions of the underlying byte string covered by the matches
+    /// of at least `min_len` (at least 1) bytes into the byte string of
+    /// `other`, as sorted, disjoint and non-adjacent ranges.
+    ///
+    /// The matches are slid along the byte string in one pass, see
+    /// `matching_statistics`.
+    pub fn coverage_against<U: TextStorage>(
+        &self,
+        other: &SuffixArray<U>,
+        min_len: usize,
+    ) -> Vec<Range<usize>> {
+        let min_len = Ord::max(min_len, 1);
+        let mut coverage: Vec<Range<usize>> = Vec::new();
+        for_each_statistic(other, self.text(), |i, len| {
+            if len < min_len {
+                return;
             }
-        }
+            match coverage.last_mut() {
+                Some(last) if last.end >= i => {
+                    last.end = Ord::max(last.end, i + len)
+                }
+                _ => coverage.push(i..i + len),
+            }
+        });
+        coverage
+    }
 
-        &sa[i..j]
+    /// Narrow down the range of suffixes sharing the leading `depth` bytes to
+    /// those followed by `c`.
+    pub(crate) fn refine(
+        &self,
+        range: Range<usize>,
+        depth: usize,
+        c: u8,
+    ) -> Range<usize> {
+        let s = self.text();
+        let sa = &self.sa[range.clone()];
+        let key = |&p: &u32| s.get(p as usize + depth).cloned();
+        let i = sa.partition_point(|p| key(p) < Some(c));
+        let j = i + sa[i..].partition_point(|p| key(p) == Some(c));
+        range.start + i..range.start + j
     }
 
-    /// Search for a sub-string that has the longest common prefix of the given pattern.
-    pub fn search_lcp(&self, pat: &[u8]) -> Range<usize> {
-        let s = self.s;
-        let sa = &self.sa[self.get_bucket(pat)];
+    /// Test if any window of `window_len` bytes of the stream occurs in the
+    /// underlying byte string, stopping at the first one found.
+    ///
+    /// The longest suffix of the stream read so far that occurs in the byte
+    /// string is maintained online, together with its range in the suffix
+    /// array, like computing the matching statistics.
+    ///
+    /// Each call first prepares the previous and next smaller values of the
+    /// LCP array in linear time, taking 8 bytes per byte until it returns,
+    /// plus 4 bytes per byte for the LCP array itself unless it is kept by
+    /// `enable_lcp` in plain. The inverse suffix array is built on first
+    /// use and kept, see `rank_of`.
+    pub fn contains_any_window<R: Read>(
+        &self,
+        mut reader: R,
+        window_len: usize,
+    ) -> Result<bool> {
+        if window_len == 0 {
+            return Ok(true);
+        }
 
-        if sa.len() == 0 {
-            // pat.len() > 0, for any i < s.len(): lcp(pat, s[i..]) <= 1.
-            let sa = &self.sa[self.get_top_bucket(pat)];
-            if sa.len() > 0 {
-                // there exists i < s.len(): lcp(pat, s[i..]) == 1.
-                let i = sa[0] as usize;
-                return i..i + 1;
-            } else {
-                // for any i < s.len(): lcp(pat, s[i..]) == 0.
-                return self.s.len()..self.s.len();
+        let mut matcher = Matcher::new(self);
+        let mut buf = vec![0; 8192];
+        loop {
+            let n = match reader.read(&mut buf[..]) {
+                Ok(0) => return Ok(false),
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            for &c in buf[..n].iter() {
+                // drop the leading bytes of the match until c follows, or
+                // the match is empty if c never occurs.
+                while !matcher.extend(c) && matcher.len() > 0 {
+                    matcher.drop_front();
+                }
+                if matcher.len() >= window_len {
+                    return Ok(true);
+                }
             }
         }
+    }
 
-        match sa.binary_search_by(|&i| s[i as usize..].cmp(pat)) {
-            Ok(i) => {
-                // find a suffix equals to the pattern.
-                let start = sa[i] as usize;
-                start..s.len()
+    /// Score the similarity between the underlying byte string and query.
+    ///
+    /// The Jaccard index takes a scan over the LCP array, which is computed
+    /// on the fly, to count the distinct q-grams of the byte string.
+    pub fn similarity(&self, query: &[u8], kind: SimilarityKind) -> f64 {
+        match kind {
+            SimilarityKind::LcsLen => {
+                let ms = self.matching_statistics(query);
+                ms.into_iter().max().unwrap_or(0) as f64
             }
-            Err(i) => {
-                // find a position to insert the pattern.
-                if i > 0 && i < sa.len() {
-                    let start_a = sa[i - 1] as usize;
-                    let start_b = sa[i] as usize;
-                    let len_a = lcp(pat, &s[start_a..]);
-                    let len_b = lcp(pat, &s[start_b..]);
-                    if len_a > len_b {
-                        start_a..start_a + len_a
-                    } else {
-                        start_b..start_b + len_b
-                    }
-                } else if i == 0 {
-                    let start = sa[i] as usize;
-                    let len = lcp(pat, &s[start..]);
-                    start..start + len
+            SimilarityKind::MatchingStatisticsSum => {
+                let ms = self.matching_statistics(query);
+                ms.into_iter().sum::<usize>() as f64
+            }
+            SimilarityKind::Jaccard(0) => 0.0,
+            SimilarityKind::Jaccard(q) => {
+                let grams = query_qgrams(query, q);
+                let common =
+                    grams.iter().filter(|&&gram| self.contains(gram)).count();
+                let union = distinct_qgrams(self.text(), &self.sa[..], q)
+                    + grams.len()
+                    - common;
+                if union == 0 {
+                    0.0
                 } else {
-                    let start = sa[i - 1] as usize;
-                    let len = lcp(pat, &s[start..]);
-                    start..start + len
+                    common as f64 / union as f64
                 }
             }
         }
     }
 
+    /// Test if the underlying byte string starts with the given pattern.
+    pub fn starts_with(&self, pat: &[u8]) -> bool {
+        self.text().starts_with(pat)
+    }
+
+    /// Test if the underlying byte string ends with the given pattern.
+    ///
+    /// The suffix equal to the pattern sorts before the others it prefixes,
+    /// so only the first suffix of the matched range is checked.
+    pub fn ends_with(&self, pat: &[u8]) -> bool {
+        match self.search_all(pat).first() {
+            Some(&i) => i as usize + pat.len() == self.len(),
+            None => false,
+        }
+    }
+
+    /// Test if the given pattern occurs at position `pos`.
+    ///
+    /// Returns false if `pos` is out of range.
+    pub fn matches_at(&self, pos: usize, pat: &[u8]) -> bool {
+        match self.text().get(pos..) {
+            Some(suffix) => suffix.starts_with(pat),
+            None => false,
+        }
+    }
+
+    /// Plan the replacements of all the non-overlapping occurrences of given
+    /// pattern, scanning from left to right.
+    ///
+    /// The returned splices are sorted by position.
+    pub fn plan_replacements<'r>(
+        &self,
+        pat: &[u8],
+        replacement: &'r [u8],
+    ) -> Vec<Splice<'r>> {
+        let mut hits = Vec::from(self.search_all(pat));
+        hits.sort_unstable();
+
+        let mut splices = Vec::with_capacity(hits.len());
+        let mut last = 0;
+        for i in hits.into_iter().map(|i| i as usize) {
+            if i < last {
+                continue;
+            }
+            splices.push(Splice {
+                range: i..i + pat.len(),
+                replacement,
+            });
+            last = i + pat.len();
+        }
+        splices
+    }
+
+    /// Apply the planned splices to a copy of the underlying byte string.
+    pub fn apply_replacements(&self, splices: &[Splice]) -> Vec<u8> {
+        apply_splices(self.text(), splices)
+    }
+
+    /// Dump the suffix array as raw little endian array without the sentinel,
+    /// following the file convention of libdivsufsort.
+    pub fn dump_raw<W: Write>(&self, file: W, width: RawWidth) -> Result<()> {
+        write_raw(file, self.raw_sa(), width)
+    }
+
+    /// Load suffix array from raw little endian array without the sentinel,
+    /// following the file convention of libdivsufsort.
+    pub fn load_raw<R: Read>(s: T, file: R, width: RawWidth) -> Result<Self> {
+        let n = s.as_ref().len();
+        if n > MAX_LENGTH {
+            return Err(Error::TooLong(n).into());
+        }
+        let sa = read_raw(file, n, width)?;
+        Self::from_parts(s, sa).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "inconsistent suffix array",
+            )
+        })
+    }
+
     /// Dump the suffix array.
     #[cfg(feature = "pack")]
     pub fn dump<W: Write>(&self, file: W) -> Result<()> {
@@ -277,20 +2022,118 @@ impl<'a> SuffixArray<'a> {
         psa.dump_bytes()
     }
 
+    /// Dump the suffix array together with the underlying byte string, which
+    /// could be loaded as `OwnedSuffixArray`.
+    ///
+    /// The byte string is compressed if the `zstd` feature is enabled.
+    #[cfg(feature = "pack")]
+    pub fn dump_with_text<W: Write>(&self, file: W) -> Result<()> {
+        let index = PackedIndex::new(self.text(), &self.sa[..])?;
+        index.dump(file)
+    }
+
+    /// Create a file and write the suffix array together with the underlying
+    /// byte string.
+    #[cfg(feature = "pack")]
+    pub fn dump_with_text_file<P: AsRef<Path>>(&self, name: P) -> Result<()> {
+        use std::fs::File;
+        use std::io::BufWriter;
+
+        let file = BufWriter::new(File::create(name)?);
+        self.dump_with_text(file)
+    }
+
+    /// Dump the suffix array as an archive together with the given optional
+    /// sections, which could be loaded lazily by `Archive`.
+    ///
+    /// The bucket pointers are computed at depth 2 if not enabled yet, the
+    /// suffix array of the reversed byte string is constructed and kept if
+    /// not yet, and the LCP array and the frequent sub-strings are computed
+    /// on the fly.
+    #[cfg(feature = "pack")]
+    pub fn dump_archive<W: Write>(
+        &self,
+        file: W,
+        sections: &[Section],
+    ) -> Result<()> {
+        let computed;
+        let bkt = if !sections.contains(&Section::Buckets) {
+            None
+        } else if let Some(ref bkt) = self.bkt {
+            Some(bkt)
+        } else {
+            computed = Buckets::new(self.text(), 2);
+            Some(&computed)
+        };
+        let lcp = if sections.contains(&Section::Lcp) {
+            Some(self.compute_lcp())
+        } else {
+            None
+        };
+        let top = sections.iter().find_map(|&section| match section {
+            Section::TopSubstrings { k, min_len } => {
+                Some(self.top_substrings(k, min_len))
+            }
+            _ => None,
+        });
+        let rev = if sections.contains(&Section::ReverseIndex) {
+            Some(&self.reverse().sa[..])
+        } else {
+            None
+        };
+        let (sa, lcp, top) = (&self.sa[..], lcp.as_deref(), top.as_deref());
+        write_archive(file, sa, bkt, lcp, top, rev)
+    }
+
+    /// Export the occurrences of each pattern of the dictionary into
+    /// compressed posting lists, for hybrid search with an inverted index.
+    #[cfg(feature = "pack")]
+    pub fn posting_lists<P: AsRef<[u8]>>(
+        &self,
+        patterns: &[P],
+    ) -> PostingLists {
+        PostingLists::new(patterns.iter().map(|pat| {
+            let pat = pat.as_ref();
+            (pat.to_vec(), self.search_all_sorted(pat))
+        }))
+    }
+
+    /// Compute the LCP array, including the sentinel.
+    #[cfg(feature = "pack")]
+    pub(crate) fn compute_lcp(&self) -> Vec<u32> {
+        self.lcp_array().into_owned()
+    }
+
+    /// Replace the suffix array of the reversed byte string with the loaded
+    /// one.
+    #[cfg(feature = "pack")]
+    pub(crate) fn set_reverse(&mut self, rev: SuffixArray<Vec<u8>>) {
+        self.rev = OnceLock::from(Box::new(rev));
+    }
+
+    /// Replace the bucket pointers with the loaded ones.
+    #[cfg(feature = "pack")]
+    pub(crate) fn set_buckets(&mut self, bkt: Buckets) {
+        self.bkt = Some(bkt);
+        self.lazy_bkt = None;
+    }
+
     /// Load suffix array from reader without integrity check.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the loaded suffix array is exactly the
+    /// suffix array of `s`.
     #[cfg(feature = "pack")]
-    pub unsafe fn unchecked_load<R: Read>(
-        s: &'a [u8],
-        file: R,
-    ) -> Result<Self> {
+    pub unsafe fn unchecked_load<R: Read>(s: T, file: R) -> Result<Self> {
         let psa = PackedSuffixArray::load(file)?;
-        let sa = psa.into_sa();
+        let sa = psa.into_sa_of(s.as_ref().len())?;
         Ok(Self::unchecked_from_parts(s, sa))
     }
 
     /// Load suffix array from reader.
     #[cfg(feature = "pack")]
-    pub fn load<R: Read>(s: &'a [u8], file: R) -> Result<Self> {
+    pub fn load<R: Read>(s: T, file: R) -> Result<Self> {
         use std::io::{Error, ErrorKind};
 
         let sa = unsafe { Self::unchecked_load(s, file)? };
@@ -305,9 +2148,14 @@ impl<'a> SuffixArray<'a> {
     }
 
     /// Load suffix array from a file without integrity check.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the loaded suffix array is exactly the
+    /// suffix array of `s`.
     #[cfg(feature = "pack")]
     pub unsafe fn unchecked_load_file<P: AsRef<Path>>(
-        s: &'a [u8],
+        s: T,
         name: P,
     ) -> Result<Self> {
         use std::fs::File;
@@ -319,7 +2167,7 @@ impl<'a> SuffixArray<'a> {
 
     /// Load suffix array from a file.
     #[cfg(feature = "pack")]
-    pub fn load_file<P: AsRef<Path>>(s: &'a [u8], name: P) -> Result<Self> {
+    pub fn load_file<P: AsRef<Path>>(s: T, name: P) -> Result<Self> {
         use std::io::{Error, ErrorKind};
 
         let sa = unsafe { Self::unchecked_load_file(s, name)? };
@@ -334,19 +2182,21 @@ impl<'a> SuffixArray<'a> {
     }
 
     /// Load suffix array from bytes without integrity check.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the loaded suffix array is exactly the
+    /// suffix array of `s`.
     #[cfg(feature = "pack")]
-    pub unsafe fn unchecked_load_bytes(
-        s: &'a [u8],
-        bytes: &[u8],
-    ) -> Result<Self> {
+    pub unsafe fn unchecked_load_bytes(s: T, bytes: &[u8]) -> Result<Self> {
         let psa = PackedSuffixArray::load_bytes(bytes)?;
-        let sa = psa.into_sa();
+        let sa = psa.into_sa_of(s.as_ref().len())?;
         Ok(Self::unchecked_from_parts(s, sa))
     }
 
     /// Load suffix array from bytes.
     #[cfg(feature = "pack")]
-    pub fn load_bytes(s: &'a [u8], bytes: &[u8]) -> Result<Self> {
+    pub fn load_bytes(s: T, bytes: &[u8]) -> Result<Self> {
         use std::io::{Error, ErrorKind};
 
         let sa = unsafe { Self::unchecked_load_bytes(s, bytes)? };
@@ -361,14 +2211,166 @@ impl<'a> SuffixArray<'a> {
     }
 }
 
-impl<'a> From<SuffixArray<'a>> for Vec<u32> {
-    fn from(sa: SuffixArray<'a>) -> Vec<u32> {
+/// Suffix array owning its byte string.
+pub type OwnedSuffixArray = SuffixArray<Vec<u8>>;
+
+impl SuffixArray<Vec<u8>> {
+    /// Load suffix array together with its byte string from reader.
+    #[cfg(feature = "pack")]
+    pub fn load_owned<R: Read>(file: R) -> Result<Self> {
+        use std::io::{Error, ErrorKind};
+
+        let (text, sa) = PackedIndex::load(file)?.into_parts()?;
+        Self::from_parts(text, sa).ok_or_else(|| {
+            Error::new(ErrorKind::InvalidData, "inconsistent suffix array")
+        })
+    }
+
+    /// Load suffix array together with its byte string from a file.
+    #[cfg(feature = "pack")]
+    pub fn load_owned_file<P: AsRef<Path>>(name: P) -> Result<Self> {
+        use std::fs::File;
+        use std::io::BufReader;
+
+        let file = BufReader::new(File::open(name)?);
+        Self::load_owned(file)
+    }
+
+    /// Replace the bytes in the range with new ones, e.g. for interactive
+    /// editing, and return whether the suffix array is repaired locally
+    /// instead of rebuilt.
+    ///
+    /// The suffixes starting in the edited range, and the ones before it
+    /// whose order is not settled by the bytes before the range, are
+    /// re-sorted and merged into the others, comparing no more bytes than
+    /// the affected ones. The suffix array is rebuilt if more than
+    /// `MAX_REPAIR` suffixes are affected, a comparison runs past them, as
+    /// in a repetitive byte string, or the repaired ones fail the
+    /// verification against their neighbors. The enabled structures are
+    /// rebuilt in both cases.
+    ///
+    /// Panics if the range is out of bounds, or the edited byte string is
+    /// longer than `MAX_LENGTH`.
+    pub fn replace_range(&mut self, range: Range<usize>, new: &[u8]) -> bool {
+        let (a, b) = (range.start, range.end);
+        assert!(a <= b && b <= self.s.len(), "range out of bounds");
+        let len = self.s.len() - (b - a) + new.len();
+        assert!(len <= MAX_LENGTH, "byte string too long");
+
+        let lo = affected_start(&self.s[..], &self.sa[..], a, MAX_REPAIR)
+            .filter(|&lo| a - lo + new.len() <= MAX_REPAIR);
+        self.s.splice(a..b, new.iter().cloned());
+        let repaired = lo.is_some_and(|lo| {
+            repair(&self.s[..], &mut self.sa, lo, (a, b), new.len())
+        });
+        if repaired {
+            self.rebuild_structures();
+        } else {
+            self.rebuild();
+        }
+        repaired
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl SuffixArray<memmap2::Mmap> {
+    /// Map the file read-only and construct its suffix array, borrowing the
+    /// map as the byte string.
+    ///
+    /// The pages are advised to be read ahead sequentially during the
+    /// construction. The file must not be modified while it is mapped.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if the file is longer than
+    /// `MAX_LENGTH`.
+    pub fn new_from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        use std::fs::File;
+
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        if len > MAX_LENGTH as u64 {
+            let len = usize::try_from(len).unwrap_or(usize::MAX);
+            return Err(Error::TooLong(len).into());
+        }
+
+        // the mapped pages are only read, see the safety note above.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        if map.len() > MAX_LENGTH {
+            return Err(Error::TooLong(map.len()).into());
+        }
+
+        // the advices are mere hints, so their failures are ignored.
+        #[cfg(unix)]
+        {
+            use memmap2::Advice;
+            let _ = map.advise(Advice::WillNeed);
+            let _ = map.advise(Advice::Sequential);
+        }
+        let sa = SuffixArray::new(map);
+        #[cfg(unix)]
+        let _ = sa.s.advise(memmap2::Advice::Normal);
+        Ok(sa)
+    }
+
+    /// Warm the index like `warm`, advising the kernel to read ahead the
+    /// whole map beforehand at `WarmLevel::Full`, and to expect random
+    /// accesses afterwards, as the queries jump between the suffixes.
+    pub fn warm_with_advice(&self, level: WarmLevel) -> usize {
+        // the advices are mere hints, so their failures are ignored.
+        #[cfg(unix)]
+        if level == WarmLevel::Full {
+            let _ = self.s.advise(memmap2::Advice::WillNeed);
+        }
+        let touched = self.warm(level);
+        #[cfg(unix)]
+        let _ = self.s.advise(memmap2::Advice::Random);
+        touched
+    }
+}
+
+impl<T> From<SuffixArray<T>> for Vec<u32> {
+    fn from(sa: SuffixArray<T>) -> Vec<u32> {
         sa.sa
     }
 }
 
-impl<'a> AsRef<[u8]> for SuffixArray<'a> {
+impl<T: TextStorage> From<&SuffixArray<T>> for RlfmIndex {
+    fn from(sa: &SuffixArray<T>) -> RlfmIndex {
+        RlfmIndex::from_parts(sa.text(), &sa.sa[..])
+    }
+}
+
+impl SampledIndex {
+    /// Construct sampled FM-index from the suffix array, where `base_rate`
+    /// must be a power of two.
+    pub fn from_suffix_array<T: TextStorage>(
+        sa: &SuffixArray<T>,
+        base_rate: usize,
+    ) -> Self {
+        SampledIndex::from_parts(sa.text(), &sa.sa[..], base_rate)
+    }
+}
+
+/// Suffix arrays are equal if their byte strings have the same contents,
+/// regardless of the storage and the auxiliary structures.
+impl<T: TextStorage, U: TextStorage> PartialEq<SuffixArray<U>>
+    for SuffixArray<T>
+{
+    fn eq(&self, other: &SuffixArray<U>) -> bool {
+        self.text() == other.text() && self.sa == other.sa
+    }
+}
+
+impl<T: TextStorage> Eq for SuffixArray<T> {}
+
+impl<T: TextStorage> Hash for SuffixArray<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.text().hash(state);
+        self.sa.hash(state);
+    }
+}
+
+impl<T: TextStorage> AsRef<[u8]> for SuffixArray<T> {
     fn as_ref(&self) -> &[u8] {
-        self.s
+        self.text()
     }
 }
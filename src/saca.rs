@@ -2,16 +2,78 @@ use std::slice::from_raw_parts_mut;
 
 use cdivsufsort::sort_in_place as dss;
 
+use super::doubling::prefix_doubling;
+use super::progress::Phase;
+use super::sais::{sais_bytes, SaisBuffers};
+use super::stats::{Algorithm, IndexStats};
+
 /// Maximum length of the input string.
-pub const MAX_LENGTH: usize = std::i32::MAX as usize;
+///
+/// It is capped by both the 32-bit signed integers used by the construction
+/// algorithm, and the `usize` of target (leaving room for the sentinel), so
+/// that any suffix array entry not exceeding it converts to `usize` losslessly.
+pub const MAX_LENGTH: usize = max_length(usize::MAX as u64) as usize;
+
+/// The cap of input length on target with given `usize::MAX`.
+pub(crate) const fn max_length(usize_max: u64) -> u64 {
+    if (i32::MAX as u64) < usize_max {
+        i32::MAX as u64
+    } else {
+        usize_max - 1
+    }
+}
 
-/// Wrapper of the underlying suffix array construction algorithm.
+/// Wrapper of the underlying suffix array construction algorithm, selected
+/// by the sampled statistics of input.
 pub fn saca(s: &[u8], sa: &mut [u32]) {
+    saca_with(s, sa, IndexStats::sample(s).algorithm)
+}
+
+/// Wrapper of the given suffix array construction algorithm.
+pub fn saca_with(s: &[u8], sa: &mut [u32], algorithm: Algorithm) {
+    saca_with_progress(s, sa, algorithm, &mut |_| {})
+}
+
+/// Wrapper of the given suffix array construction algorithm, reporting the
+/// phases.
+pub fn saca_with_progress(
+    s: &[u8],
+    sa: &mut [u32],
+    algorithm: Algorithm,
+    progress: &mut dyn FnMut(Phase),
+) {
+    let mut buf = SaisBuffers::default();
+    saca_with_buffers(s, sa, algorithm, progress, &mut buf)
+}
+
+/// Wrapper of the given suffix array construction algorithm, reporting the
+/// phases and reusing the scratch buffers of SA-IS.
+pub fn saca_with_buffers(
+    s: &[u8],
+    sa: &mut [u32],
+    algorithm: Algorithm,
+    progress: &mut dyn FnMut(Phase),
+    buf: &mut SaisBuffers,
+) {
     assert!(s.len() <= MAX_LENGTH);
     assert_eq!(s.len() + 1, sa.len());
 
-    sa[0] = s.len() as u32;
-    dss(s, as_signed_integer_slice(&mut sa[1..]));
+    progress(Phase::Selected(algorithm));
+    match algorithm {
+        Algorithm::Naive => {
+            for (i, p) in sa.iter_mut().enumerate() {
+                *p = i as u32;
+            }
+            sa.sort_unstable_by(|&i, &j| s[i as usize..].cmp(&s[j as usize..]));
+        }
+        Algorithm::Divsufsort => {
+            sa[0] = s.len() as u32;
+            dss(s, as_signed_integer_slice(&mut sa[1..]));
+        }
+        Algorithm::Sais => sais_bytes(s, sa, progress, buf),
+        Algorithm::PrefixDoubling => prefix_doubling(s, sa),
+    }
+    progress(Phase::Done);
 }
 
 fn as_signed_integer_slice(sa: &mut [u32]) -> &mut [i32] {
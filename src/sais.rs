@@ -0,0 +1,303 @@
+use super::progress::Phase;
+use super::saca::MAX_LENGTH;
+
+/// Marks an empty slot of the suffix array under construction.
+const EMPTY: u32 = u32::MAX;
+
+/// String of integer characters, read one by one during the construction.
+pub trait Symbols {
+    /// Length of the string.
+    fn len(&self) -> usize;
+    /// Get the `i`-th character.
+    fn at(&self, i: usize) -> u32;
+}
+
+impl<C: Copy + Into<u32>> Symbols for [C] {
+    #[inline]
+    fn len(&self) -> usize {
+        <[C]>::len(self)
+    }
+
+    #[inline]
+    fn at(&self, i: usize) -> u32 {
+        self[i].into()
+    }
+}
+
+/// Construct suffix array of integer string using the SA-IS algorithm.
+///
+/// Each character of `s` must be less than `k`, and the length of `sa` must
+/// be `s.len() + 1`. Like the byte string suffix arrays of this crate, the
+/// sentinel suffix `s.len()` is stored at `sa[0]`.
+///
+/// The reduced subproblem is built inside `sa` and sorted by a recursive
+/// call on the very same buffer, then overwritten in place when mapping the
+/// result back, so that no copy of the subproblem is ever made. The content
+/// of `s` is left unchanged on return.
+pub fn sais_ints_mut(s: &mut [u32], k: usize, sa: &mut [u32]) {
+    assert!(s.len() <= MAX_LENGTH);
+    assert_eq!(s.len() + 1, sa.len());
+    assert!(s.iter().all(|&c| (c as usize) < k));
+
+    sais(&*s, k, sa, 0, &mut |_| {}, &mut SaisBuffers::default());
+}
+
+/// Construct suffix array of byte string using the SA-IS algorithm,
+/// reporting the phases.
+pub fn sais_bytes(
+    s: &[u8],
+    sa: &mut [u32],
+    progress: &mut dyn FnMut(Phase),
+    buf: &mut SaisBuffers,
+) {
+    assert!(s.len() <= MAX_LENGTH);
+    assert_eq!(s.len() + 1, sa.len());
+
+    sais(s, 256, sa, 0, progress, buf);
+}
+
+/// Construct suffix array of string of characters less than `k` using the
+/// SA-IS algorithm.
+pub fn sais_symbols<S: Symbols + ?Sized>(s: &S, k: usize, sa: &mut [u32]) {
+    assert!(s.len() <= MAX_LENGTH);
+    assert_eq!(s.len() + 1, sa.len());
+
+    sais(s, k, sa, 0, &mut |_| {}, &mut SaisBuffers::default());
+}
+
+/// Scratch buffers of the top level of SA-IS, which could be reused across
+/// constructions, see `Workspace`.
+#[derive(Debug, Clone, Default)]
+pub struct SaisBuffers {
+    stype: Vec<bool>,
+    cnt: Vec<u32>,
+    bkt: Vec<u32>,
+}
+
+impl SaisBuffers {
+    /// Heap memory taken by the buffers, in bytes.
+    pub fn heap_size(&self) -> usize {
+        self.stype.capacity() + (self.cnt.capacity() + self.bkt.capacity()) * 4
+    }
+}
+
+fn sais<S: Symbols + ?Sized>(
+    s: &S,
+    k: usize,
+    sa: &mut [u32],
+    depth: usize,
+    progress: &mut dyn FnMut(Phase),
+    buf: &mut SaisBuffers,
+) {
+    let n = s.len();
+    sa[0] = n as u32;
+    if n == 0 {
+        return;
+    }
+
+    progress(Phase::BucketCounting { depth, length: n });
+    let SaisBuffers { stype, cnt, bkt } = buf;
+    classify(s, stype);
+    let is_lms = |i: usize| i > 0 && stype[i] && !stype[i - 1];
+    cnt.clear();
+    cnt.resize(k, 0);
+    for i in 0..n {
+        cnt[s.at(i) as usize] += 1;
+    }
+
+    // sort the LMS sub-strings by induced sorting.
+    progress(Phase::LmsSorting { depth });
+    for p in sa[1..].iter_mut() {
+        *p = EMPTY;
+    }
+    bucket_tails(&cnt[..], bkt);
+    for i in (1..n).rev() {
+        if is_lms(i) {
+            let c = s.at(i) as usize;
+            sa[bkt[c] as usize] = i as u32;
+            bkt[c] -= 1;
+        }
+    }
+    induce(s, &stype[..], &cnt[..], sa, bkt);
+
+    // compact the sorted LMS sub-strings into the head of sa.
+    let mut n1 = 0;
+    for i in 1..=n {
+        let j = sa[i] as usize;
+        if is_lms(j) {
+            n1 += 1;
+            sa[n1] = j as u32;
+        }
+    }
+
+    // name the LMS sub-strings, storing name of j at sa[n1 + 1 + j / 2].
+    progress(Phase::Naming { depth });
+    for p in sa[n1 + 1..].iter_mut() {
+        *p = EMPTY;
+    }
+    let mut name = 0;
+    let mut prev = None;
+    for i in 1..=n1 {
+        let j = sa[i] as usize;
+        let same = match prev {
+            Some(p) => same_lms(s, &stype[..], p, j),
+            None => false,
+        };
+        if !same {
+            name += 1;
+        }
+        prev = Some(j);
+        sa[n1 + 1 + j / 2] = name - 1;
+    }
+    let k1 = name as usize;
+
+    // gather the reduced string into the tail of sa, in text order.
+    let mut w = n + 1;
+    for r in (n1 + 1..=n).rev() {
+        if sa[r] != EMPTY {
+            w -= 1;
+            sa[w] = sa[r];
+        }
+    }
+
+    // sort the suffixes of the reduced string.
+    {
+        let (head, s1) = sa.split_at_mut(n + 1 - n1);
+        let sa1 = &mut head[..n1 + 1];
+        if k1 < n1 {
+            progress(Phase::Recursion {
+                depth: depth + 1,
+                length: n1,
+            });
+            // the buffers of this level are still in use.
+            let mut buf = SaisBuffers::default();
+            sais(&*s1, k1, sa1, depth + 1, progress, &mut buf);
+        } else {
+            sa1[0] = n1 as u32;
+            for (i, &c) in s1.iter().enumerate() {
+                sa1[c as usize + 1] = i as u32;
+            }
+        }
+
+        // overwrite the reduced string with the LMS positions, then map the
+        // reduced suffix array back.
+        let mut w = 0;
+        for i in 1..n {
+            if is_lms(i) {
+                s1[w] = i as u32;
+                w += 1;
+            }
+        }
+        for p in sa1[1..].iter_mut() {
+            *p = s1[*p as usize];
+        }
+    }
+
+    // induce the final suffix array from the sorted LMS suffixes.
+    progress(Phase::Inducing { depth });
+    sa[0] = n as u32;
+    for p in sa[n1 + 1..].iter_mut() {
+        *p = EMPTY;
+    }
+    bucket_tails(&cnt[..], bkt);
+    for i in (1..=n1).rev() {
+        let j = sa[i] as usize;
+        sa[i] = EMPTY;
+        let c = s.at(j) as usize;
+        sa[bkt[c] as usize] = j as u32;
+        bkt[c] -= 1;
+    }
+    induce(s, &stype[..], &cnt[..], sa, bkt);
+}
+
+/// Classify the suffixes into S-type (true) and L-type (false), including
+/// the S-type sentinel.
+fn classify<S: Symbols + ?Sized>(s: &S, stype: &mut Vec<bool>) {
+    let n = s.len();
+    stype.clear();
+    stype.resize(n + 1, false);
+    stype[n] = true;
+    for i in (0..n.saturating_sub(1)).rev() {
+        let (x, y) = (s.at(i), s.at(i + 1));
+        stype[i] = x < y || (x == y && stype[i + 1]);
+    }
+}
+
+/// Test if the LMS sub-strings starting at `x` and `y` are identical.
+fn same_lms<S: Symbols + ?Sized>(
+    s: &S,
+    stype: &[bool],
+    x: usize,
+    y: usize,
+) -> bool {
+    let n = s.len();
+    let is_lms = |i: usize| i > 0 && stype[i] && !stype[i - 1];
+    let mut d = 0;
+    loop {
+        if x + d == n || y + d == n {
+            return false;
+        }
+        if s.at(x + d) != s.at(y + d) || stype[x + d] != stype[y + d] {
+            return false;
+        }
+        d += 1;
+        let end_x = is_lms(x + d);
+        let end_y = is_lms(y + d);
+        if end_x || end_y {
+            let cx = Some(x + d).filter(|&i| i < n).map(|i| s.at(i));
+            let cy = Some(y + d).filter(|&i| i < n).map(|i| s.at(i));
+            return end_x && end_y && cx == cy;
+        }
+    }
+}
+
+/// Induce L-type suffixes from left to right, then S-type suffixes from
+/// right to left.
+fn induce<S: Symbols + ?Sized>(
+    s: &S,
+    stype: &[bool],
+    cnt: &[u32],
+    sa: &mut [u32],
+    bkt: &mut Vec<u32>,
+) {
+    bucket_heads(cnt, bkt);
+    for i in 0..sa.len() {
+        let j = sa[i];
+        if j != EMPTY && j > 0 && !stype[j as usize - 1] {
+            let c = s.at(j as usize - 1) as usize;
+            sa[bkt[c] as usize] = j - 1;
+            bkt[c] += 1;
+        }
+    }
+
+    bucket_tails(cnt, bkt);
+    for i in (1..sa.len()).rev() {
+        let j = sa[i];
+        if j != EMPTY && j > 0 && stype[j as usize - 1] {
+            let c = s.at(j as usize - 1) as usize;
+            sa[bkt[c] as usize] = j - 1;
+            bkt[c] -= 1;
+        }
+    }
+}
+
+/// Get the first slot of each bucket, skipping the sentinel slot.
+fn bucket_heads(cnt: &[u32], heads: &mut Vec<u32>) {
+    let mut sum = 1;
+    heads.clear();
+    heads.extend(cnt.iter().map(|&c| {
+        let head = sum;
+        sum += c;
+        head
+    }));
+}
+
+/// Get the last slot of each bucket, skipping the sentinel slot.
+fn bucket_tails(cnt: &[u32], tails: &mut Vec<u32>) {
+    let mut sum = 0;
+    tails.clear();
+    tails.extend(cnt.iter().map(|&c| {
+        sum += c;
+        sum
+    }));
+}
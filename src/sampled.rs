@@ -0,0 +1,310 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::bitvec::RsBitVec;
+use super::saca::saca;
+
+/// Number of rows between the rank checkpoints of the BWT.
+const CHECKPOINT: usize = 256;
+
+/// FM-index keeping the Burrows-Wheeler transform with a sample of the
+/// suffix array, which locates each occurrence by LF steps to the nearest
+/// sampled row.
+///
+/// The sampling has two levels. Every text position that is a multiple of
+/// the base rate is sampled, so locating one occurrence never takes more
+/// than `base_rate - 1` steps. On top of that, the buckets (rows starting
+/// with the same byte) located often sample every `r`-th of their rows,
+/// where `r` is a smaller power of two, to keep the average latency bounded
+/// under skewed workloads.
+///
+/// The bucket rates are tuned online: `locate` counts the rows it reports
+/// from each bucket, and `retune` sets the rates from the counts since.
+/// A bucket gets `2^e` times the base density when its share of the located
+/// rows is at least `2^e` times its share of the rows, so the second level
+/// never takes more than `len / base_rate + 256` samples.
+///
+/// ```rust
+/// use suffix_array::SampledIndex;
+///
+/// let mut index = SampledIndex::new(b"abracadabra", 8);
+/// assert_eq!(index.bucket_rate(b'a'), None);
+/// for _ in 0..10 {
+///     assert_eq!(index.locate(b"abra"), vec![7, 0]);
+/// }
+/// assert!(index.retune());
+/// assert_eq!(index.bucket_rate(b'a'), Some(4));
+/// assert_eq!(index.locate(b"abra"), vec![7, 0]);
+/// ```
+#[derive(Debug)]
+pub struct SampledIndex {
+    len: usize,
+    // the BWT, the sentinel stored as byte 0 at the primary row.
+    bwt: Vec<u8>,
+    primary: usize,
+    // occurrences of each byte before each checkpoint, excluding the primary.
+    occ: Vec<[u32; 256]>,
+    // first row of each bucket, terminated by the total number of rows.
+    lower: Vec<u32>,
+    base_shift: u32,
+    // density exponent of each bucket, zero for the regular samples only.
+    density: Vec<u8>,
+    marks: RsBitVec,
+    samples: Vec<u32>,
+    // rows located from each bucket since the last retune.
+    hits: Vec<AtomicU64>,
+}
+
+impl SampledIndex {
+    /// Construct sampled FM-index for given byte string, where `base_rate`
+    /// must be a power of two.
+    pub fn new(s: &[u8], base_rate: usize) -> Self {
+        let mut sa = vec![0; s.len() + 1];
+        saca(s, &mut sa[..]);
+        Self::from_parts(s, &sa[..], base_rate)
+    }
+
+    /// Construct from byte string and its suffix array.
+    pub(crate) fn from_parts(s: &[u8], sa: &[u32], base_rate: usize) -> Self {
+        assert!(base_rate.is_power_of_two());
+        assert!(base_rate <= 1 << 31);
+
+        let rows = sa.len();
+        let mut bwt = Vec::with_capacity(rows);
+        let mut primary = 0;
+        for (i, &p) in sa.iter().enumerate() {
+            if p == 0 {
+                primary = i;
+                bwt.push(0);
+            } else {
+                bwt.push(s[p as usize - 1]);
+            }
+        }
+
+        let mut occ = Vec::with_capacity(rows / CHECKPOINT + 1);
+        let mut counts = [0u32; 256];
+        for (i, &c) in bwt.iter().enumerate() {
+            if i % CHECKPOINT == 0 {
+                occ.push(counts);
+            }
+            if i != primary {
+                counts[c as usize] += 1;
+            }
+        }
+        if occ.len() == rows / CHECKPOINT {
+            occ.push(counts);
+        }
+
+        let mut lower = vec![0; 257];
+        let mut sum = 1;
+        for c in 0..256 {
+            lower[c] = sum;
+            sum += counts[c];
+        }
+        lower[256] = sum;
+
+        let mut index = SampledIndex {
+            len: s.len(),
+            bwt,
+            primary,
+            occ,
+            lower,
+            base_shift: base_rate.trailing_zeros(),
+            density: vec![0; 256],
+            marks: RsBitVec::default(),
+            samples: Vec::new(),
+            hits: (0..256).map(|_| AtomicU64::new(0)).collect(),
+        };
+        index.resample((0..rows).map(|i| (i, sa[i])));
+        index
+    }
+
+    /// Length of the indexed byte string.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Test if the indexed byte string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The rate of the regular samples, in text positions.
+    pub fn base_rate(&self) -> usize {
+        1 << self.base_shift
+    }
+
+    /// The rate of the extra samples of the bucket starting with `c`, in
+    /// rows, or `None` if the bucket takes the regular samples only.
+    pub fn bucket_rate(&self, c: u8) -> Option<usize> {
+        match self.density[c as usize] {
+            0 => None,
+            e => Some(self.base_rate() >> e),
+        }
+    }
+
+    /// Number of the suffix array samples kept.
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Heap memory taken by the index, in bytes.
+    pub fn heap_size(&self) -> usize {
+        self.bwt.len()
+            + self.occ.len() * std::mem::size_of::<[u32; 256]>()
+            + self.lower.len() * 4
+            + self.density.len()
+            + self.marks.heap_size()
+            + self.samples.len() * 4
+            + self.hits.len() * 8
+    }
+
+    /// Count the occurrences of given pattern (can overlap).
+    pub fn count(&self, pat: &[u8]) -> usize {
+        let (sp, ep) = self.backward_search(pat);
+        ep - sp
+    }
+
+    /// Locate all the occurrences of given pattern (can overlap), in the
+    /// order of the corresponding suffixes, counting them for the tuner.
+    pub fn locate(&self, pat: &[u8]) -> Vec<u32> {
+        let (sp, ep) = self.backward_search(pat);
+        if let Some(&c) = pat.first() {
+            self.hits[c as usize]
+                .fetch_add((ep - sp) as u64, Ordering::Relaxed);
+        }
+        (sp..ep).map(|i| self.locate_row(i)).collect()
+    }
+
+    /// Rows located from the bucket starting with `c` since the last
+    /// retune, halved by each retune.
+    pub fn bucket_hits(&self, c: u8) -> u64 {
+        self.hits[c as usize].load(Ordering::Relaxed)
+    }
+
+    /// Set the bucket rates from the rows located since the last retune,
+    /// rebuilding the samples if any rate changes. Returns whether the
+    /// samples are rebuilt.
+    ///
+    /// The counts are halved afterwards, so that the rates follow the
+    /// recent workload.
+    pub fn retune(&mut self) -> bool {
+        let hits: Vec<u64> = self
+            .hits
+            .iter_mut()
+            .map(|h| std::mem::take(h.get_mut()))
+            .collect();
+        let total: u128 = hits.iter().map(|&h| h as u128).sum();
+        for (h, &n) in self.hits.iter_mut().zip(hits.iter()) {
+            *h.get_mut() = n / 2;
+        }
+
+        let mut density = vec![0; 256];
+        if total > 0 {
+            let rows = self.len as u128;
+            for c in 0..256 {
+                let size = (self.lower[c + 1] - self.lower[c]) as u128;
+                let weighted = hits[c] as u128 * rows;
+                let mut e = 0;
+                while e < self.base_shift && size > 0 {
+                    match (size * total).checked_mul(2 << e) {
+                        Some(need) if weighted >= need => e += 1,
+                        _ => break,
+                    }
+                }
+                density[c] = e as u8;
+            }
+        }
+        if density == self.density {
+            return false;
+        }
+
+        // recover the whole suffix array by walking from the sentinel.
+        self.density = density;
+        let mut sa = Vec::with_capacity(self.bwt.len());
+        let mut row = 0;
+        let mut pos = self.len as u32;
+        loop {
+            sa.push((row, pos));
+            if row == self.primary {
+                break;
+            }
+            row = self.lf(row);
+            pos -= 1;
+        }
+        sa.sort_unstable();
+        self.resample(sa.into_iter());
+        true
+    }
+
+    /// Rebuild the samples from the rows and their suffixes, in row order.
+    fn resample<I>(&mut self, sa: I)
+    where
+        I: Iterator<Item = (usize, u32)>,
+    {
+        let base_mask = (1u32 << self.base_shift) - 1;
+        let mut ones = Vec::new();
+        self.samples.clear();
+        for (i, p) in sa {
+            if i == 0 || p & base_mask == 0 || self.extra_sample(i) {
+                ones.push(i);
+                self.samples.push(p);
+            }
+        }
+        self.marks = RsBitVec::from_ones(self.bwt.len(), ones);
+    }
+
+    /// Test if row `i > 0` is sampled by the rate of its bucket.
+    fn extra_sample(&self, i: usize) -> bool {
+        let c = self.lower.partition_point(|&x| x as usize <= i) - 1;
+        match self.density[c] as u32 {
+            0 => false,
+            e => {
+                let mask = (1 << (self.base_shift - e)) - 1;
+                (i - self.lower[c] as usize) & mask == 0
+            }
+        }
+    }
+
+    /// Find the suffix of row `i` by LF steps to the nearest sampled row.
+    fn locate_row(&self, mut i: usize) -> u32 {
+        let mut steps = 0;
+        while !self.marks.get(i).unwrap_or(false) {
+            i = self.lf(i);
+            steps += 1;
+        }
+        self.samples[self.marks.rank1(i)] + steps
+    }
+
+    /// Find the rows `sp..ep` prefixed by the pattern.
+    fn backward_search(&self, pat: &[u8]) -> (usize, usize) {
+        let mut sp = 0;
+        let mut ep = self.len + 1;
+        for &c in pat.iter().rev() {
+            let lower = self.lower[c as usize] as usize;
+            sp = lower + self.rank(c, sp);
+            ep = lower + self.rank(c, ep);
+            if sp >= ep {
+                return (0, 0);
+            }
+        }
+        (sp, ep)
+    }
+
+    /// Map the row of suffix `p > 0` to the row of suffix `p - 1`.
+    fn lf(&self, i: usize) -> usize {
+        let c = self.bwt[i];
+        self.lower[c as usize] as usize + self.rank(c, i)
+    }
+
+    /// Number of occurrences of `c` in the rows before `i`.
+    fn rank(&self, c: u8, i: usize) -> usize {
+        let k = i / CHECKPOINT;
+        let start = k * CHECKPOINT;
+        let mut rank = self.occ[k][c as usize] as usize;
+        rank += self.bwt[start..i].iter().filter(|&&x| x == c).count();
+        if c == 0 && start <= self.primary && self.primary < i {
+            rank -= 1;
+        }
+        rank
+    }
+}
@@ -0,0 +1,315 @@
+use std::convert::TryFrom;
+#[cfg(feature = "pack")]
+use std::fs::File;
+#[cfg(feature = "pack")]
+use std::io::{BufWriter, ErrorKind, Result};
+#[cfg(feature = "pack")]
+use std::path::Path;
+
+use super::sa::SuffixArray;
+
+/// Suffix arrays over the overlapping shards of a byte string, constructed
+/// in parallel and queried as a whole by the global positions.
+///
+/// Each shard owns a disjoint range of the byte string, and extends
+/// `overlap` bytes past it, so any pattern of at most `overlap + 1` bytes
+/// occurring at an owned position is found in the owning shard only.
+///
+/// The global positions are 64-bit, so the shards could span more than
+/// 4 GiB as a whole while each of them is indexed by 32-bit positions, see
+/// `Shards::from_parts`.
+pub struct Shards<'a> {
+    overlap: usize,
+    shards: Vec<Shard<'a>>,
+}
+
+struct Shard<'a> {
+    start: u64,
+    owned: usize,
+    sa: SuffixArray<&'a [u8]>,
+}
+
+impl<'a> Shards<'a> {
+    /// Split the byte string into `count` shards of nearly equal length,
+    /// each extending `overlap` bytes past its owned range, and construct
+    /// their suffix arrays in parallel.
+    pub fn new(s: &'a [u8], count: usize, overlap: usize) -> Self {
+        let shards = build(s, count, overlap, |_, start, owned, text| Shard {
+            start: start as u64,
+            owned,
+            sa: SuffixArray::new(text),
+        });
+        Shards { overlap, shards }
+    }
+
+    /// Construct the shards like `new`, checkpointing the suffix array of
+    /// each shard into the directory as soon as it is constructed, so that
+    /// an interrupted construction could be resumed by calling it again.
+    ///
+    /// The checkpoints found in the directory are checked against their
+    /// shards before being reused, and reconstructed if inconsistent. They
+    /// are written to temporary files first and then renamed, so that a
+    /// checkpoint is either complete or absent. The directory is left as
+    /// is afterwards.
+    #[cfg(feature = "pack")]
+    pub fn with_checkpoints<P: AsRef<Path>>(
+        s: &'a [u8],
+        count: usize,
+        overlap: usize,
+        dir: P,
+    ) -> Result<Self> {
+        let dir = dir.as_ref();
+        let shards = build(s, count, overlap, |i, start, owned, text| {
+            let path = dir.join(format!("shard-{}-of-{}.sa", i, count));
+            Ok(Shard {
+                start: start as u64,
+                owned,
+                sa: checkpoint(text, &path)?,
+            })
+        });
+        let shards = shards.into_iter().collect::<Result<Vec<_>>>()?;
+        Ok(Shards { overlap, shards })
+    }
+
+    /// Compose the shards from their global starting positions and suffix
+    /// arrays, e.g. those constructed separately for a corpus too large to
+    /// be held at once.
+    ///
+    /// Each shard owns the bytes up to the start of the next shard, and
+    /// must extend exactly `overlap` bytes past them, or up to the end of the
+    /// last shard. Returns `None` if the shards are not laid out like this,
+    /// or the global positions overflow.
+    pub fn from_parts(
+        overlap: usize,
+        parts: Vec<(u64, SuffixArray<&'a [u8]>)>,
+    ) -> Option<Self> {
+        let (last_start, last_sa) = parts.last()?;
+        let end = last_start.checked_add(last_sa.len() as u64)?;
+
+        let mut shards = Vec::with_capacity(parts.len());
+        let mut next_starts: Vec<u64> =
+            parts.iter().skip(1).map(|&(start, _)| start).collect();
+        next_starts.push(end);
+        for ((start, sa), next) in parts.into_iter().zip(next_starts) {
+            let owned = usize::try_from(next.checked_sub(start)?).ok()?;
+            let rest = usize::try_from(end - start).unwrap_or(usize::MAX);
+            let extent = Ord::min(owned.checked_add(overlap)?, rest);
+            if sa.len() != extent {
+                return None;
+            }
+            shards.push(Shard { start, owned, sa });
+        }
+        Some(Shards { overlap, shards })
+    }
+
+    /// Number of the shards.
+    pub fn count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Length of the whole byte string.
+    pub fn len(&self) -> u64 {
+        self.shards.iter().map(|shard| shard.owned as u64).sum()
+    }
+
+    /// Test if the whole byte string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Maximum length of pattern found within the owning shard alone, i.e.
+    /// `overlap + 1`. The longer ones are searched by their leading bytes,
+    /// and then verified across the shards.
+    pub fn max_pattern_len(&self) -> usize {
+        self.overlap.saturating_add(1)
+    }
+
+    /// Convert the position in the given shard to the global position.
+    pub fn to_global(&self, shard: usize, pos: u32) -> Option<u64> {
+        let shard = self.shards.get(shard)?;
+        if pos as usize > shard.sa.len() {
+            return None;
+        }
+        Some(shard.start + pos as u64)
+    }
+
+    /// Convert the global position to the shard owning it and the position
+    /// in that shard.
+    pub fn to_local(&self, pos: u64) -> Option<(usize, u32)> {
+        let i = self.shards.partition_point(|shard| shard.start <= pos);
+        let shard = self.shards.get(i.checked_sub(1)?)?;
+        let local = pos - shard.start;
+        let last = i == self.shards.len();
+        if local < shard.owned as u64 || (last && local == shard.owned as u64) {
+            Some((i - 1, local as u32))
+        } else {
+            None
+        }
+    }
+
+    /// Test if the whole byte string contains the given pattern.
+    pub fn contains(&self, pat: &[u8]) -> bool {
+        if pat.len() > self.max_pattern_len() {
+            return !self.search_all(pat).is_empty();
+        }
+        self.shards.iter().any(|shard| shard.sa.contains(pat))
+    }
+
+    /// Search for all the occurrences of given pattern (can overlap) by
+    /// their global positions in text order, each reported once even if it
+    /// falls in an overlap.
+    pub fn search_all(&self, pat: &[u8]) -> Vec<u64> {
+        let max = self.max_pattern_len();
+        if pat.len() > max {
+            let (head, rest) = pat.split_at(max);
+            let mut result = self.search_all(head);
+            result.retain(|&p| self.matches_at(p + max as u64, rest));
+            return result;
+        }
+
+        let last = self.shards.len() - 1;
+        let mut result = Vec::new();
+        for (i, shard) in self.shards.iter().enumerate() {
+            // the empty suffix at the end is owned by the last shard.
+            let owned = |p: usize| i == last || p < shard.owned;
+            let mut positions: Vec<u64> = shard
+                .sa
+                .search_all(pat)
+                .iter()
+                .map(|&p| p as usize)
+                .filter(|&p| owned(p))
+                .map(|p| shard.start + p as u64)
+                .collect();
+            positions.sort_unstable();
+            result.extend(positions);
+        }
+        result
+    }
+
+    /// Test if the pattern occurs at the global position, reading the owned
+    /// bytes of the shards one after another.
+    fn matches_at(&self, mut pos: u64, mut pat: &[u8]) -> bool {
+        while !pat.is_empty() {
+            let (i, local) = match self.to_local(pos) {
+                Some(found) => found,
+                None => return false,
+            };
+            let shard = &self.shards[i];
+            let text = &shard.sa.text()[local as usize..shard.owned];
+            let k = Ord::min(text.len(), pat.len());
+            if k == 0 || text[..k] != pat[..k] {
+                return false;
+            }
+            pat = &pat[k..];
+            pos += k as u64;
+        }
+        true
+    }
+}
+
+/// Construct the shards by `f`, given the index, the start, the owned
+/// length and the text of each shard, in the order of shards.
+///
+/// The shards are constructed on the rayon thread pool if the `rayon`
+/// feature is enabled, or otherwise on at most as many scoped threads as
+/// the available parallelism, each taking a run of shards.
+fn build<'a, R, F>(s: &'a [u8], count: usize, overlap: usize, f: F) -> Vec<R>
+where
+    R: Send,
+    F: Fn(usize, usize, usize, &'a [u8]) -> R + Sync,
+{
+    let parts: Vec<_> =
+        split(s, count, overlap).into_iter().enumerate().collect();
+
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+
+        parts
+            .into_par_iter()
+            .map(|(i, (start, owned, text))| f(i, start, owned, text))
+            .collect()
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    {
+        let threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        let run = Ord::max(parts.len().div_ceil(threads), 1);
+        let f = &f;
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = parts
+                .chunks(run)
+                .map(|parts| {
+                    scope.spawn(move || {
+                        parts
+                            .iter()
+                            .map(|&(i, (start, owned, text))| {
+                                f(i, start, owned, text)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    }
+}
+
+/// Starting position, owned length and extended text of a shard.
+type ShardRange<'a> = (usize, usize, &'a [u8]);
+
+/// Split the byte string into `count` shards of nearly equal length, each
+/// extending `overlap` bytes past its owned range.
+fn split(s: &[u8], count: usize, overlap: usize) -> Vec<ShardRange<'_>> {
+    assert!(count > 0);
+
+    let base = s.len() / count;
+    let extra = s.len() % count;
+    let mut ranges = Vec::with_capacity(count);
+    let mut start = 0;
+    for i in 0..count {
+        let owned = base + (i < extra) as usize;
+        let end = Ord::min(start + owned + overlap, s.len());
+        ranges.push((start, owned, &s[start..end]));
+        start += owned;
+    }
+    ranges
+}
+
+/// Load the suffix array of a shard from its checkpoint, or construct and
+/// checkpoint it if the checkpoint is absent or inconsistent.
+#[cfg(feature = "pack")]
+fn checkpoint<'a>(
+    text: &'a [u8],
+    path: &Path,
+) -> Result<SuffixArray<&'a [u8]>> {
+    // the other errors are not going away by reconstruction.
+    let stale = |kind| {
+        matches!(
+            kind,
+            ErrorKind::NotFound
+                | ErrorKind::InvalidData
+                | ErrorKind::UnexpectedEof
+        )
+    };
+    match SuffixArray::load_file(text, path) {
+        Ok(sa) => return Ok(sa),
+        Err(e) if !stale(e.kind()) => return Err(e),
+        Err(_) => {}
+    }
+
+    let sa = SuffixArray::new(text);
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    // flush and sync explicitly, since the dropped writer ignores errors.
+    let mut file = BufWriter::new(File::create(&temp)?);
+    sa.dump(&mut file)?;
+    file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    std::fs::rename(&temp, path)?;
+    Ok(sa)
+}
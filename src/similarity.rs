@@ -0,0 +1,29 @@
+use std::collections::HashSet;
+
+use super::lcp::lcp_array;
+
+/// Kinds of similarity scores between the indexed byte string and a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SimilarityKind {
+    /// Length of the longest common sub-string.
+    LcsLen,
+    /// Sum of the matching statistics, i.e. the lengths of the longest
+    /// prefixes of each suffix of query that occur in the byte string.
+    MatchingStatisticsSum,
+    /// Jaccard index of the sets of distinct q-grams, defined as 0 if both
+    /// sets are empty, or q is 0.
+    Jaccard(usize),
+}
+
+/// Count the distinct q-grams of byte string using its LCP array.
+pub fn distinct_qgrams(s: &[u8], sa: &[u32], q: usize) -> usize {
+    let lcp = lcp_array(s, sa);
+    (1..sa.len())
+        .filter(|&i| s.len() - sa[i] as usize >= q && (lcp[i] as usize) < q)
+        .count()
+}
+
+/// Collect the distinct q-grams of query.
+pub fn query_qgrams(query: &[u8], q: usize) -> HashSet<&[u8]> {
+    query.windows(q).collect()
+}
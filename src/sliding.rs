@@ -0,0 +1,143 @@
+use std::io::Result;
+
+use super::error::Error;
+use super::saca::{saca, MAX_LENGTH};
+use super::utils::{scan, trunc};
+
+/// Suffix array over the last bytes of a stream.
+///
+/// The buffered bytes are re-indexed each time half a window of new bytes
+/// has arrived, so the construction cost is amortized to O(1) per byte.
+/// The bytes arrived since the last re-indexing are scanned naively when
+/// searching.
+#[derive(Debug, Clone)]
+pub struct SlidingWindowIndex {
+    window: usize,
+    buf: Vec<u8>,
+    sa: Vec<u32>,
+    indexed: usize,
+    offset: u64,
+}
+
+impl SlidingWindowIndex {
+    /// Create an empty index over the last `window` bytes of a stream.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if the window together with the
+    /// bytes buffered before re-indexing may be longer than `MAX_LENGTH`.
+    pub fn new(window: usize) -> Result<Self> {
+        let len = window.saturating_add(half(window));
+        if len > MAX_LENGTH {
+            return Err(Error::TooLong(len).into());
+        }
+
+        Ok(SlidingWindowIndex {
+            window,
+            buf: Vec::with_capacity(len),
+            sa: vec![0],
+            indexed: 0,
+            offset: 0,
+        })
+    }
+
+    /// Append bytes to the stream.
+    pub fn push(&mut self, mut data: &[u8]) {
+        if data.len() >= self.window {
+            // the buffered bytes all slide out of the window.
+            let skip = data.len() - self.window;
+            self.offset += (self.buf.len() + skip) as u64;
+            self.buf.clear();
+            self.buf.extend_from_slice(&data[skip..]);
+            self.reindex();
+            return;
+        }
+
+        while !data.is_empty() {
+            let pending = self.buf.len() - self.indexed;
+            let n = Ord::min(data.len(), half(self.window) - pending);
+            self.buf.extend_from_slice(&data[..n]);
+            data = &data[n..];
+            if pending + n == half(self.window) {
+                self.reindex();
+            }
+        }
+    }
+
+    /// Size of the window.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Bytes in the current window.
+    pub fn window_bytes(&self) -> &[u8] {
+        &self.buf[self.window_start()..]
+    }
+
+    /// Stream offset of the first byte in the current window.
+    pub fn window_offset(&self) -> u64 {
+        self.offset + self.window_start() as u64
+    }
+
+    /// Test if the current window contains the given pattern.
+    pub fn contains(&self, pat: &[u8]) -> bool {
+        let start = self.window_start();
+        let (i, j) = self.indexed_range(pat);
+        self.sa[i..j].iter().any(|&p| p as usize >= start)
+            || self.pending_positions(pat).next().is_some()
+    }
+
+    /// Search for the stream offsets of all the unsorted occurrences of given
+    /// pattern within the current window (can overlap).
+    pub fn search_all(&self, pat: &[u8]) -> Vec<u64> {
+        let start = self.window_start();
+        let (i, j) = self.indexed_range(pat);
+        let indexed = self.sa[i..j]
+            .iter()
+            .map(|&p| p as usize)
+            .filter(|&p| p >= start);
+        indexed
+            .chain(self.pending_positions(pat))
+            .map(|p| self.offset + p as u64)
+            .collect()
+    }
+
+    fn window_start(&self) -> usize {
+        self.buf.len().saturating_sub(self.window)
+    }
+
+    /// Drop the bytes out of window and rebuild the suffix array.
+    fn reindex(&mut self) {
+        let drop = self.window_start();
+        self.buf.drain(..drop);
+        self.offset += drop as u64;
+        self.sa.resize(self.buf.len() + 1, 0);
+        saca(&self.buf[..], &mut self.sa[..]);
+        self.indexed = self.buf.len();
+    }
+
+    /// Get the suffix array range of occurrences within the indexed bytes.
+    fn indexed_range(&self, pat: &[u8]) -> (usize, usize) {
+        let s = &self.buf[..self.indexed];
+        let key = |&p: &u32| trunc(&s[p as usize..], pat.len());
+        let i = self.sa.partition_point(|p| key(p) < pat);
+        let j = i + self.sa[i..].partition_point(|p| key(p) == pat);
+        (i, j)
+    }
+
+    /// Scan the occurrences overlapping the bytes not yet indexed.
+    fn pending_positions<'s>(
+        &'s self,
+        pat: &'s [u8],
+    ) -> impl Iterator<Item = usize> + 's {
+        let first = (self.indexed + 1).saturating_sub(pat.len());
+        let first = Ord::max(first, self.window_start());
+        self.buf
+            .get(first..)
+            .into_iter()
+            .flat_map(move |pending| scan(pending, pat))
+            .map(move |p| first + p as usize)
+    }
+}
+
+fn half(window: usize) -> usize {
+    window - window / 2
+}
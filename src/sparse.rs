@@ -0,0 +1,75 @@
+use super::saca::MAX_LENGTH;
+use super::storage::TextStorage;
+
+/// Suffix array over the suffixes starting at the positions of interest
+/// only, e.g. the word starts or the record starts.
+///
+/// Only the occurrences starting at these positions could be found, in
+/// exchange for memory and construction time proportional to the number of
+/// positions instead of the length of byte string.
+#[derive(Debug, Clone)]
+pub struct PositionFilteredSuffixArray<T> {
+    s: T,
+    sa: Vec<u32>,
+}
+
+impl<T: TextStorage> PositionFilteredSuffixArray<T> {
+    /// Construct new suffix array over the suffixes of given byte string
+    /// starting at given positions, ignoring the duplicated ones.
+    pub fn new(s: T, positions: &[u32]) -> Self {
+        let text = s.as_ref();
+        assert!(text.len() <= MAX_LENGTH);
+        assert!(
+            positions.iter().all(|&i| i as usize <= text.len()),
+            "position out of range"
+        );
+
+        let mut sa = Vec::from(positions);
+        sa.sort_unstable();
+        sa.dedup();
+        sa.sort_unstable_by(|&i, &j| {
+            text[i as usize..].cmp(&text[j as usize..])
+        });
+        PositionFilteredSuffixArray { s, sa }
+    }
+
+    /// Number of the positions of interest.
+    pub fn len(&self) -> usize {
+        self.sa.len()
+    }
+
+    /// Test if there is no position of interest.
+    pub fn is_empty(&self) -> bool {
+        self.sa.is_empty()
+    }
+
+    /// Get the underlying byte string.
+    pub fn text(&self) -> &[u8] {
+        self.s.as_ref()
+    }
+
+    /// Get the positions of interest, ordered by the suffixes they start.
+    pub fn positions(&self) -> &[u32] {
+        &self.sa[..]
+    }
+
+    /// Take out the byte string and the ordered positions.
+    pub fn into_parts(self) -> (T, Vec<u32>) {
+        (self.s, self.sa)
+    }
+
+    /// Test if given pattern occurs at any of the positions of interest.
+    pub fn contains(&self, pat: &[u8]) -> bool {
+        !self.search_all(pat).is_empty()
+    }
+
+    /// Search for all the unsorted occurrences of given pattern starting at
+    /// the positions of interest.
+    pub fn search_all(&self, pat: &[u8]) -> &[u32] {
+        let s = self.text();
+        let i = self.sa.partition_point(|&i| &s[i as usize..] < pat);
+        let j = i + self.sa[i..]
+            .partition_point(|&i| s[i as usize..].starts_with(pat));
+        &self.sa[i..j]
+    }
+}
@@ -0,0 +1,29 @@
+use std::ops::Range;
+
+/// A planned replacement of `range` in the underlying byte string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Splice<'r> {
+    /// The replaced range of the underlying byte string.
+    pub range: Range<usize>,
+    /// The bytes to be filled in.
+    pub replacement: &'r [u8],
+}
+
+/// Apply position-sorted and non-overlapping splices to a byte string.
+pub fn apply_splices(s: &[u8], splices: &[Splice]) -> Vec<u8> {
+    let delta: isize = splices
+        .iter()
+        .map(|sp| sp.replacement.len() as isize - sp.range.len() as isize)
+        .sum();
+    let mut result = Vec::with_capacity((s.len() as isize + delta) as usize);
+
+    let mut last = 0;
+    for sp in splices.iter() {
+        assert!(last <= sp.range.start, "splices overlap or are unsorted");
+        result.extend_from_slice(&s[last..sp.range.start]);
+        result.extend_from_slice(sp.replacement);
+        last = sp.range.end;
+    }
+    result.extend_from_slice(&s[last..]);
+    result
+}
@@ -0,0 +1,253 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+
+use super::sa::SuffixArray;
+use super::storage::TextStorage;
+
+/// Inputs no longer than this are sorted by naive suffix comparison.
+const TINY_LENGTH: usize = 32;
+
+/// Maximum number of windows sampled to estimate the repetitiveness.
+const SAMPLE_WINDOWS: usize = 4096;
+
+/// Length of each sampled window.
+const SAMPLE_WINDOW_LENGTH: usize = 8;
+
+/// Suffix array construction algorithms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    /// Sort the suffixes by direct comparison, for tiny inputs only.
+    Naive,
+    /// Yuta Mori's divsufsort, the fastest on typical inputs.
+    Divsufsort,
+    /// The SA-IS algorithm, linear time regardless of the input.
+    ///
+    /// It stands in for SACA-K: both sort by induced sorting, but SA-IS
+    /// keeps the suffix types in a separate workspace of one byte per input
+    /// byte instead of reusing the suffix array.
+    Sais,
+    /// The Larsson-Sadakane prefix doubling algorithm, O(n log n) time
+    /// regardless of the input. It is never selected by sampling.
+    PrefixDoubling,
+}
+
+/// Statistics sampled from the input, and the construction algorithm they
+/// lead to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexStats {
+    /// Length of the input.
+    pub length: usize,
+    /// Number of distinct bytes.
+    pub alphabet_size: usize,
+    /// Number of maximal runs of identical bytes.
+    pub runs: usize,
+    /// Fraction of the sampled windows that have been seen before, ranges
+    /// from 0 (no repeats) to 1 (highly repetitive).
+    pub repetitiveness: f64,
+    /// The selected construction algorithm.
+    pub algorithm: Algorithm,
+}
+
+impl IndexStats {
+    /// Sample the input and select the construction algorithm.
+    ///
+    /// The sampling is deterministic, so the decision can be reproduced
+    /// for the same input.
+    pub fn sample(s: &[u8]) -> Self {
+        let length = s.len();
+
+        let mut seen = [false; 256];
+        let mut runs = 0;
+        for (i, &c) in s.iter().enumerate() {
+            seen[c as usize] = true;
+            if i == 0 || s[i - 1] != c {
+                runs += 1;
+            }
+        }
+        let alphabet_size = seen.iter().filter(|&&x| x).count();
+
+        let mut repetitiveness = 0.0;
+        if length >= SAMPLE_WINDOW_LENGTH {
+            let windows = length - SAMPLE_WINDOW_LENGTH + 1;
+            let step = Ord::max(1, windows / SAMPLE_WINDOWS);
+            let mut set = HashSet::new();
+            let mut total = 0;
+            let mut repeats = 0;
+            for i in (0..windows).step_by(step).take(SAMPLE_WINDOWS) {
+                total += 1;
+                if !set.insert(&s[i..i + SAMPLE_WINDOW_LENGTH]) {
+                    repeats += 1;
+                }
+            }
+            repetitiveness = repeats as f64 / total as f64;
+        }
+
+        let algorithm = if length <= TINY_LENGTH {
+            Algorithm::Naive
+        } else if runs * 16 < length || repetitiveness > 0.9 {
+            Algorithm::Sais
+        } else {
+            Algorithm::Divsufsort
+        };
+
+        IndexStats {
+            length,
+            alphabet_size,
+            runs,
+            repetitiveness,
+            algorithm,
+        }
+    }
+}
+
+/// Heap memory taken by a suffix array and its auxiliary structures, in
+/// bytes, excluding the underlying byte string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct MemoryBreakdown {
+    /// The suffix array itself.
+    pub sa: usize,
+    /// The bucket pointers, either eager or lazy.
+    pub buckets: usize,
+    /// The kept LCP array, either plain or compressed.
+    pub lcp: usize,
+    /// The fast search samples, the prefix filter, the inverse suffix array
+    /// and the reversed index, once computed.
+    pub extra: usize,
+}
+
+impl MemoryBreakdown {
+    /// Total heap memory, in bytes.
+    pub fn total(&self) -> usize {
+        self.sa + self.buckets + self.lcp + self.extra
+    }
+}
+
+/// Number of suffixes starting with each byte and each pair of bytes, i.e.
+/// the sizes of the buckets, see `SuffixArray::bucket_stats`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BucketStats {
+    /// Number of suffixes starting with each byte.
+    pub first: [u64; 256],
+    /// Number of suffixes starting with each pair of bytes, indexed by
+    /// `c0 * 256 + c1`.
+    pub pairs: Vec<u64>,
+}
+
+impl BucketStats {
+    /// Collect the sizes from the boundaries of the sub-buckets `(c0, $),
+    /// (c0, 0), ..., (c0, 255)` followed by the end, for each first byte.
+    pub(crate) fn from_bounds<F, B>(mut bounds_of: F) -> Self
+    where
+        F: FnMut(u8) -> B,
+        B: AsRef<[u32]>,
+    {
+        let mut first = [0; 256];
+        let mut pairs = vec![0; 1 << 16];
+        for c0 in 0..=255 {
+            let bounds = bounds_of(c0);
+            let bounds = bounds.as_ref();
+            first[c0 as usize] = (bounds[257] - bounds[0]) as u64;
+            for (c1, w) in bounds[1..].windows(2).enumerate() {
+                pairs[(c0 as usize) << 8 | c1] = (w[1] - w[0]) as u64;
+            }
+        }
+        BucketStats { first, pairs }
+    }
+
+    /// Number of suffixes starting with the pair of bytes.
+    pub fn pair(&self, c0: u8, c1: u8) -> u64 {
+        self.pairs[(c0 as usize) << 8 | c1 as usize]
+    }
+
+    /// The present first bytes with their numbers of suffixes, the most
+    /// frequent first, and the smaller byte first on ties.
+    pub fn by_frequency(&self) -> Vec<(u8, u64)> {
+        let mut sorted: Vec<_> = (0..=255)
+            .map(|c| (c, self.first[c as usize]))
+            .filter(|&(_, k)| k > 0)
+            .collect();
+        sorted.sort_by_key(|&(c, k)| (Reverse(k), c));
+        sorted
+    }
+}
+
+/// Statistics of the underlying byte string of a suffix array, predicting
+/// its compressibility and index size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStats {
+    /// Length of the byte string.
+    pub length: usize,
+    /// Number of occurrences of each byte.
+    pub histogram: [u64; 256],
+    /// Number of maximal runs of identical symbols in the BWT, with the
+    /// sentinel counted as a distinct symbol.
+    pub bwt_runs: usize,
+    /// Empirical order-0 entropy, in bits per byte.
+    pub entropy0: f64,
+    /// Empirical order-2 entropy, i.e. the entropy of a byte given the two
+    /// bytes before it, in bits per byte.
+    pub entropy2: f64,
+}
+
+impl TextStats {
+    /// Compute the statistics of the underlying byte string.
+    pub fn compute<T: TextStorage>(sa: &SuffixArray<T>) -> Self {
+        let s = sa.text();
+        let length = s.len();
+
+        let mut histogram = [0; 256];
+        for &c in s.iter() {
+            histogram[c as usize] += 1;
+        }
+
+        let mut bwt_runs = 0;
+        let mut last = None;
+        for (_, p, _) in sa.iter_suffixes() {
+            let c = (p as usize).checked_sub(1).map(|i| s[i]);
+            if bwt_runs == 0 || c != last {
+                bwt_runs += 1;
+            }
+            last = c;
+        }
+
+        let entropy0 = if length > 0 {
+            let total = length as f64;
+            histogram
+                .iter()
+                .filter(|&&k| k > 0)
+                .map(|&k| -(k as f64) * (k as f64 / total).log2())
+                .sum::<f64>()
+                / total
+        } else {
+            0.0
+        };
+
+        let mut contexts = vec![0u64; 1 << 16];
+        // summed up in order, so the entropy is reproducible to the bit.
+        let mut triples = BTreeMap::new();
+        for w in s.windows(3) {
+            let ctx = (w[0] as usize) << 8 | w[1] as usize;
+            contexts[ctx] += 1;
+            *triples.entry((ctx, w[2])).or_insert(0u64) += 1;
+        }
+        let entropy2 = if length > 0 {
+            triples
+                .iter()
+                .map(|(&(ctx, _), &k)| {
+                    -(k as f64) * (k as f64 / contexts[ctx] as f64).log2()
+                })
+                .sum::<f64>()
+                / length as f64
+        } else {
+            0.0
+        };
+
+        TextStats {
+            length,
+            histogram,
+            bwt_runs,
+            entropy0,
+            entropy2,
+        }
+    }
+}
@@ -0,0 +1,10 @@
+/// Storage of the byte string to be indexed, which dereferences to `[u8]`.
+///
+/// It is implemented for anything viewed as a byte slice, including `&[u8]`,
+/// `Vec<u8>`, `Box<[u8]>`, `Arc<[u8]>`, and the memory maps of common crates.
+///
+/// The contents must not change while being indexed, otherwise the searching
+/// results are meaningless.
+pub trait TextStorage: AsRef<[u8]> {}
+
+impl<T: AsRef<[u8]> + ?Sized> TextStorage for T {}
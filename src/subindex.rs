@@ -0,0 +1,96 @@
+use std::ops::Range;
+
+use super::sa::SuffixArray;
+use super::storage::TextStorage;
+
+/// View of a suffix array restricted to a range of the byte string, see
+/// `SuffixArray::subindex`.
+///
+/// The queries are answered by the whole suffix array, keeping only the
+/// occurrences lying entirely in the range, so they take time proportional
+/// to the occurrences in the whole byte string. The positions are relative
+/// to the start of range, as if the range is indexed on its own.
+pub struct SubIndexView<'a, T> {
+    sa: &'a SuffixArray<T>,
+    start: usize,
+    end: usize,
+}
+
+// derived ones would require `T: Copy`.
+impl<T> Clone for SubIndexView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for SubIndexView<'_, T> {}
+
+impl<'a, T: TextStorage> SubIndexView<'a, T> {
+    pub(crate) fn new(sa: &'a SuffixArray<T>, range: Range<usize>) -> Self {
+        assert!(
+            range.start <= range.end && range.end <= sa.len(),
+            "range out of bounds"
+        );
+        SubIndexView {
+            sa,
+            start: range.start,
+            end: range.end,
+        }
+    }
+
+    /// Range of the byte string in view.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Length of the range.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Test if the range is empty.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Get the bytes in view.
+    pub fn text(&self) -> &'a [u8] {
+        &self.sa.text()[self.start..self.end]
+    }
+
+    /// Test if the range contains the given pattern.
+    pub fn contains(&self, pat: &[u8]) -> bool {
+        self.occurrences(pat).next().is_some()
+    }
+
+    /// Count the occurrences of given pattern in the range.
+    pub fn count(&self, pat: &[u8]) -> usize {
+        self.occurrences(pat).count()
+    }
+
+    /// Search for all the occurrences of given pattern in the range, in
+    /// text order.
+    pub fn search_all(&self, pat: &[u8]) -> Vec<u32> {
+        let mut result: Vec<u32> = self.occurrences(pat).collect();
+        result.sort_unstable();
+        result
+    }
+
+    /// Construct a standalone suffix array of the bytes in view, which is
+    /// smaller and faster to query if the range is searched repeatedly.
+    pub fn to_suffix_array(&self) -> SuffixArray<&'a [u8]> {
+        SuffixArray::new(self.text())
+    }
+
+    /// Iterate over the unsorted occurrences relative to the start.
+    fn occurrences<'b>(&self, pat: &'b [u8]) -> impl Iterator<Item = u32> + 'b
+    where
+        'a: 'b,
+    {
+        let (start, end, m) = (self.start, self.end, pat.len());
+        let found = self.sa.search_all(pat).iter().map(|&p| p as usize);
+        found
+            .filter(move |&p| p >= start && p + m <= end)
+            .map(move |p| (p - start) as u32)
+    }
+}
@@ -1,7 +1,20 @@
+use super::bitvec::RsBitVec;
+use super::lcp::lcp_array;
+use super::saca::{max_length, MAX_LENGTH};
 use super::utils::lcp;
-use super::SuffixArray;
+use super::{
+    dna_symbol, index_file, sais_ints_mut, Algorithm, CollatedSuffixArray,
+    CorpusSet, Encoded2Bit, Encoded2BitSuffixArray, Error, IndexCell,
+    IndexStats, LcpKind, Normalization, NormalizedText, OwnedSuffixArray,
+    Phase, PositionFilteredSuffixArray, RawWidth, RlfmIndex, SampledIndex,
+    Shards, SimilarityKind, SlidingWindowIndex, SuffixArray, TextStats,
+    Transform, WarmLevel, MAX_BUCKET_DEPTH,
+};
 
 use proptest::prelude::*;
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::sync::Arc;
 
 macro_rules! bytes {
     ($range:expr) => {
@@ -13,49 +26,2123 @@ proptest! {
     #[test]
     fn conversion_correctness(s in bytes!(0..4096_usize)) {
         let (_, sa_vec) = SuffixArray::new(&s[..]).into_parts();
-        prop_assert!(SuffixArray::from_parts(&s[..], sa_vec).is_some());
+        prop_assert!(SuffixArray::from_parts(&s[..], sa_vec.clone()).is_some());
+
+        let mut bad = sa_vec;
+        bad[s.len() / 2] = s.len() as u32 + 1;
+        prop_assert!(SuffixArray::from_parts(&s[..], bad).is_none());
+    }
+
+    #[test]
+    fn sais_ints_correctness(
+        s in bytes!(0..4096_usize),
+        k in 1..300_usize,
+    ) {
+        let mut ints: Vec<u32> =
+            s.iter().map(|&c| (c as usize % k) as u32).collect();
+        let bytes: Vec<u8> = ints.iter().map(|&c| c as u8).collect();
+        let expected = if k <= 256 {
+            Some(SuffixArray::new(&bytes[..]).into_parts().1)
+        } else {
+            None
+        };
+
+        let mut sa = vec![0; ints.len() + 1];
+        let copy = ints.clone();
+        sais_ints_mut(&mut ints[..], k, &mut sa[..]);
+        prop_assert_eq!(&ints, &copy);
+        if let Some(expected) = expected {
+            prop_assert_eq!(sa, expected);
+        } else {
+            for i in 1..sa.len() {
+                let x = &ints[sa[i - 1] as usize..];
+                let y = &ints[sa[i] as usize..];
+                prop_assert!(x < y);
+            }
+        }
+    }
+
+    #[test]
+    fn algorithms_agree(s in prop::collection::vec(0..3_u8, 0..2048_usize)) {
+        let stats = IndexStats::sample(&s[..]);
+        prop_assert!(stats.alphabet_size <= 3);
+        prop_assert!(stats.runs <= s.len());
+
+        let (_, expected) =
+            SuffixArray::with_algorithm(&s[..], Algorithm::Divsufsort)
+                .into_parts();
+        let algorithms =
+            [Algorithm::Naive, Algorithm::Sais, Algorithm::PrefixDoubling];
+        for &algorithm in algorithms.iter() {
+            let (_, sa) =
+                SuffixArray::with_algorithm(&s[..], algorithm).into_parts();
+            prop_assert_eq!(&sa, &expected);
+        }
+        let (_, sa) = SuffixArray::new(&s[..]).into_parts();
+        prop_assert_eq!(&sa, &expected);
+    }
+
+    #[test]
+    fn progress_correctness(
+        runs in prop::collection::vec((0..4_u8, 1..64_usize), 0..128_usize),
+    ) {
+        let s: Vec<u8> = runs
+            .iter()
+            .flat_map(|&(c, n)| vec![c; n])
+            .collect();
+        let mut phases = Vec::new();
+        let (_, sa) = SuffixArray::with_progress(&s[..], |phase| {
+            phases.push(phase)
+        })
+        .into_parts();
+        prop_assert_eq!(sa, SuffixArray::new(&s[..]).into_parts().1);
+
+        let algorithm = IndexStats::sample(&s[..]).algorithm;
+        prop_assert_eq!(phases.first(), Some(&Phase::Selected(algorithm)));
+        prop_assert_eq!(phases.last(), Some(&Phase::Done));
+        if algorithm == Algorithm::Sais && !s.is_empty() {
+            let first = Phase::BucketCounting { depth: 0, length: s.len() };
+            prop_assert_eq!(phases[1], first);
+            let last = Phase::Inducing { depth: 0 };
+            prop_assert_eq!(phases[phases.len() - 2], last);
+            for w in phases.windows(2) {
+                if let Phase::Recursion { depth, length } = w[0] {
+                    let next = Phase::BucketCounting { depth, length };
+                    prop_assert_eq!(w[1], next);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn collation_correctness(
+        (s, pat) in bytes_with_pat(0..1024_usize),
+        order in Just((0..=255_u8).collect::<Vec<_>>()).prop_shuffle(),
+    ) {
+        let mut table = [0; 256];
+        table.copy_from_slice(&order[..]);
+        let sa = CollatedSuffixArray::new(&s[..], table);
+        prop_assert_eq!(sa.text(), s.clone());
+
+        let key = |i: u32| -> Vec<u8> {
+            s[i as usize..].iter().map(|&c| table[c as usize]).collect()
+        };
+        let raw = sa.suffix_array().raw_sa();
+        for w in raw.windows(2) {
+            prop_assert!(key(w[0]) < key(w[1]));
+        }
+
+        let mut result = Vec::from(sa.search_all(&pat[..]));
+        result.sort_unstable();
+        prop_assert_eq!(result, naive_search_all(&s[..], &pat[..]));
+        let expected = naive_contains(&s[..], &pat[..]);
+        prop_assert_eq!(sa.contains(&pat[..]), expected);
+    }
+
+    #[test]
+    fn text_stats_correctness(
+        s in prop::collection::vec(0..4_u8, 0..1024_usize),
+    ) {
+        let sa = SuffixArray::new(&s[..]);
+        let stats = TextStats::compute(&sa);
+        prop_assert_eq!(stats.length, s.len());
+        prop_assert_eq!(stats.histogram.iter().sum::<u64>(), s.len() as u64);
+
+        let mut bwt: Vec<Option<u8>> = sa
+            .iter_suffixes()
+            .map(|(_, p, _)| (p as usize).checked_sub(1).map(|i| s[i]))
+            .collect();
+        bwt.dedup();
+        prop_assert_eq!(stats.bwt_runs, bwt.len());
+
+        prop_assert!(stats.entropy0 >= 0.0 && stats.entropy0 <= 2.0 + 1e-9);
+        prop_assert!(stats.entropy2 >= 0.0);
+        prop_assert!(stats.entropy2 <= stats.entropy0 + 1e-9);
+    }
+
+    #[test]
+    fn bucket_stats_correctness(
+        s in prop::collection::vec(0..4_u8, 0..1024_usize),
+        lazy in any::<bool>(),
+    ) {
+        let mut sa = SuffixArray::new(&s[..]);
+        if lazy {
+            sa.enable_lazy_buckets();
+        }
+        let stats = sa.bucket_stats();
+        let text = TextStats::compute(&sa);
+        prop_assert_eq!(&stats.first[..], &text.histogram[..]);
+        for c0 in 0..4 {
+            for c1 in 0..4 {
+                let count = s.windows(2).filter(|w| w == &[c0, c1]).count();
+                prop_assert_eq!(stats.pair(c0, c1), count as u64);
+            }
+        }
+        let total = stats.pairs.iter().sum::<u64>() as usize;
+        prop_assert_eq!(total, s.len().saturating_sub(1));
+
+        let sorted = stats.by_frequency();
+        prop_assert!(sorted.windows(2).all(|w| w[0].1 >= w[1].1));
+        prop_assert!(sorted.iter().all(|&(c, k)| stats.first[c as usize] == k));
+    }
+
+    #[test]
+    fn reproducible_correctness(
+        s in prop::collection::vec(0..4_u8, 0..2048_usize),
+    ) {
+        let dump = |sa: &SuffixArray<&[u8]>| {
+            let mut bytes = Vec::new();
+            sa.dump_raw(&mut bytes, RawWidth::U32).unwrap();
+            #[cfg(feature = "pack")]
+            sa.dump(&mut bytes).unwrap();
+            bytes
+        };
+        let expected = dump(&SuffixArray::new(&s[..]));
+        let algorithms = [
+            Algorithm::Naive,
+            Algorithm::Divsufsort,
+            Algorithm::Sais,
+            Algorithm::PrefixDoubling,
+        ];
+        for &algorithm in algorithms.iter() {
+            let sa = SuffixArray::with_algorithm(&s[..], algorithm);
+            prop_assert_eq!(dump(&sa), expected.clone());
+        }
+
+        let sa = SuffixArray::new(&s[..]);
+        let x = TextStats::compute(&sa);
+        let y = TextStats::compute(&sa);
+        prop_assert_eq!(x.entropy2.to_bits(), y.entropy2.to_bits());
+    }
+
+    #[test]
+    fn set_correctness(
+        s in prop::collection::vec(0..4_u8, 0..512_usize),
+        t in prop::collection::vec(0..4_u8, 0..512_usize),
+        pat in prop::collection::vec(0..4_u8, 0..4_usize),
+        depth in 1..=2_usize,
+        compressed in any::<bool>(),
+    ) {
+        let kind = if compressed {
+            LcpKind::Compressed
+        } else {
+            LcpKind::Plain
+        };
+        let mut sa = SuffixArray::new(&s[..]);
+        sa.enable_buckets_with_depth(depth);
+        sa.enable_fast_search();
+        sa.enable_lcp_with(kind);
+        sa.set_naive_search_length(0);
+        sa.set(&t[..]);
+
+        let expected = SuffixArray::new(&t[..]);
+        prop_assert!(sa == expected);
+        prop_assert_eq!(sa.bucket_depth(), depth);
+        prop_assert_eq!(sa.lcp_kind(), Some(kind));
+        prop_assert_eq!(sa.search_all(&pat[..]), expected.search_all(&pat[..]));
+        for i in 0..=t.len() {
+            prop_assert_eq!(sa.lcp_at(i), expected.lcp_at(i));
+        }
+    }
+
+    #[test]
+    fn map_text_correctness(
+        s in prop::collection::vec(0..4_u8, 0..512_usize),
+        t in prop::collection::vec(0..4_u8, 0..512_usize),
+    ) {
+        let mut sa = SuffixArray::new(&s[..]);
+        sa.enable_buckets();
+        let owned = sa.map_text(|s| s.to_vec());
+        prop_assert!(owned == SuffixArray::new(&s[..]));
+        prop_assert_eq!(owned.bucket_depth(), 2);
+
+        let replaced = owned.map_text(|_| Arc::<[u8]>::from(&t[..]));
+        prop_assert!(replaced == SuffixArray::new(&t[..]));
+        prop_assert_eq!(replaced.bucket_depth(), 2);
+
+        let reindexed = replaced.reindex(&s[..]);
+        prop_assert!(reindexed == SuffixArray::new(&s[..]));
+    }
+
+    #[test]
+    fn search_all_partial_correctness(
+        s in prop::collection::vec(0..2_u8, 0..256_usize),
+        pat in prop::collection::vec(0..2_u8, 0..8_usize),
+    ) {
+        let sa = SuffixArray::new(&s[..]);
+        let mut expected: Vec<(u32, usize)> = sa
+            .search_all(&pat[..])
+            .iter()
+            .map(|&p| (p, pat.len()))
+            .collect();
+        for p in 0..s.len() {
+            if s.len() - p < pat.len() && pat.starts_with(&s[p..]) {
+                expected.push((p as u32, s.len() - p));
+            }
+        }
+        let found: Vec<_> = sa.search_all_partial(&pat[..]).collect();
+        prop_assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn search_all_docs_correctness(
+        docs in prop::collection::vec(
+            prop::collection::vec(0..3_u8, 0..32_usize),
+            0..16_usize,
+        ),
+        pat in prop::collection::vec(0..3_u8, 1..4_usize),
+    ) {
+        use super::{Documents, Position};
+
+        let (text, bounds) = Documents::concat(&docs[..]).unwrap();
+        prop_assert_eq!(bounds.len(), docs.len());
+        prop_assert_eq!(bounds.total_len(), text.len());
+        let mut expected = Vec::new();
+        for (i, doc) in docs.iter().enumerate() {
+            for p in naive_search_all(&doc[..], &pat[..]) {
+                expected.push(Position { doc: i as u32, offset: p });
+            }
+        }
+
+        let sa = SuffixArray::new(&text[..]);
+        let mut found: Vec<_> = sa.search_all_docs(&pat[..], &bounds).collect();
+        found.sort();
+        prop_assert_eq!(found, expected);
+        prop_assert_eq!(bounds.locate(text.len() as u32), None);
+    }
+
+    #[test]
+    fn explain_correctness(
+        s in prop::collection::vec(0..4_u8, 0..2048_usize),
+        pat in prop::collection::vec(0..5_u8, 0..12_usize),
+        depth in 0..=2_usize,
+        fast in any::<bool>(),
+    ) {
+        use super::Strategy;
+
+        let mut sa = SuffixArray::new(&s[..]);
+        let count = naive_search_all(&s[..], &pat[..]).len();
+        if s.len() <= 1024 {
+            prop_assert_eq!(sa.explain(&pat[..]).strategy, Strategy::Scan);
+        }
+
+        sa.set_naive_search_length(0);
+        if depth > 0 {
+            sa.enable_buckets_with_depth(depth);
+        }
+        if fast {
+            sa.enable_fast_search();
+        }
+        let plan = sa.explain(&pat[..]);
+        prop_assert!(plan.candidates >= count);
+        if s.is_empty() {
+            // never longer than the naive search length.
+            prop_assert_eq!(plan.strategy, Strategy::Scan);
+            return Ok(());
+        }
+        prop_assert_eq!(plan.fast_search, fast && !pat.is_empty());
+        prop_assert_eq!(plan.bucket_bytes, Ord::min(depth, pat.len()));
+        let expected = match pat.len() {
+            _ if plan.candidates == 0 => Strategy::Empty,
+            1..=8 => Strategy::PackedBinarySearch,
+            _ => Strategy::BinarySearch,
+        };
+        prop_assert_eq!(plan.strategy, expected);
+        if depth >= pat.len() && !fast {
+            prop_assert_eq!(plan.candidates, count);
+        }
+    }
+
+    #[test]
+    fn search_all_with_correctness(
+        s in prop::collection::vec(0..3_u8, 0..512_usize),
+        pat in prop::collection::vec(0..3_u8, 0..4_usize),
+        base in 0..512_usize,
+    ) {
+        use super::{Occurrences, SearchOptions};
+
+        let sa = SuffixArray::new(&s[..]);
+        let mut expected = naive_search_all(&s[..], &pat[..]);
+        expected.sort();
+        let offsets: Vec<usize> = expected
+            .iter()
+            .filter(|&&p| p as usize >= base)
+            .map(|&p| p as usize - base)
+            .collect();
+
+        let mut options = SearchOptions { base, spans: false };
+        let found = sa.search_all_with(&pat[..], options);
+        prop_assert_eq!(found, Occurrences::Offsets(offsets.clone()));
+
+        options.spans = true;
+        let found = sa.search_all_with(&pat[..], options);
+        prop_assert_eq!(found.len(), offsets.len());
+        let spans = offsets.iter().map(|&i| i..i + pat.len()).collect();
+        prop_assert_eq!(found, Occurrences::Spans(spans));
+    }
+
+    #[test]
+    fn occurrences_algebra_correctness(
+        s in prop::collection::vec(0..3_u8, 0..512_usize),
+        pat1 in prop::collection::vec(0..3_u8, 1..4_usize),
+        pat2 in prop::collection::vec(0..3_u8, 1..4_usize),
+        window in 0..32_usize,
+        range in (0..512_usize, 0..512_usize),
+    ) {
+        use super::{Occurrences, SearchOptions};
+
+        let sa = SuffixArray::new(&s[..]);
+        let spans = SearchOptions { base: 0, spans: true };
+        let a = sa.search_all_with(&pat1[..], spans);
+        let b = sa.search_all_with(&pat2[..], SearchOptions::default());
+        let a_vec: Vec<usize> = a.offsets().collect();
+        let b_vec: Vec<usize> = b.offsets().collect();
+
+        let near = |x: usize| b_vec.iter().any(|&y| x.abs_diff(y) <= window);
+        let expected: Vec<usize> =
+            a_vec.iter().cloned().filter(|&x| near(x)).collect();
+        let result = Occurrences::and(a.offsets(), b.offsets(), window);
+        let result: Vec<usize> = result.collect();
+        prop_assert_eq!(result, expected);
+
+        let mut expected = [&a_vec[..], &b_vec[..]].concat();
+        expected.sort();
+        expected.dedup();
+        let result = Occurrences::or(a.offsets(), b.offsets());
+        let result: Vec<usize> = result.collect();
+        prop_assert_eq!(result, expected);
+
+        let range = range.0..range.1;
+        let expected: Vec<usize> =
+            a_vec.iter().cloned().filter(|x| !range.contains(x)).collect();
+        let result = Occurrences::not_within(a.offsets(), range);
+        let result: Vec<usize> = result.collect();
+        prop_assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn unicode_correctness(
+        s in "[aé€😀]{0,64}",
+        pat in "[aé€😀]{0,3}",
+        pos in 0..300_usize,
+    ) {
+        let sa = SuffixArray::new(&s[..]);
+        let snapped = sa.snap_to_char_boundary(pos);
+        let floor = (0..=Ord::min(pos, s.len()))
+            .rev()
+            .find(|&i| s.is_char_boundary(i));
+        prop_assert_eq!(Some(snapped), floor);
+
+        let expected: Vec<_> = s
+            .char_indices()
+            .enumerate()
+            .map(|(i, (p, _))| (i, p))
+            .chain(Some((s.chars().count(), s.len())))
+            .filter(|&(_, p)| s[p..].starts_with(&pat[..]))
+            .collect();
+        let found: Vec<_> = sa.search_all_chars(pat.as_bytes()).collect();
+        prop_assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn replace_range_correctness(
+        s in prop::collection::vec(0..3_u8, 0..1024_usize),
+        edits in prop::collection::vec(
+            (any::<usize>(), 0..8_usize, prop::collection::vec(0..3_u8, 0..8)),
+            1..4_usize,
+        ),
+    ) {
+        let mut sa = SuffixArray::new(s.clone());
+        sa.enable_buckets();
+        let mut text = s;
+        for (start, del, new) in edits {
+            let start = start % (text.len() + 1);
+            let end = Ord::min(start + del, text.len());
+            text.splice(start..end, new.iter().cloned());
+
+            // either repaired locally or rebuilt.
+            sa.replace_range(start..end, &new[..]);
+            prop_assert_eq!(sa.text(), &text[..]);
+            let expected = SuffixArray::new(&text[..]);
+            prop_assert!(sa == expected);
+            let pat = &text[start..Ord::min(start + 3, text.len())];
+            prop_assert_eq!(sa.search_all(pat), expected.search_all(pat));
+        }
+    }
+
+    #[test]
+    fn dedup_report_correctness(
+        s in prop::collection::vec(0..3_u8, 0..256_usize),
+        min_len in 0..6_usize,
+    ) {
+        // greedy LZ77 factorization by brute force.
+        let mut expected = Vec::new();
+        let mut i = 0;
+        while i < s.len() {
+            let len = (0..i).map(|j| lcp(&s[i..], &s[j..])).max().unwrap_or(0);
+            if len >= Ord::max(min_len, 1) {
+                expected.push((i as u32, len as u32));
+            }
+            i += Ord::max(len, 1);
+        }
+
+        let regions = SuffixArray::new(&s[..]).dedup_report(min_len);
+        let found: Vec<_> = regions.iter().map(|r| (r.offset, r.len)).collect();
+        prop_assert_eq!(found, expected);
+        for r in regions {
+            let (src, dst) = (r.source as usize, r.offset as usize);
+            let len = r.len as usize;
+            prop_assert!(src < dst);
+            prop_assert_eq!(&s[src..src + len], &s[dst..dst + len]);
+        }
+    }
+
+    #[test]
+    fn longest_match_at_correctness(
+        s in prop::collection::vec(0..3_u8, 0..256_usize),
+        pos in 0..260_usize,
+        max_len in 0..12_usize,
+        with_lcp in any::<bool>(),
+    ) {
+        let mut sa = SuffixArray::new(&s[..]);
+        if with_lcp {
+            sa.enable_lcp();
+        }
+        let found = sa.longest_match_at(pos, max_len);
+        if pos >= s.len() {
+            prop_assert_eq!(found, None);
+            return Ok(());
+        }
+
+        let len = (0..s.len())
+            .filter(|&j| j != pos)
+            .map(|j| Ord::min(lcp(&s[pos..], &s[j..]), max_len))
+            .max()
+            .unwrap_or(0);
+        match found {
+            Some((range, other)) => {
+                let other = other as usize;
+                prop_assert_eq!(range.clone(), pos..pos + len);
+                prop_assert!(other != pos && len > 0);
+                prop_assert_eq!(&s[other..other + len], &s[range]);
+            }
+            None => prop_assert_eq!(len, 0),
+        }
+    }
+
+    #[test]
+    fn circular_correctness(
+        s in prop::collection::vec(0..3_u8, 0..256_usize),
+        pat in prop::collection::vec(0..3_u8, 0..12_usize),
+    ) {
+        let n = s.len();
+        let rotation = |p: usize| s[p..].iter().chain(&s[..p]).cloned();
+        let expected = (0..n).min_by(|&i, &j| rotation(i).cmp(rotation(j)));
+
+        let mut sa = SuffixArray::new(&s[..]);
+        prop_assert_eq!(sa.min_rotation(), expected.unwrap_or(0));
+
+        let expected: Vec<u32> = (0..n)
+            .filter(|&p| (0..pat.len()).all(|i| s[(p + i) % n] == pat[i]))
+            .map(|p| p as u32)
+            .collect();
+        prop_assert_eq!(&sa.search_circular(&pat[..]), &expected);
+        sa.set_naive_search_length(0);
+        prop_assert_eq!(sa.search_circular(&pat[..]), expected);
+    }
+
+    #[test]
+    fn subindex_correctness(
+        (s, pat) in bytes_with_pat(0..1024_usize),
+        range in (0..1024_usize, 0..1024_usize),
+    ) {
+        let start = Ord::min(range.0, s.len());
+        let end = Ord::min(Ord::max(range.1, start), s.len());
+        let expected = naive_search_all(&s[start..end], &pat[..]);
+
+        let sa = SuffixArray::new(&s[..]);
+        let view = sa.subindex(start..end);
+        prop_assert_eq!(view.text(), &s[start..end]);
+        prop_assert_eq!(view.len(), end - start);
+        prop_assert_eq!(&view.search_all(&pat[..]), &expected);
+        prop_assert_eq!(view.count(&pat[..]), expected.len());
+        prop_assert_eq!(view.contains(&pat[..]), !expected.is_empty());
+
+        let standalone = view.to_suffix_array();
+        let mut result = standalone.search_all(&pat[..]).to_vec();
+        result.sort();
+        prop_assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_trie_correctness(
+        s in prop::collection::vec(0..3_u8, 0..512_usize),
+        pats in prop::collection::vec(
+            prop::collection::vec(0..4_u8, 0..6_usize),
+            0..16_usize,
+        ),
+    ) {
+        use super::PatternTrie;
+
+        let trie = PatternTrie::compile(&pats[..]);
+        prop_assert_eq!(trie.len(), pats.len());
+        let sa = SuffixArray::new(&s[..]);
+        let result = sa.search_trie(&trie);
+        prop_assert_eq!(result.len(), pats.len());
+        for (found, pat) in result.into_iter().zip(pats.iter()) {
+            let mut found = found.to_vec();
+            found.sort();
+            prop_assert_eq!(found, naive_search_all(&s[..], &pat[..]));
+        }
+    }
+
+    #[test]
+    fn workspace_correctness(
+        records in prop::collection::vec(
+            prop_oneof![
+                prop::collection::vec(0..4_u8, 0..256_usize),
+                (0..4_u8, 0..1024_usize).prop_map(|(c, n)| vec![c; n]),
+            ],
+            1..8_usize,
+        ),
+    ) {
+        use super::Workspace;
+
+        let mut workspace = Workspace::new();
+        let mut sizes = Vec::new();
+        for record in records.iter() {
+            let sa = workspace.build(&record[..]);
+            prop_assert!(*sa == SuffixArray::new(&record[..]));
+            drop(sa);
+            sizes.push(workspace.heap_size());
+        }
+
+        // the same records again, without growing the buffers.
+        for record in records.iter() {
+            let sa = workspace.build(&record[..]);
+            prop_assert!(*sa == SuffixArray::new(&record[..]));
+        }
+        prop_assert_eq!(workspace.heap_size(), *sizes.iter().max().unwrap());
+    }
+
+    #[test]
+    fn extensions_correctness(
+        s in prop::collection::vec(0..4_u8, 0..512_usize),
+        pat in prop::collection::vec(0..4_u8, 0..4_usize),
+    ) {
+        let mut expected = std::collections::BTreeMap::new();
+        for p in naive_search_all(&s[..], &pat[..]) {
+            if let Some(&c) = s.get(p as usize + pat.len()) {
+                *expected.entry(c).or_insert(0) += 1;
+            }
+        }
+        let expected: Vec<(u8, usize)> = expected.into_iter().collect();
+
+        let sa = SuffixArray::new(&s[..]);
+        let found: Vec<_> = sa.extensions(&pat[..]).collect();
+        prop_assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn search_all_until_correctness(
+        s in prop::collection::vec(0..3_u8, 0..512_usize),
+        pat in prop::collection::vec(0..3_u8, 0..4_usize),
+        offset in 0..512_u32,
+    ) {
+        use std::ops::ControlFlow;
+
+        let sa = SuffixArray::new(&s[..]);
+        let all = sa.search_all(&pat[..]);
+        let mut visited = 0;
+        let found = sa.search_all_until(&pat[..], |p| {
+            visited += 1;
+            if p >= offset {
+                ControlFlow::Break(p)
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        let expected = all.iter().position(|&p| p >= offset);
+        match expected {
+            Some(i) => {
+                prop_assert_eq!(found, ControlFlow::Break(all[i]));
+                prop_assert_eq!(visited, i + 1);
+            }
+            None => {
+                prop_assert_eq!(found, ControlFlow::Continue(()));
+                prop_assert_eq!(visited, all.len());
+            }
+        }
+    }
+
+    #[test]
+    fn equality_correctness(
+        s in prop::collection::vec(0..4_u8, 0..256_usize),
+        t in prop::collection::vec(0..4_u8, 0..256_usize),
+    ) {
+        let x = SuffixArray::new(&s[..]);
+        let mut y = SuffixArray::new(s.clone());
+        y.enable_buckets();
+        prop_assert!(x == y);
+        prop_assert_eq!(x.content_hash(), y.content_hash());
+
+        let z = SuffixArray::new(&t[..]);
+        prop_assert_eq!(x == z, s == t);
+        if s != t {
+            prop_assert_ne!(x.content_hash(), z.content_hash());
+        }
+    }
+
+    #[test]
+    fn encoded_2bit_correctness(
+        symbols in prop::collection::vec(0..4_u8, 0..2048_usize),
+        pat in prop::collection::vec(0..4_u8, 0..8_usize),
+    ) {
+        let text = Encoded2Bit::from_symbols(&symbols[..]).unwrap();
+        prop_assert_eq!(text.as_bytes().len(), symbols.len().div_ceil(4));
+        let dna = text.to_dna();
+        prop_assert_eq!(Encoded2Bit::from_dna(&dna[..]), Some(text.clone()));
+        for (i, &c) in dna.iter().enumerate() {
+            prop_assert_eq!(dna_symbol(c), Some(symbols[i]));
+            prop_assert_eq!(text.get(i), Some(symbols[i]));
+        }
+
+        let sa = Encoded2BitSuffixArray::new(text);
+        let expected = SuffixArray::new(&symbols[..]).into_parts().1;
+        let mut result = Vec::from(sa.search_all(&pat[..]));
+        result.sort_unstable();
+        prop_assert_eq!(result, naive_search_all(&symbols[..], &pat[..]));
+        prop_assert_eq!(sa.into_parts().1, expected);
+    }
+
+    #[test]
+    fn iter_suffixes_correctness(s in bytes!(0..1024_usize)) {
+        let sa = SuffixArray::new(&s[..]);
+        let suffixes: Vec<_> = sa.iter_suffixes().collect();
+        prop_assert_eq!(suffixes.len(), s.len() + 1);
+        for (i, &(rank, pos, suffix)) in suffixes.iter().enumerate() {
+            prop_assert_eq!(rank, i);
+            prop_assert_eq!(suffix, &s[pos as usize..]);
+            if i > 0 {
+                prop_assert!(suffixes[i - 1].2 < suffix);
+            }
+        }
+
+        let mid = s.len() / 2;
+        let part: Vec<_> = sa.iter_suffixes_in(mid..s.len() + 1).collect();
+        prop_assert_eq!(&part[..], &suffixes[mid..]);
+    }
+
+    #[test]
+    fn contains_correctness((s, pat) in bytes_with_pat(0..4096_usize)) {
+        let naive_result = naive_contains(&s[..], &pat[..]);
+
+        let mut sa = SuffixArray::new(&s[..]);
+        prop_assert_eq!(sa.contains(&pat[..]), naive_result);
+
+        sa.set_naive_search_length(0);
+        let sa_result_simple = sa.contains(&pat[..]);
+        prop_assert_eq!(sa_result_simple, naive_result);
+
+        sa.enable_buckets();
+        let sa_result_bucket = sa.contains(&pat[..]);
+        prop_assert_eq!(sa_result_bucket, naive_result);
+    }
+
+    #[test]
+    fn search_all_correctness((s, pat) in bytes_with_pat(0..4096_usize)) {
+        let mut naive_result = naive_search_all(&s[..], &pat[..]);
+        naive_result.sort();
+
+        let mut sa = SuffixArray::new(&s[..]);
+        let mut sa_result_simple = Vec::from(sa.search_all(&pat[..]));
+        sa_result_simple.sort();
+        prop_assert_eq!(&sa_result_simple[..], &naive_result[..]);
+
+        sa.enable_buckets();
+        let mut sa_result_bucket = Vec::from(sa.search_all(&pat[..]));
+        sa_result_bucket.sort();
+        prop_assert_eq!(&sa_result_bucket[..], &naive_result[..]);
+    }
+
+    #[test]
+    fn search_order_correctness(
+        (s, pat) in bytes_with_pat(0..1024_usize),
+    ) {
+        let sa = SuffixArray::new(&s[..]);
+        let expected = sa.search_all(&pat[..]);
+        let algorithms = [
+            Algorithm::Naive,
+            Algorithm::Divsufsort,
+            Algorithm::Sais,
+            Algorithm::PrefixDoubling,
+        ];
+        for &algorithm in algorithms.iter() {
+            let other = SuffixArray::with_algorithm(&s[..], algorithm);
+            prop_assert_eq!(other.search_all(&pat[..]), expected);
+        }
+
+        let mut sa = sa;
+        let naive = naive_search_all(&s[..], &pat[..]);
+        for &len in [s.len(), 0].iter() {
+            sa.set_naive_search_length(len);
+            prop_assert_eq!(sa.search_all_sorted(&pat[..]), &naive[..]);
+        }
+    }
+
+    #[test]
+    fn search_bitset_correctness((s, pat) in bytes_with_pat(0..1024_usize)) {
+        let naive_result = naive_search_all(&s[..], &pat[..]);
+
+        let sa = SuffixArray::new(&s[..]);
+        let bitset = sa.search_bitset(&pat[..]);
+        prop_assert_eq!(bitset.len(), (s.len() + 64) / 64);
+        let positions: Vec<u32> = (0..bitset.len() as u32 * 64)
+            .filter(|&i| bitset[i as usize / 64] & (1 << (i % 64)) != 0)
+            .collect();
+        prop_assert_eq!(&positions, &naive_result);
+
+        #[cfg(feature = "roaring")]
+        {
+            let bitmap = sa.search_bitmap(&pat[..]);
+            prop_assert_eq!(bitmap.into_iter().collect::<Vec<_>>(), positions);
+        }
+    }
+
+    #[test]
+    fn nth_occurrence_correctness((s, pat) in bytes_with_pat(0..1024_usize)) {
+        let mut sa = SuffixArray::new(&s[..]);
+        let expected = naive_search_all(&s[..], &pat[..]);
+        let mut buf = Vec::new();
+        for &len in [s.len(), 0].iter() {
+            sa.set_naive_search_length(len);
+            for n in 0..expected.len() + 1 {
+                let nth = sa.nth_occurrence(&pat[..], n);
+                prop_assert_eq!(nth, expected.get(n).cloned());
+                let nth = sa.nth_occurrence_into(&pat[..], n, &mut buf);
+                prop_assert_eq!(nth, expected.get(n).cloned());
+            }
+        }
+    }
+
+    #[test]
+    fn cursor_correctness((s, pat) in bytes_with_pat(0..1024_usize)) {
+        let sa = SuffixArray::new(&s[..]);
+        let mut cursor = sa.cursor();
+        prop_assert_eq!(cursor.count(), s.len() + 1);
+        for (i, &c) in pat.iter().enumerate() {
+            let found = cursor.extend(c);
+            prop_assert_eq!(cursor.pattern(), &pat[..i + 1]);
+            prop_assert_eq!(cursor.positions(), sa.search_all(&pat[..i + 1]));
+            prop_assert_eq!(found, cursor.count() > 0);
+        }
+        for i in (0..pat.len()).rev() {
+            prop_assert_eq!(cursor.pop(), Some(pat[i]));
+            prop_assert_eq!(cursor.positions(), sa.search_all(&pat[..i]));
+        }
+        prop_assert_eq!(cursor.pop(), None);
+    }
+
+    #[test]
+    fn cursor_front_correctness(
+        (s, pat) in bytes_with_pat(0..1024_usize),
+        fronts in prop::collection::vec(any::<bool>(), 1..32_usize),
+    ) {
+        let sa = SuffixArray::new(&s[..]);
+        let mut cursor = sa.cursor();
+        let (mut i, mut j) = (pat.len() / 2, pat.len() / 2);
+        for &front in fronts.iter().cycle().take(pat.len()) {
+            if (front && i > 0) || j == pat.len() {
+                i -= 1;
+                cursor.extend_front(pat[i]);
+            } else {
+                cursor.extend(pat[j]);
+                j += 1;
+            }
+            prop_assert_eq!(cursor.pattern(), &pat[i..j]);
+            prop_assert_eq!(cursor.positions(), sa.search_all(&pat[i..j]));
+        }
+        while cursor.pop().is_some() {
+            let pattern = cursor.pattern().to_vec();
+            prop_assert_eq!(cursor.positions(), sa.search_all(&pattern[..]));
+        }
+        prop_assert!(cursor.pattern().is_empty());
+    }
+
+    #[test]
+    fn sample_occurrences_correctness(
+        (s, pat) in bytes_with_pat(0..1024_usize),
+        k in 0..16_usize,
+        seed in any::<u64>(),
+    ) {
+        let sa = SuffixArray::new(&s[..]);
+        let mut state = seed;
+        let rng = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            state
+        };
+        let mut sample = sa.sample_occurrences(&pat[..], k, rng);
+        let mut state = seed;
+        let rng = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            state
+        };
+        let mut reused = vec![0; 4];
+        sa.sample_occurrences_into(&pat[..], k, rng, &mut reused);
+        prop_assert_eq!(&reused, &sample);
+        sample.sort_unstable();
+        let expected = naive_search_all(&s[..], &pat[..]);
+        prop_assert_eq!(sample.len(), Ord::min(k, expected.len()));
+        sample.dedup();
+        prop_assert_eq!(sample.len(), Ord::min(k, expected.len()));
+        for p in sample {
+            prop_assert!(expected.binary_search(&p).is_ok());
+        }
+    }
+
+    #[test]
+    fn search_page_correctness(
+        (s, pat) in bytes_with_pat(0..1024_usize),
+        limit in 1..16_usize,
+    ) {
+        let sa = SuffixArray::new(&s[..]);
+        let all = sa.search_all(&pat[..]);
+
+        let mut pages = Vec::new();
+        let mut offset = Some(0);
+        while let Some(i) = offset {
+            let page = sa.search_page(&pat[..], i, limit);
+            prop_assert_eq!(page.total, all.len());
+            prop_assert!(page.positions.len() <= limit);
+            pages.extend_from_slice(page.positions);
+            offset = page.next;
+        }
+        prop_assert_eq!(&pages[..], all);
+
+        let past = sa.search_page(&pat[..], all.len() + 1, limit);
+        prop_assert!(past.positions.is_empty() && past.next.is_none());
+    }
+
+    #[test]
+    fn search_words_correctness(
+        (s, pat) in bytes_with_pat(0..1024_usize),
+    ) {
+        let boundary = |c: u8| c < 64;
+        let sa = SuffixArray::new(&s[..]);
+        let mut result: Vec<_> = sa.search_words(&pat[..], boundary).collect();
+        result.sort_unstable();
+
+        let expected: Vec<u32> = naive_search_all(&s[..], &pat[..])
+            .into_iter()
+            .filter(|&i| {
+                let (i, j) = (i as usize, i as usize + pat.len());
+                (i == 0 || boundary(s[i - 1]))
+                    && (j == s.len() || boundary(s[j]))
+            })
+            .collect();
+        prop_assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_gapped_correctness(
+        s in prop::collection::vec(0..3_u8, 0..1024_usize),
+        a in prop::collection::vec(0..3_u8, 0..4_usize),
+        b in prop::collection::vec(0..3_u8, 0..4_usize),
+        min_gap in 0..8_usize,
+        extra_gap in 0..8_usize,
+    ) {
+        let gap = min_gap..=min_gap + extra_gap;
+        let ys = naive_search_all(&s[..], &b[..]);
+        let naive_result: Vec<u32> = naive_search_all(&s[..], &a[..])
+            .into_iter()
+            .filter(|&x| {
+                let start = x as usize + a.len();
+                ys.iter().any(|&y| {
+                    y as usize >= start && gap.contains(&(y as usize - start))
+                })
+            })
+            .collect();
+
+        let sa = SuffixArray::new(&s[..]);
+        prop_assert_eq!(sa.search_gapped(&a[..], &b[..], gap), naive_result);
+    }
+
+    #[test]
+    fn max_pattern_len_correctness(
+        (s, pat) in bytes_with_pat(0..1024_usize),
+        max in 0..16_usize,
+        depth in 0..=2_usize,
+    ) {
+        let mut sa = SuffixArray::new(&s[..]);
+        if depth > 0 {
+            sa.enable_buckets_with_depth(depth);
+        }
+        sa.set_max_pattern_len(max);
+        prop_assert_eq!(sa.max_pattern_len(), max);
+
+        let expected = naive_contains(&s[..], &pat[..]);
+        prop_assert!(sa.prefilter(&pat[..]) || !expected);
+        if pat.len() > max {
+            let err = Error::PatternTooLong { len: pat.len(), max };
+            prop_assert_eq!(sa.try_contains(&pat[..]), Err(err.clone()));
+            prop_assert_eq!(sa.try_search_all(&pat[..]), Err(err));
+        } else {
+            prop_assert_eq!(sa.try_contains(&pat[..]), Ok(expected));
+            let all = sa.try_search_all(&pat[..]).unwrap();
+            prop_assert_eq!(all, sa.search_all(&pat[..]));
+        }
+
+        let truncated = sa.truncate_pattern(&pat[..]);
+        prop_assert_eq!(truncated, &pat[..Ord::min(pat.len(), max)]);
+    }
+
+    #[test]
+    fn short_pattern_correctness(
+        s in prop::collection::vec(0..4_u8, 0..4096_usize),
+        pat in prop::collection::vec(0..4_u8, 1..=8_usize),
+    ) {
+        let mut naive_result = naive_search_all(&s[..], &pat[..]);
+        naive_result.sort();
+
+        let mut sa = SuffixArray::new(&s[..]);
+        sa.set_naive_search_length(0);
+        for _ in 0..2 {
+            prop_assert_eq!(sa.contains(&pat[..]), !naive_result.is_empty());
+            let mut sa_result = Vec::from(sa.search_all(&pat[..]));
+            sa_result.sort();
+            prop_assert_eq!(&sa_result[..], &naive_result[..]);
+            sa.enable_buckets();
+        }
+    }
+
+    #[test]
+    fn fast_search_correctness(
+        s in prop::collection::vec(0..3_u8, 0..4096_usize),
+        pat in prop::collection::vec(0..3_u8, 0..16_usize),
+    ) {
+        let mut naive_result = naive_search_all(&s[..], &pat[..]);
+        naive_result.sort();
+
+        let mut sa = SuffixArray::new(&s[..]);
+        sa.set_naive_search_length(0);
+        sa.enable_fast_search();
+        for _ in 0..2 {
+            prop_assert_eq!(sa.contains(&pat[..]), !naive_result.is_empty());
+            let mut sa_result = Vec::from(sa.search_all(&pat[..]));
+            sa_result.sort();
+            prop_assert_eq!(&sa_result[..], &naive_result[..]);
+            sa.enable_buckets();
+        }
+    }
+
+    #[test]
+    fn concurrent_correctness(
+        s in prop::collection::vec(0..3_u8, 0..1024_usize),
+        pats in prop::collection::vec(
+            prop::collection::vec(0..3_u8, 0..4_usize),
+            0..16_usize,
+        ),
+        threads in 1..8_usize,
+    ) {
+        let plain = SuffixArray::new(&s[..]);
+        let expected: Vec<&[u32]> =
+            pats.iter().map(|pat| plain.search_all(&pat[..])).collect();
+
+        // race on the lazily computed structures.
+        let mut sa = SuffixArray::new(&s[..]);
+        sa.enable_lazy_buckets();
+        let sa = &sa;
+        std::thread::scope(|scope| {
+            for pos in 0..threads {
+                scope.spawn(move || sa.rank_of(pos));
+            }
+        });
+        prop_assert_eq!(sa.concurrent_search_all(&pats[..], threads), expected);
+    }
+
+    #[test]
+    fn lazy_buckets_correctness((s, pat) in bytes_with_pat(0..4096_usize)) {
+        let mut eager = SuffixArray::new(&s[..]);
+        eager.enable_buckets();
+        let mut lazy = SuffixArray::new(&s[..]);
+        lazy.enable_lazy_buckets();
+
+        let lazy = &lazy;
+        let pats = [&pat[..], &pat[..Ord::min(pat.len(), 1)], &s[..0]];
+        std::thread::scope(|scope| {
+            for &pat in pats.iter() {
+                scope.spawn(move || lazy.search_all(pat));
+            }
+        });
+        for &pat in pats.iter() {
+            prop_assert_eq!(lazy.contains(pat), eager.contains(pat));
+            prop_assert_eq!(lazy.search_all(pat), eager.search_all(pat));
+            prop_assert_eq!(lazy.search_lcp(pat), eager.search_lcp(pat));
+        }
+    }
+
+    #[test]
+    fn position_filtered_correctness(
+        (s, pat) in bytes_with_pat(0..1024_usize),
+        step in 1..8_u32,
+    ) {
+        let positions: Vec<u32> = (0..=s.len() as u32)
+            .step_by(step as usize)
+            .chain(Some(0))
+            .collect();
+        let sa = PositionFilteredSuffixArray::new(&s[..], &positions[..]);
+        prop_assert_eq!(sa.len(), s.len() / step as usize + 1);
+        for w in sa.positions().windows(2) {
+            prop_assert!(s[w[0] as usize..] < s[w[1] as usize..]);
+        }
+
+        let mut result = Vec::from(sa.search_all(&pat[..]));
+        result.sort_unstable();
+        let expected: Vec<u32> = naive_search_all(&s[..], &pat[..])
+            .into_iter()
+            .filter(|&i| i % step == 0)
+            .collect();
+        prop_assert_eq!(sa.contains(&pat[..]), !expected.is_empty());
+        prop_assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn rank_correctness(s in bytes!(0..1024_usize)) {
+        let sa = SuffixArray::new(&s[..]);
+        for (rank, pos, _) in sa.iter_suffixes() {
+            prop_assert_eq!(sa.pos_at_rank(rank), Some(pos));
+            prop_assert_eq!(sa.rank_of(pos as usize), Some(rank as u32));
+        }
+        prop_assert_eq!(sa.rank_of(s.len()), Some(0));
+        prop_assert_eq!(sa.rank_of(s.len() + 1), None);
+        prop_assert_eq!(sa.pos_at_rank(s.len() + 1), None);
+    }
+
+    #[test]
+    fn search_lcp_correctness((s, pat) in bytes_with_pat(0..1024_usize)) {
+        let naive_result = naive_search_lcp(&s[..], &pat[..]);
+
+        let mut sa = SuffixArray::new(&s[..]);
+        let sa_result_simple = &s[sa.search_lcp(&pat[..])];
+        prop_assert_eq!(sa_result_simple, naive_result);
+
+        sa.enable_buckets();
+        let sa_result_bucket = &s[sa.search_lcp(&pat[..])];
+        prop_assert_eq!(sa_result_bucket, naive_result);
+    }
+
+    #[test]
+    fn search_lcs_suffix_correctness(
+        (s, pat) in bytes_with_pat(0..1024_usize),
+    ) {
+        let sa = SuffixArray::new(&s[..]);
+        let found = sa.search_lcs_suffix(&pat[..]);
+        let len = (0..=pat.len())
+            .rev()
+            .find(|&k| naive_contains(&s[..], &pat[pat.len() - k..]))
+            .unwrap();
+        prop_assert_eq!(found.len(), len);
+        prop_assert_eq!(&s[found], &pat[pat.len() - len..]);
+    }
+
+    #[test]
+    fn reverse_index_correctness(
+        (s, pat) in bytes_with_pat(0..1024_usize),
+        fronts in prop::collection::vec(any::<bool>(), 1..32_usize),
+    ) {
+        let mut sa = SuffixArray::new(&s[..]);
+        prop_assert!(!sa.has_reverse_index());
+        sa.enable_reverse_index();
+        prop_assert!(sa.has_reverse_index());
+
+        let mut ends = sa.search_ends(&pat[..]);
+        ends.sort();
+        let mut expected: Vec<u32> = sa
+            .search_all(&pat[..])
+            .iter()
+            .map(|&p| p + pat.len() as u32)
+            .collect();
+        expected.sort();
+        prop_assert_eq!(ends, expected);
+
+        let (len, mut positions) = sa.search_suffixes_of(&pat[..]);
+        prop_assert_eq!(len, sa.search_lcs_suffix(&pat[..]).len());
+        positions.sort();
+        prop_assert_eq!(positions, sa.search_all_sorted(&pat[pat.len() - len..]));
+
+        let mut cursor = sa.bi_cursor();
+        let (mut i, mut j) = (pat.len() / 2, pat.len() / 2);
+        for &front in fronts.iter().cycle().take(pat.len()) {
+            let found = if (front && i > 0) || j == pat.len() {
+                i -= 1;
+                cursor.extend_front(pat[i])
+            } else {
+                j += 1;
+                cursor.extend(pat[j - 1])
+            };
+            prop_assert_eq!(cursor.pattern(), &pat[i..j]);
+            prop_assert_eq!(found, cursor.count() > 0);
+            let mut positions = cursor.positions();
+            positions.sort();
+            prop_assert_eq!(positions, sa.search_all_sorted(&pat[i..j]));
+        }
+
+        sa.drop_reverse_index();
+        prop_assert!(!sa.has_reverse_index());
+    }
+
+    #[test]
+    fn overlaps_correctness(
+        s in prop::collection::vec(0..2_u8, 0..256_usize),
+        other in prop::collection::vec(0..2_u8, 0..256_usize),
+        min_len in 0..4_usize,
+    ) {
+        let sa = SuffixArray::new(&s[..]);
+        let found = sa.overlaps(&other[..], min_len);
+
+        let max = Ord::min(s.len(), other.len());
+        let lens = (Ord::max(min_len, 1)..=max).rev();
+        let suffix_prefix: Vec<usize> = lens
+            .clone()
+            .filter(|&k| s[s.len() - k..] == other[..k])
+            .collect();
+        let prefix_suffix: Vec<usize> = lens
+            .filter(|&k| s[..k] == other[other.len() - k..])
+            .collect();
+        prop_assert_eq!(found.suffix_prefix, suffix_prefix);
+        prop_assert_eq!(found.prefix_suffix, prefix_suffix);
+    }
+
+    #[test]
+    fn search_lcp_all_correctness((s, pat) in bytes_with_pat(0..1024_usize)) {
+        let naive_len = naive_search_lcp(&s[..], &pat[..]).len();
+        let mut naive_result: Vec<u32> = (0..=s.len())
+            .filter(|&i| lcp(&pat[..], &s[i..]) == naive_len)
+            .map(|i| i as u32)
+            .collect();
+        naive_result.sort();
+
+        let sa = SuffixArray::new(&s[..]);
+        let (len, positions) = sa.search_lcp_all(&pat[..]);
+        let mut sa_result: Vec<u32> = positions.collect();
+        sa_result.sort();
+        prop_assert_eq!(len, naive_len);
+        prop_assert_eq!(sa_result, naive_result);
+    }
+
+    #[test]
+    fn best_prefix_match_correctness(
+        (s, pat) in bytes_with_pat(0..1024_usize),
+        depth in 0..=3_usize,
+    ) {
+        let naive_len = naive_search_lcp(&s[..], &pat[..]).len();
+
+        let mut sa = SuffixArray::new(&s[..]);
+        if depth > 0 {
+            sa.enable_buckets_with_depth(depth);
+        }
+        let (len, hit) = sa.best_prefix_match(&pat[..]);
+        prop_assert_eq!(len, naive_len);
+        prop_assert_eq!(hit.clone(), sa.search_full(&pat[..len]));
+        let range = sa.search_lcp(&pat[..]);
+        prop_assert!(hit.positions.contains(&(range.start as u32)));
+        if !pat.is_empty() {
+            prop_assert_eq!(sa.matching_statistics(&pat[..])[0], len);
+        }
+    }
+
+    #[test]
+    fn min_count_substrings_correctness(
+        s in prop::collection::vec(0..3_u8, 0..64_usize),
+        min_len in 0..4_usize,
+        min_count in 0..5_usize,
+    ) {
+        let sa = SuffixArray::new(&s[..]);
+        let mut result: Vec<(Vec<u8>, Vec<u32>)> = sa
+            .substrings_with_min_count(min_len, min_count)
+            .map(|(sub, occ)| {
+                let mut occ = Vec::from(occ);
+                occ.sort();
+                (Vec::from(sub), occ)
+            })
+            .collect();
+        result.sort();
+
+        let mut naive_result = Vec::new();
+        for i in 0..s.len() {
+            for j in i + Ord::max(min_len, 1)..=s.len() {
+                let sub = &s[i..j];
+                let occ = naive_search_all(&s[..], sub);
+                let mut next: Vec<_> = occ
+                    .iter()
+                    .map(|&p| s.get(p as usize + sub.len()))
+                    .collect();
+                next.sort();
+                next.dedup();
+                let repeated = occ.len() >= Ord::max(min_count, 2);
+                if repeated && next.len() > 1 && occ[0] as usize == i {
+                    naive_result.push((Vec::from(sub), occ));
+                }
+            }
+        }
+        naive_result.sort();
+        prop_assert_eq!(result, naive_result);
+    }
+
+    #[test]
+    fn kmer_counts_correctness(
+        s in prop::collection::vec(0..4_u8, 0..1024_usize),
+        k in 1..8_usize,
+    ) {
+        let mut expected = std::collections::BTreeMap::new();
+        for w in s.windows(k) {
+            *expected.entry(w).or_insert(0_u32) += 1;
+        }
+        let expected: Vec<_> = expected.into_iter().collect();
+
+        let mut sa = SuffixArray::new(&s[..]);
+        let result: Vec<_> = sa.kmer_counts(k).collect();
+        prop_assert_eq!(&result, &expected);
+
+        sa.enable_lcp();
+        let result: Vec<_> = sa.kmer_counts(k).collect();
+        prop_assert_eq!(&result, &expected);
+
+        sa.enable_lcp_with(LcpKind::Compressed);
+        let result: Vec<_> = sa.kmer_counts(k).collect();
+        prop_assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_masked_correctness(
+        s in prop::collection::vec(0..8_u8, 0..1024_usize),
+        pat_mask in prop::collection::vec(
+            (0..8_u8, prop::sample::select(vec![0_u8, 0, 0, 1, 6, 0xff])),
+            0..6_usize,
+        ),
+    ) {
+        let (pat, mask): (Vec<u8>, Vec<u8>) = pat_mask.into_iter().unzip();
+        let expected: Vec<u32> = (0..=s.len())
+            .filter(|&p| p + pat.len() <= s.len())
+            .filter(|&p| {
+                let window = &s[p..p + pat.len()];
+                let masked = |i: usize, c: u8| c | mask[i];
+                let eq = |i: usize| masked(i, window[i]) == masked(i, pat[i]);
+                (0..pat.len()).all(eq)
+            })
+            .map(|p| p as u32)
+            .collect();
+
+        let mut sa = SuffixArray::new(&s[..]);
+        prop_assert_eq!(&sa.search_masked(&pat[..], &mask[..]), &expected);
+        // the trailing bytes without don't-care bits may be left out.
+        let len = mask.iter().rposition(|&bits| bits != 0).map_or(0, |i| i + 1);
+        prop_assert_eq!(&sa.search_masked(&pat[..], &mask[..len]), &expected);
+        sa.set_naive_search_length(0);
+        prop_assert_eq!(sa.search_masked(&pat[..], &mask[..]), expected);
+    }
+
+    #[test]
+    fn search_full_correctness(
+        s in prop::collection::vec(0..4_u8, 0..1024_usize),
+        pat in prop::collection::vec(0..4_u8, 0..4_usize),
+        depth in 0..3_usize,
+    ) {
+        let mut sa = SuffixArray::new(&s[..]);
+        if depth > 0 {
+            sa.enable_buckets_with_depth(depth);
+        }
+        let hit = sa.search_full(&pat[..]);
+        prop_assert_eq!(hit.positions, sa.search_all(&pat[..]));
+        prop_assert_eq!(hit.count(), hit.positions.len());
+        for (rank, &p) in hit.sa_range.clone().zip(hit.positions) {
+            prop_assert_eq!(sa.pos_at_rank(rank), Some(p));
+        }
+
+        let mut expected = naive_search_all(&s[..], &pat[..]);
+        expected.sort();
+        prop_assert_eq!(hit.sorted(), expected);
+
+        // the empty range is at the insertion point.
+        let lt = sa.iter_suffixes().filter(|&(_, _, suf)| suf < &pat[..]);
+        prop_assert_eq!(hit.sa_range.start, lt.count());
+    }
+
+    #[test]
+    fn prefix_filter_correctness(
+        (s, pat) in bytes_with_pat(0..2048_usize),
+        absent in bytes!(0..12_usize),
+        k in 1..=8_usize,
+    ) {
+        let plain = SuffixArray::new(&s[..]);
+        let mut sa = SuffixArray::new(&s[..]);
+        sa.enable_prefix_filter(k);
+        for p in [&pat[..], &absent[..]] {
+            prop_assert_eq!(sa.contains(p), plain.contains(p));
+            prop_assert_eq!(sa.search_all(p), plain.search_all(p));
+            prop_assert!(sa.prefilter(p) || !plain.contains(p));
+            prop_assert!(sa.prefilter(p) || p.len() >= k);
+            if !sa.prefilter(p) {
+                prop_assert_eq!(sa.explain(p).candidates, 0);
+            }
+        }
+        prop_assert!(sa.memory_usage().extra > 0);
+
+        // the filter follows the byte string.
+        let mut sa = SuffixArray::new(s.clone());
+        sa.enable_prefix_filter(k);
+        sa.set(absent.clone());
+        prop_assert!(sa.contains(&absent[..]));
+    }
+
+    #[test]
+    fn search_deadline_correctness(
+        s in prop::collection::vec(0..4_u8, 0..1024_usize),
+        pat in prop::collection::vec(0..4_u8, 0..4_usize),
+        mask in prop::collection::vec(0..4_u8, 0..4_usize),
+    ) {
+        use std::time::{Duration, Instant};
+
+        let mask = &mask[..Ord::min(mask.len(), pat.len())];
+        let pat = &pat[..mask.len()];
+        let sa = SuffixArray::new(&s[..]);
+        let later = Instant::now() + Duration::from_secs(3600);
+        let found = sa.search_all_deadline(pat, later);
+        prop_assert!(found.complete);
+        prop_assert_eq!(&found.positions[..], sa.search_all(pat));
+        let found = sa.search_masked_deadline(pat, mask, later);
+        prop_assert!(found.complete);
+        prop_assert_eq!(found.positions, sa.search_masked(pat, mask));
+
+        // nothing is verified after the deadline.
+        let found = sa.search_all_deadline(pat, Instant::now());
+        prop_assert!(found.positions.is_empty());
+        prop_assert_eq!(found.complete, sa.search_all(pat).is_empty());
+    }
+
+    #[test]
+    fn top_substrings_correctness(
+        s in prop::collection::vec(0..3_u8, 0..80_usize),
+        k in 0..12_usize,
+        min_len in 0..4_usize,
+    ) {
+        // right-maximal repeats, i.e. not always followed by the same byte.
+        let mut expected = Vec::new();
+        let mut seen = HashSet::new();
+        for i in 0..s.len() {
+            for j in i + Ord::max(min_len, 1)..=s.len() {
+                let sub = &s[i..j];
+                let count = naive_search_all(&s[..], sub).len();
+                let extended = (0..3_u8).any(|c| {
+                    let mut ext = sub.to_vec();
+                    ext.push(c);
+                    naive_search_all(&s[..], &ext[..]).len() == count
+                });
+                if count >= 2 && !extended && seen.insert(sub) {
+                    expected.push((Reverse(count), Reverse(sub.len()), sub));
+                }
+            }
+        }
+        expected.sort();
+        expected.truncate(k);
+
+        let sa = SuffixArray::new(&s[..]);
+        let result = sa.top_substrings(k, min_len);
+        prop_assert_eq!(result.len(), expected.len());
+        for (sub, (count, _, bytes)) in result.iter().zip(expected) {
+            let offset = sub.offset as usize;
+            prop_assert_eq!(&s[offset..offset + sub.len as usize], bytes);
+            prop_assert_eq!(sub.count as usize, count.0);
+            let first = naive_search_all(&s[..], bytes)[0];
+            prop_assert_eq!(offset, first as usize);
+        }
+    }
+
+    #[test]
+    fn rs_bitvec_correctness(
+        bits in prop::collection::vec(prop::bool::weighted(0.3), 0..2048_usize),
+    ) {
+        let ones = bits.iter().enumerate().filter(|(_, &b)| b).map(|(i, _)| i);
+        let bv = RsBitVec::from_ones(bits.len(), ones.clone());
+        let mut words = vec![!0u64; bits.len().div_ceil(64)];
+        for (i, &b) in bits.iter().enumerate() {
+            if !b {
+                words[i / 64] &= !(1 << (i % 64));
+            }
+        }
+        prop_assert_eq!(&RsBitVec::from_words(words, bits.len()), &bv);
+
+        let ones: Vec<usize> = ones.collect();
+        let zeros: Vec<usize> = (0..bits.len()).filter(|&i| !bits[i]).collect();
+        prop_assert_eq!(bv.len(), bits.len());
+        prop_assert_eq!(bv.count_ones(), ones.len());
+        prop_assert_eq!(bv.count_zeros(), zeros.len());
+        prop_assert_eq!(bv.get(bits.len()), None);
+        for i in 0..=bits.len() {
+            let rank = bits[..i].iter().filter(|&&b| b).count();
+            prop_assert_eq!(bv.rank1(i), rank);
+            prop_assert_eq!(bv.rank0(i), i - rank);
+            if i < bits.len() {
+                prop_assert_eq!(bv.get(i), Some(bits[i]));
+            }
+        }
+        for k in 0..=bits.len() {
+            prop_assert_eq!(bv.select1(k), ones.get(k).copied());
+            prop_assert_eq!(bv.select0(k), zeros.get(k).copied());
+        }
+    }
+
+    #[test]
+    fn index_cell_correctness(
+        texts in prop::collection::vec(
+            prop::collection::vec(0..4_u8, 0..64_usize),
+            1..8_usize,
+        ),
+        pat in prop::collection::vec(0..4_u8, 1..3_usize),
+    ) {
+        let cell = IndexCell::new(SuffixArray::new(texts[0].clone()));
+        std::thread::scope(|scope| {
+            let readers: Vec<_> = (0..2)
+                .map(|_| {
+                    scope.spawn(|| {
+                        for _ in 0..texts.len() {
+                            let sa = cell.load();
+                            let mut result = sa.search_all(&pat[..]).to_vec();
+                            result.sort();
+                            assert_eq!(result, naive_search_all(sa.text(), &pat[..]));
+                        }
+                    })
+                })
+                .collect();
+            for (i, s) in texts.iter().enumerate().skip(1) {
+                let old = cell.store(SuffixArray::new(s.clone()));
+                assert_eq!(old.text(), &texts[i - 1][..]);
+            }
+            readers.into_iter().for_each(|r| r.join().unwrap());
+        });
+        prop_assert_eq!(cell.generation(), texts.len() as u64 - 1);
+        let last = cell.into_inner();
+        prop_assert_eq!(last.text(), &texts[texts.len() - 1][..]);
+    }
+
+    #[test]
+    fn lcp_at_correctness(
+        s in prop::collection::vec(0..3_u8, 0..1024_usize),
+    ) {
+        let mut sa = SuffixArray::new(&s[..]);
+        let expected = lcp_array(&s[..], &Vec::from(sa.clone())[..]);
+        let kinds = [None, Some(LcpKind::Plain), Some(LcpKind::Compressed)];
+        for &kind in kinds.iter() {
+            match kind {
+                Some(kind) => sa.enable_lcp_with(kind),
+                None => sa.drop_lcp(),
+            }
+            prop_assert_eq!(sa.lcp_kind(), kind);
+            let lcp: Vec<u32> = (0..=s.len())
+                .map(|i| sa.lcp_at(i).unwrap())
+                .collect();
+            prop_assert_eq!(&lcp, &expected);
+            prop_assert_eq!(sa.lcp_at(s.len() + 1), None);
+        }
+
+        prop_assert!(sa.memory_usage().lcp <= s.len() / 2 + 16);
+    }
+
+    #[test]
+    fn export_blocks_correctness(
+        s in prop::collection::vec(0..4_u8, 0..512_usize),
+        block_len in 1..64_usize,
+        c in 0..5_u8,
+    ) {
+        let sa = SuffixArray::new(&s[..]);
+        let blocks: Vec<&[u32]> = sa.export_blocks(block_len).collect();
+        let bounds = sa.export_block_bounds(block_len);
+        prop_assert_eq!(blocks.concat(), Vec::from(sa.clone()));
+        prop_assert_eq!(blocks.len(), bounds.len());
+        prop_assert!(blocks.iter().all(|b| !b.is_empty() && b.len() <= block_len));
+
+        for (block, bound) in blocks.iter().zip(bounds.iter()) {
+            let starts = block.iter().any(|&p| s.get(p as usize) == Some(&c));
+            if starts {
+                prop_assert!(bound.may_start_with(c));
+            }
+        }
+    }
+
+    #[test]
+    fn neighbors_of_rank_correctness(
+        s in prop::collection::vec(0..3_u8, 0..256_usize),
+        rank in 0..260_usize,
+        w in 0..8_usize,
+    ) {
+        let sa = SuffixArray::new(&s[..]);
+        let all: Vec<_> = sa.iter_suffixes().collect();
+        for (i, p, suffix) in all.iter().cloned() {
+            prop_assert_eq!(sa.suffix_at_rank(i), Some((p, suffix)));
+        }
+        prop_assert_eq!(sa.suffix_at_rank(s.len() + 1), None);
+
+        let expected: Vec<_> = all
+            .iter()
+            .cloned()
+            .filter(|&(i, _, _)| i + w >= rank && i <= rank + w)
+            .collect();
+        let found: Vec<_> = sa.neighbors_of_rank(rank, w).collect();
+        prop_assert_eq!(found, expected);
     }
 
     #[test]
-    fn contains_correctness((s, pat) in bytes_with_pat(0..4096_usize)) {
-        let naive_result = naive_contains(&s[..], &pat[..]);
+    fn coverage_against_correctness(
+        s in prop::collection::vec(0..3_u8, 0..256_usize),
+        t in prop::collection::vec(0..3_u8, 0..256_usize),
+        min_len in 0..6_usize,
+    ) {
+        let x = SuffixArray::new(&s[..]);
+        let y = SuffixArray::new(&t[..]);
+        let coverage = x.coverage_against(&y, min_len);
+
+        let k = Ord::max(min_len, 1);
+        let mut covered = vec![false; s.len()];
+        for i in 0..(s.len() + 1).saturating_sub(k) {
+            if !naive_search_all(&t[..], &s[i..i + k]).is_empty() {
+                covered[i..i + k].iter_mut().for_each(|c| *c = true);
+            }
+        }
+        let mut expected: Vec<std::ops::Range<usize>> = Vec::new();
+        for i in (0..s.len()).filter(|&i| covered[i]) {
+            match expected.last_mut() {
+                Some(last) if last.end == i => last.end += 1,
+                _ => expected.push(i..i + 1),
+            }
+        }
+        prop_assert_eq!(coverage, expected);
+    }
+
+    #[test]
+    fn complexity_profile_correctness(
+        s in prop::collection::vec(0..3_u8, 0..512_usize),
+        max_k in 0..12_usize,
+    ) {
+        let expected: Vec<u64> = (1..=max_k)
+            .map(|k| s.windows(k).collect::<HashSet<_>>().len() as u64)
+            .collect();
+        let sa = SuffixArray::new(&s[..]);
+        prop_assert_eq!(sa.complexity_profile(max_k), expected);
+    }
+
+    #[test]
+    fn frequency_transform_correctness(
+        s in prop::collection::vec(0..4_u8, 0..1024_usize),
+        window in 1..8_usize,
+    ) {
+        let mut counts = std::collections::HashMap::new();
+        for w in s.windows(window) {
+            *counts.entry(w).or_insert(0_u32) += 1;
+        }
+        let expected: Vec<u32> = (0..s.len())
+            .map(|i| s.get(i..i + window).map_or(0, |w| counts[w]))
+            .collect();
+
+        let sa = SuffixArray::new(&s[..]);
+        prop_assert_eq!(sa.frequency_transform(window), expected);
+    }
 
+    #[test]
+    fn memory_usage_correctness(
+        s in prop::collection::vec(0..4_u8, 0..1024_usize),
+    ) {
         let mut sa = SuffixArray::new(&s[..]);
-        let sa_result_simple = sa.contains(&pat[..]);
-        prop_assert_eq!(sa_result_simple, naive_result);
+        let base = sa.memory_usage();
+        prop_assert!(base.sa >= 4 * (s.len() + 1));
+        prop_assert_eq!(base.total(), base.sa);
 
         sa.enable_buckets();
-        let sa_result_bucket = sa.contains(&pat[..]);
-        prop_assert_eq!(sa_result_bucket, naive_result);
+        sa.enable_lcp();
+        sa.enable_fast_search();
+        let full = sa.memory_usage();
+        prop_assert_eq!(full.sa, base.sa);
+        prop_assert!(full.buckets > 0);
+        prop_assert!(full.lcp >= 4 * (s.len() + 1));
+        prop_assert!(full.extra > 0);
+
+        sa.disable_buckets();
+        sa.drop_lcp();
+        let shed = sa.memory_usage();
+        prop_assert_eq!(shed.buckets, 0);
+        prop_assert_eq!(shed.lcp, 0);
+        prop_assert_eq!(shed.extra, full.extra);
+        prop_assert_eq!(sa.bucket_depth(), 0);
     }
 
     #[test]
-    fn search_all_correctness((s, pat) in bytes_with_pat(0..4096_usize)) {
+    fn warm_correctness(
+        s in prop::collection::vec(0..4_u8, 0..20000_usize),
+        pat in prop::collection::vec(0..4_u8, 1..4_usize),
+    ) {
+        let mut sa = SuffixArray::new(&s[..]);
+        let pages = |bytes: usize| bytes.div_ceil(4096);
+        prop_assert_eq!(sa.warm(WarmLevel::Index), pages(4 * (s.len() + 1)));
+
+        let samples = sa.warm(WarmLevel::SearchPaths) - sa.warm(WarmLevel::Index);
+        // every 32nd suffix except the empty one at rank 0.
+        prop_assert_eq!(samples, s.len() / 32);
+        let full = sa.warm(WarmLevel::Full) - sa.warm(WarmLevel::Index);
+        prop_assert_eq!(full, pages(s.len()));
+
+        let before = Vec::from(sa.search_all(&pat[..]));
+        sa.enable_buckets();
+        sa.enable_lcp();
+        prop_assert!(sa.warm(WarmLevel::Index) > pages(4 * (s.len() + 1)));
+        prop_assert_eq!(sa.search_all(&pat[..]), &before[..]);
+    }
+
+    #[test]
+    fn longest_palindrome_correctness(
+        s in prop::collection::vec(0..3_u8, 0..256_usize),
+    ) {
+        let sa = SuffixArray::new(&s[..]);
+        prop_assert_eq!(sa.longest_palindrome(), naive_longest_palindrome(&s[..]));
+    }
+
+    #[test]
+    fn similarity_correctness(
+        s in prop::collection::vec(0..3_u8, 0..256_usize),
+        query in prop::collection::vec(0..3_u8, 0..32_usize),
+        q in 1..4_usize,
+    ) {
+        let sa = SuffixArray::new(&s[..]);
+        let ms: Vec<usize> = (0..query.len())
+            .map(|i| naive_search_lcp(&s[..], &query[i..]).len())
+            .collect();
+        prop_assert_eq!(&sa.matching_statistics(&query[..]), &ms);
+
+        let lcs = ms.iter().cloned().max().unwrap_or(0) as f64;
+        let sum = ms.iter().sum::<usize>() as f64;
+        prop_assert_eq!(sa.similarity(&query[..], SimilarityKind::LcsLen), lcs);
+        prop_assert_eq!(
+            sa.similarity(&query[..], SimilarityKind::MatchingStatisticsSum),
+            sum
+        );
+
+        let x: HashSet<&[u8]> = s.windows(q).collect();
+        let y: HashSet<&[u8]> = query.windows(q).collect();
+        let union = x.union(&y).count();
+        let jaccard = if union == 0 {
+            0.0
+        } else {
+            x.intersection(&y).count() as f64 / union as f64
+        };
+        prop_assert_eq!(sa.similarity(&query[..], SimilarityKind::Jaccard(q)), jaccard);
+        prop_assert_eq!(sa.similarity(&query[..], SimilarityKind::Jaccard(0)), 0.0);
+    }
+
+    #[test]
+    fn matching_statistics_repetitive(
+        unit in prop::collection::vec(0..3_u8, 1..8_usize),
+        copies in 1..64_usize,
+        query_copies in 1..256_usize,
+        noise in prop::collection::vec((any::<prop::sample::Index>(), 0..3_u8), 0..4_usize),
+    ) {
+        let s = unit.repeat(copies);
+        let mut query = unit.repeat(query_copies);
+        for (i, c) in noise {
+            let i = i.index(query.len());
+            query[i] = c;
+        }
+        let sa = SuffixArray::new(&s[..]);
+        let ms: Vec<usize> = (0..query.len())
+            .map(|i| sa.search_lcp(&query[i..]).len())
+            .collect();
+        prop_assert_eq!(sa.matching_statistics(&query[..]), ms);
+    }
+
+    #[test]
+    fn contains_any_window_correctness(
+        s in prop::collection::vec(0..3_u8, 0..256_usize),
+        stream in prop::collection::vec(0..3_u8, 0..256_usize),
+        window_len in 0..8_usize,
+    ) {
+        let naive_result = window_len <= stream.len()
+            && (0..=stream.len() - window_len)
+                .any(|i| naive_contains(&s[..], &stream[i..i + window_len]));
+
+        let sa = SuffixArray::new(&s[..]);
+        let sa_result = sa.contains_any_window(&stream[..], window_len).unwrap();
+        prop_assert_eq!(sa_result, naive_result);
+    }
+
+    #[test]
+    fn normalization_correctness(
+        s in prop::collection::vec(
+            prop::sample::select(&b"ab \r\n"[..]),
+            0..256_usize,
+        ),
+        crlf in any::<bool>(),
+        collapse_whitespace in any::<bool>(),
+        pat in prop::collection::vec(
+            prop::sample::select(&b"ab \r\n"[..]),
+            1..4_usize,
+        ),
+    ) {
+        let options = Normalization { crlf, collapse_whitespace };
+        let norm = NormalizedText::new(&s[..], options);
+        let text = options.apply(&s[..]);
+        prop_assert_eq!(norm.text(), &text[..]);
+        prop_assert_eq!(norm.original_offset(text.len()), s.len());
+
+        // each normalized byte comes from its own span of original bytes.
+        for (p, &c) in text.iter().enumerate() {
+            let span = norm.original_range(p..p + 1);
+            prop_assert_eq!(options.apply(&s[span]), vec![c]);
+        }
+
+        let pat = options.apply(&pat[..]);
+        let sa = SuffixArray::new(norm);
+        let mut hits = Vec::from(sa.search_all(&pat[..]));
+        hits.sort();
+        let (norm, _) = sa.into_parts();
+        for p in hits.into_iter().map(|p| p as usize) {
+            let range = norm.original_range(p..p + pat.len());
+            prop_assert_eq!(options.apply(&s[range]), pat.clone());
+        }
+    }
+
+    #[test]
+    fn corpus_set_correctness(
+        s in prop::collection::vec(
+            prop::sample::select(&b"aB \r\n"[..]),
+            0..256_usize,
+        ),
+        pat in prop::collection::vec(
+            prop::sample::select(&b"aB \r\n"[..]),
+            1..4_usize,
+        ),
+    ) {
+        let options = Normalization { crlf: true, collapse_whitespace: true };
+        let transforms = [
+            ("raw", Transform::Raw),
+            ("lower", Transform::AsciiLowercase),
+            ("norm", Transform::Normalize(options)),
+            ("raw2", Transform::Raw),
+        ];
+        let mut corpus = CorpusSet::new(&s[..]);
+        for &(name, transform) in transforms.iter() {
+            corpus.add_index(name, transform);
+        }
+        prop_assert!(corpus.buffer_count() <= 3);
+        prop_assert_eq!(corpus.search_all("none", &pat[..]), None);
+
+        for &(name, transform) in transforms.iter() {
+            let expected = transform.apply(&pat[..]);
+            let hits = corpus.search_all(name, &pat[..]).unwrap();
+            prop_assert!(hits.windows(2).all(|w| w[0].start < w[1].start));
+            for range in hits.iter().cloned() {
+                prop_assert_eq!(transform.apply(&s[range]), expected.clone());
+            }
+            let sa = corpus.index(name).unwrap();
+            let fresh = SuffixArray::new(transform.apply(&s[..]));
+            prop_assert_eq!(sa.raw_sa(), fresh.raw_sa());
+            prop_assert_eq!(sa.text(), fresh.text());
+            prop_assert_eq!(hits.len(), sa.search_all(&expected[..]).len());
+            prop_assert_eq!(corpus.contains(name, &pat[..]), Some(!hits.is_empty()));
+        }
+
+        // identical byte strings share one suffix array, counted once.
+        let shared = |a, b| {
+            std::ptr::eq(corpus.index(a).unwrap(), corpus.index(b).unwrap())
+        };
+        prop_assert!(shared("raw", "raw2"));
+        prop_assert_eq!(shared("raw", "lower"), !s.contains(&b'B'));
+        let heap_size = corpus.heap_size();
+
+        prop_assert!(corpus.remove_index("raw2"));
+        prop_assert_eq!(corpus.names().collect::<Vec<_>>(), vec!["raw", "lower", "norm"]);
+        prop_assert_eq!(corpus.heap_size(), heap_size);
+    }
+
+    #[test]
+    fn anchored_correctness((s, pat) in bytes_with_pat(0..1024_usize)) {
+        let sa = SuffixArray::new(&s[..]);
+        prop_assert_eq!(sa.starts_with(&pat[..]), s.starts_with(&pat[..]));
+        prop_assert_eq!(sa.ends_with(&pat[..]), s.ends_with(&pat[..]));
+
         let mut naive_result = naive_search_all(&s[..], &pat[..]);
         naive_result.sort();
+        for pos in 0..=s.len() + 1 {
+            let expected = naive_result.binary_search(&(pos as u32)).is_ok();
+            prop_assert_eq!(sa.matches_at(pos, &pat[..]), expected);
+        }
+    }
 
-        let mut sa = SuffixArray::new(&s[..]);
-        let mut sa_result_simple = Vec::from(sa.search_all(&pat[..]));
-        sa_result_simple.sort();
-        prop_assert_eq!(&sa_result_simple[..], &naive_result[..]);
+    #[test]
+    fn replacements_correctness(
+        (s, pat) in bytes_with_pat(1..1024_usize),
+        rep in bytes!(0..8_usize),
+    ) {
+        prop_assume!(!pat.is_empty());
+        let naive_result = naive_replace(&s[..], &pat[..], &rep[..]);
 
-        sa.enable_buckets();
-        let mut sa_result_bucket = Vec::from(sa.search_all(&pat[..]));
-        sa_result_bucket.sort();
-        prop_assert_eq!(&sa_result_bucket[..], &naive_result[..]);
+        let sa = SuffixArray::new(&s[..]);
+        let splices = sa.plan_replacements(&pat[..], &rep[..]);
+        prop_assert_eq!(sa.apply_replacements(&splices[..]), naive_result);
     }
 
     #[test]
-    fn search_lcp_correctness((s, pat) in bytes_with_pat(0..1024_usize)) {
-        let naive_result = naive_search_lcp(&s[..], &pat[..]);
+    fn rlfm_correctness(
+        s in prop::collection::vec(0..3_u8, 0..1024_usize),
+        pat in prop::collection::vec(0..3_u8, 0..6_usize),
+    ) {
+        let sa = SuffixArray::new(&s[..]);
+        let rlfm = RlfmIndex::from(&sa);
+        prop_assert_eq!(rlfm.len(), s.len());
+        prop_assert_eq!(&rlfm.locate(&pat[..])[..], sa.search_all(&pat[..]));
+        prop_assert_eq!(rlfm.count(&pat[..]), sa.search_all(&pat[..]).len());
+    }
 
-        let mut sa = SuffixArray::new(&s[..]);
-        let sa_result_simple = &s[sa.search_lcp(&pat[..])];
-        prop_assert_eq!(sa_result_simple, naive_result);
+    #[test]
+    fn sampled_correctness(
+        s in prop::collection::vec(0..3_u8, 0..1024_usize),
+        pats in prop::collection::vec(
+            prop::collection::vec(0..3_u8, 0..6_usize),
+            1..8_usize,
+        ),
+        shift in 0..6_u32,
+        skew in 0..64_usize,
+    ) {
+        let base = 1 << shift;
+        let sa = SuffixArray::new(&s[..]);
+        let mut index = SampledIndex::from_suffix_array(&sa, base);
+        prop_assert_eq!(index.len(), s.len());
+        prop_assert_eq!(index.base_rate(), base);
 
-        sa.enable_buckets();
-        let sa_result_bucket = &s[sa.search_lcp(&pat[..])];
-        prop_assert_eq!(sa_result_bucket, naive_result);
+        for round in 0..3 {
+            // a skewed workload, repeating the first pattern.
+            for (i, pat) in pats.iter().enumerate() {
+                let times = if i == 0 { skew + 1 } else { 1 };
+                for _ in 0..times {
+                    prop_assert_eq!(&index.locate(&pat[..])[..], sa.search_all(&pat[..]));
+                }
+                prop_assert_eq!(index.count(&pat[..]), sa.search_all(&pat[..]).len());
+            }
+            if round == 2 {
+                break;
+            }
+
+            index.retune();
+            prop_assert!(index.sample_count() <= 2 * (s.len() / base) + 258);
+            for c in 0..=255_u8 {
+                if let Some(rate) = index.bucket_rate(c) {
+                    prop_assert!(rate < base && base % rate == 0);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "pack")]
+    #[test]
+    fn shards_checkpoint_correctness(
+        (s, pat) in bytes_with_pat(0..1024_usize),
+        count in 1..6_usize,
+        corrupt in 0..6_usize,
+    ) {
+        let dir = temp_path("shards_checkpoint");
+        std::fs::create_dir_all(&dir).unwrap();
+        let shards = Shards::with_checkpoints(&s[..], count, 16, &dir).unwrap();
+        let pat = &pat[..Ord::min(pat.len(), shards.max_pattern_len())];
+        let expected = Shards::new(&s[..], count, 16).search_all(pat);
+        prop_assert_eq!(shards.search_all(pat), expected.clone());
+
+        // resumed from the checkpoints, one of which is corrupt or missing.
+        let path = dir.join(format!("shard-{}-of-{}.sa", corrupt, count));
+        if corrupt % 2 == 0 {
+            let _ = std::fs::write(&path, b"junk");
+        } else {
+            let _ = std::fs::remove_file(&path);
+        }
+        let resumed = Shards::with_checkpoints(&s[..], count, 16, &dir);
+        prop_assert_eq!(resumed.unwrap().search_all(pat), expected);
+        prop_assert!(path.exists() || corrupt >= count);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn shards_correctness(
+        (s, pat) in bytes_with_pat(0..1024_usize),
+        count in 1..8_usize,
+    ) {
+        let shards = Shards::new(&s[..], count, 16);
+        prop_assert_eq!(shards.count(), count);
+        prop_assert_eq!(shards.len(), s.len() as u64);
+        let pat = &pat[..Ord::min(pat.len(), shards.max_pattern_len())];
+
+        let expected: Vec<u64> = naive_search_all(&s[..], pat)
+            .into_iter()
+            .map(|p| p as u64)
+            .collect();
+        prop_assert_eq!(shards.search_all(pat), expected.clone());
+        prop_assert_eq!(shards.contains(pat), !expected.is_empty());
+
+        // the same shards placed past 4 GiB.
+        let base = 5 << 30;
+        let step = s.len() / count + 1;
+        let parts: Vec<_> = (0..s.len().div_ceil(step).max(1))
+            .map(|i| {
+                let end = Ord::min(i * step + step + 16, s.len());
+                let sa = SuffixArray::new(&s[i * step..end]);
+                (base + (i * step) as u64, sa)
+            })
+            .collect();
+        let shards = Shards::from_parts(16, parts).unwrap();
+        let expected: Vec<u64> = expected.iter().map(|&p| base + p).collect();
+        prop_assert_eq!(shards.search_all(pat), expected.clone());
+        for &p in expected.iter() {
+            let (i, local) = shards.to_local(p).unwrap();
+            prop_assert_eq!(shards.to_global(i, local), Some(p));
+        }
+        prop_assert!(shards.to_local(base - 1).is_none());
+        prop_assert!(shards.to_local(base + s.len() as u64 + 1).is_none());
+        prop_assert!(Shards::from_parts(16, Vec::new()).is_none());
+    }
+
+    #[test]
+    fn shards_long_pattern_correctness(
+        s in prop::collection::vec(0..2_u8, 0..512_usize),
+        pat in prop::collection::vec(0..2_u8, 0..12_usize),
+        count in 1..8_usize,
+        overlap in 0..4_usize,
+    ) {
+        let shards = Shards::new(&s[..], count, overlap);
+        let expected: Vec<u64> = naive_search_all(&s[..], &pat[..])
+            .into_iter()
+            .map(|p| p as u64)
+            .collect();
+        prop_assert_eq!(shards.search_all(&pat[..]), expected.clone());
+        prop_assert_eq!(shards.contains(&pat[..]), !expected.is_empty());
+    }
+
+    #[test]
+    fn sliding_window_correctness(
+        window in 1..64_usize,
+        chunks in prop::collection::vec(
+            prop::collection::vec(0..3_u8, 0..100_usize),
+            0..16_usize,
+        ),
+        pat in prop::collection::vec(0..3_u8, 0..4_usize),
+    ) {
+        let mut index = SlidingWindowIndex::new(window).unwrap();
+        let mut stream = Vec::new();
+        for chunk in chunks.iter() {
+            index.push(&chunk[..]);
+            stream.extend_from_slice(&chunk[..]);
+
+            let start = stream.len().saturating_sub(window);
+            let w = &stream[start..];
+            prop_assert_eq!(index.window_bytes(), w);
+            prop_assert_eq!(index.window_offset(), start as u64);
+
+            let naive_result: Vec<u64> = naive_search_all(w, &pat[..])
+                .into_iter()
+                .map(|p| start as u64 + p as u64)
+                .collect();
+            let mut result = index.search_all(&pat[..]);
+            result.sort();
+            prop_assert_eq!(index.contains(&pat[..]), !naive_result.is_empty());
+            prop_assert_eq!(result, naive_result);
+        }
+    }
+
+    #[test]
+    fn raw_correctness(s in bytes!(0..1024_usize)) {
+        let sa = SuffixArray::new(&s[..]);
+        let raw = Vec::from(sa.raw_sa());
+        prop_assert_eq!(&raw[..], &sa.clone().into_parts().1[1..]);
+        let raw64 = sa.to_raw_sa64();
+        prop_assert!(SuffixArray::from_raw_sa(&s[..], &raw).is_some());
+        prop_assert!(SuffixArray::from_raw_sa64(&s[..], &raw64).is_some());
+
+        for &width in [RawWidth::U32, RawWidth::U64].iter() {
+            let mut bytes = Vec::new();
+            sa.dump_raw(&mut bytes, width).unwrap();
+            let entry = if width == RawWidth::U32 { 4 } else { 8 };
+            prop_assert_eq!(bytes.len(), raw.len() * entry);
+            let loaded = SuffixArray::load_raw(&s[..], &bytes[..], width).unwrap();
+            prop_assert_eq!(loaded.raw_sa(), &raw[..]);
+
+            bytes.push(0);
+            prop_assert!(SuffixArray::load_raw(&s[..], &bytes[..], width).is_err());
+        }
+    }
+
+    #[test]
+    fn index_file_correctness(s in bytes!(0..4096_usize)) {
+        let path = temp_path("index_file");
+        std::fs::write(&path, &s).unwrap();
+        let sa = index_file(&path).unwrap();
+        prop_assert_eq!(sa.into_parts(), SuffixArray::new(s.clone()).into_parts());
+
+        #[cfg(feature = "pack")]
+        {
+            use super::{index_and_save, PackOptions};
+
+            let out = temp_path("index_and_save");
+            index_and_save(&path, &out, PackOptions::default()).unwrap();
+            let owned = OwnedSuffixArray::load_owned_file(&out).unwrap();
+            prop_assert_eq!(owned.text(), &s[..]);
+
+            let options = PackOptions { with_text: false };
+            index_and_save(&path, &out, options).unwrap();
+            prop_assert!(SuffixArray::load_file(&s[..], &out).is_ok());
+            std::fs::remove_file(&out).unwrap();
+        }
+
+        #[cfg(feature = "mmap")]
+        {
+            let sa = SuffixArray::new_from_file(&path).unwrap();
+            prop_assert_eq!(sa.text(), &s[..]);
+            let expected = SuffixArray::new(&s[..]).into_parts().1;
+            prop_assert_eq!(sa.into_parts().1, expected);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(any(unix, windows))]
+    #[test]
+    fn provided_correctness(
+        (s, pat) in bytes_with_pat(0..4096_usize),
+        i in any::<prop::sample::Index>(),
+    ) {
+        use super::{FileText, ProvidedSuffixArray};
+
+        let sa = SuffixArray::new(&s[..]);
+        let path = temp_path("provided");
+        std::fs::write(&path, &s).unwrap();
+        let file = FileText::open(&path).unwrap();
+        let raw = Vec::from(sa.clone());
+        let provided = ProvidedSuffixArray::new(file, raw.clone()).unwrap();
+        prop_assert!(provided.check_integrity().unwrap());
+        prop_assert_eq!(provided.search_all(&pat[..]).unwrap(), sa.search_all(&pat[..]));
+        prop_assert_eq!(provided.contains(&pat[..]).unwrap(), sa.contains(&pat[..]));
+        drop(provided);
+        std::fs::remove_file(&path).unwrap();
+
+        #[cfg(feature = "pack")]
+        {
+            let bytes = sa.dump_bytes().unwrap();
+            let loaded = ProvidedSuffixArray::load(&s[..], &bytes[..]).unwrap();
+            prop_assert_eq!(loaded.into_parts().1, Vec::from(sa.clone()));
+        }
+
+        // swap two adjacent entries after the empty suffix.
+        let mut swapped = raw.clone();
+        if swapped.len() > 2 {
+            let i = 1 + i.index(swapped.len() - 2);
+            swapped.swap(i, i + 1);
+            let provided = ProvidedSuffixArray::new(&s[..], swapped).unwrap();
+            prop_assert!(!provided.check_integrity().unwrap());
+        }
+        let mut truncated = raw;
+        truncated.pop();
+        prop_assert!(ProvidedSuffixArray::new(&s[..], truncated).is_err());
     }
 
     #[cfg(feature = "pack")]
@@ -67,15 +2154,438 @@ proptest! {
         let bytes1 = sa1.dump_bytes().unwrap();
         let mut bytes2 = Vec::with_capacity(bytes1.len());
         sa1.dump(Cursor::new(&mut bytes2)).unwrap();
-        let sa2 = SuffixArray::load_bytes(&s[..], &*bytes1).unwrap();
+        let sa2 = SuffixArray::load_bytes(&s[..], &bytes1).unwrap();
 
         let (_, sa1) = sa1.into_parts();
         let (_, sa2) = sa2.into_parts();
         prop_assert_eq!(sa1, sa2);
-        prop_assert_eq!(bytes1, bytes2);
+        prop_assert_eq!(&bytes1, &bytes2);
+
+        // declared length (after the magic number) mismatching the data.
+        let mut bad = bytes1;
+        bad[4] ^= 0x80;
+        prop_assert!(SuffixArray::load_bytes(&s[..], &bad).is_err());
+    }
+
+    #[cfg(feature = "pack")]
+    #[test]
+    fn pack_corrupted_correctness(
+        s in prop::collection::vec(0..3_u8, 0..1024_usize),
+        edits in prop::collection::vec(
+            (any::<prop::sample::Index>(), any::<u8>()),
+            0..4_usize,
+        ),
+        cut in any::<prop::sample::Index>(),
+    ) {
+        let sa = SuffixArray::new(&s[..]);
+        let mut bytes = Vec::new();
+        sa.dump_with_text(&mut bytes).unwrap();
+        for (i, x) in edits.iter() {
+            let i = i.index(bytes.len());
+            bytes[i] = *x;
+        }
+        bytes.truncate(cut.index(bytes.len() + 1));
+
+        // never panics, and never loads an inconsistent suffix array.
+        if let Ok(loaded) = SuffixArray::load_bytes(&s[..], &bytes[..]) {
+            prop_assert!(loaded == sa);
+        }
+        if let Ok(loaded) = OwnedSuffixArray::load_owned(&bytes[..]) {
+            prop_assert_eq!(loaded.clone().into_parts().1.len(), loaded.len() + 1);
+        }
+
+        // the length is checked before unpacking.
+        let bytes = sa.dump_bytes().unwrap();
+        if !s.is_empty() {
+            let loaded = SuffixArray::load_bytes(&s[1..], &bytes[..]);
+            let err = loaded.err().unwrap();
+            let expected = Error::LengthMismatch {
+                expected: s.len(),
+                found: s.len() + 1,
+            };
+            prop_assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+            let inner = err.get_ref().and_then(|e| e.downcast_ref::<Error>());
+            prop_assert_eq!(inner, Some(&expected));
+        }
+    }
+
+    #[cfg(feature = "pack")]
+    #[test]
+    fn pack_trimmed_chunk_correctness(chunks in 1..8_usize) {
+        // the last chunk only contains the suffix at 0, which packs to zeros.
+        let mut s = vec![b'a'; chunks * 128];
+        s[0] = b'b';
+        let sa1 = SuffixArray::try_new(&s[..]).unwrap();
+        let bytes = sa1.dump_bytes().unwrap();
+        let sa2 = SuffixArray::load_bytes(&s[..], &bytes).unwrap();
+        prop_assert_eq!(sa1.into_parts().1, sa2.into_parts().1);
+    }
+
+    #[test]
+    fn owned_correctness((s, pat) in bytes_with_pat(0..1024_usize)) {
+        let owned = OwnedSuffixArray::new(s.clone());
+        let copy = owned.clone();
+        drop(owned);
+        prop_assert_eq!(copy.text(), &s[..]);
+        let mut sa_result = Vec::from(copy.search_all(&pat[..]));
+        sa_result.sort();
+        prop_assert_eq!(sa_result, naive_search_all(&s[..], &pat[..]));
+
+        let shared = SuffixArray::new(Arc::<[u8]>::from(&s[..]));
+        prop_assert_eq!(shared.search_all(&pat[..]), copy.search_all(&pat[..]));
+
+        let (text, sa) = copy.into_parts();
+        prop_assert_eq!(&sa, &SuffixArray::new(&s[..]).into_parts().1);
+        prop_assert!(OwnedSuffixArray::from_parts(text.clone(), sa).is_some());
+        prop_assert!(OwnedSuffixArray::from_parts(text, Vec::new()).is_none());
+    }
+
+    #[cfg(feature = "pack")]
+    #[test]
+    fn archive_correctness(
+        (s, pat) in bytes_with_pat(0..4096_usize),
+        with_buckets in any::<bool>(),
+        with_lcp in any::<bool>(),
+        with_top in any::<bool>(),
+        with_rev in any::<bool>(),
+    ) {
+        use super::{Archive, Section};
+        use std::io::Cursor;
+
+        let mut sections = Vec::new();
+        if with_buckets {
+            sections.push(Section::Buckets);
+        }
+        if with_lcp {
+            sections.push(Section::Lcp);
+        }
+        if with_top {
+            sections.push(Section::TopSubstrings { k: 4, min_len: 2 });
+        }
+        if with_rev {
+            sections.push(Section::ReverseIndex);
+        }
+        let sa = SuffixArray::new(&s[..]);
+        let mut bytes = vec![0xff; 3];
+        sa.dump_archive(&mut bytes, &sections[..]).unwrap();
+
+        let mut file = Cursor::new(&bytes[..]);
+        file.set_position(3);
+        let archive = Archive::open(file).unwrap();
+        prop_assert_eq!(archive.has_section(Section::Buckets), with_buckets);
+        prop_assert_eq!(archive.has_section(Section::Lcp), with_lcp);
+
+        let mut loaded = archive.load(&s[..]).unwrap();
+        prop_assert_eq!(archive.load_buckets(&mut loaded).is_ok(), with_buckets);
+        prop_assert_eq!(loaded.bucket_depth(), if with_buckets { 2 } else { 0 });
+        prop_assert_eq!(loaded.search_all(&pat[..]), sa.search_all(&pat[..]));
+
+        let expected = lcp_array(&s[..], &Vec::from(sa.clone())[..]);
+        match archive.lcp() {
+            Ok(lcp) => prop_assert_eq!(lcp, &expected[..]),
+            Err(_) => prop_assert!(!with_lcp),
+        }
+        match archive.top_substrings(s.len()) {
+            Ok(top) => prop_assert_eq!(top, sa.top_substrings(4, 2)),
+            Err(_) => prop_assert!(!with_top),
+        }
+        let has_top = Section::TopSubstrings { k: 0, min_len: 0 };
+        prop_assert_eq!(archive.has_section(has_top), with_top);
+        prop_assert_eq!(archive.load_reverse_index(&mut loaded).is_ok(), with_rev);
+        prop_assert_eq!(loaded.has_reverse_index(), with_rev);
+        prop_assert_eq!(loaded.search_suffixes_of(&pat[..]), sa.search_suffixes_of(&pat[..]));
+
+        // the last section is corrupt, which is rebuilt if optional.
+        let mut corrupt = bytes.clone();
+        *corrupt.last_mut().unwrap() ^= 1;
+        let mut file = Cursor::new(&corrupt[..]);
+        file.set_position(3);
+        let archive = Archive::open(file).unwrap();
+        let loaded = archive.load(&s[..]);
+        let optional = with_lcp || with_top || with_rev;
+        prop_assert_eq!(loaded.is_ok(), with_buckets || optional);
+        let top = archive.top_substrings(s.len());
+        prop_assert!(!with_top || with_rev || top.is_err());
+        if let Ok(mut loaded) = loaded {
+            let rebuilt = archive.load_buckets_or_rebuild(&mut loaded);
+            prop_assert_eq!(rebuilt.unwrap(), with_buckets && optional);
+            prop_assert_eq!(loaded.bucket_depth(), 2);
+            let lcp = archive.lcp_or_rebuild(&loaded).unwrap();
+            prop_assert_eq!(lcp, &expected[..]);
+            let rebuilt = archive.load_reverse_index_or_rebuild(&mut loaded);
+            prop_assert_eq!(rebuilt.unwrap(), false);
+            prop_assert!(loaded.has_reverse_index());
+        }
+
+        let mut truncated = Cursor::new(&bytes[..bytes.len() - 1]);
+        truncated.set_position(3);
+        prop_assert!(Archive::open(truncated).is_err());
+    }
+
+    #[cfg(feature = "ffi")]
+    #[test]
+    fn ffi_correctness((s, pat) in bytes_with_pat(0..1024_usize)) {
+        use super::ffi::*;
+        use std::ffi::CString;
+
+        let expected = naive_search_all(&s[..], &pat[..]);
+        unsafe {
+            let sa = suffix_array_new(s.as_ptr(), s.len());
+            prop_assert!(!sa.is_null());
+            prop_assert_eq!(suffix_array_len(sa), s.len());
+            let found = suffix_array_contains(sa, pat.as_ptr(), pat.len());
+            prop_assert_eq!(found, !expected.is_empty() as i32);
+
+            // a short buffer gets the first ones and the total count.
+            let mut out = vec![0; expected.len() / 2];
+            let count = suffix_array_search_all(
+                sa,
+                pat.as_ptr(),
+                pat.len(),
+                out.as_mut_ptr(),
+                out.len(),
+            );
+            prop_assert_eq!(count, expected.len() as isize);
+            prop_assert_eq!(&out[..], &expected[..out.len()]);
+
+            let path = temp_path("ffi");
+            let c_path = CString::new(path.to_str().unwrap()).unwrap();
+            prop_assert_eq!(suffix_array_dump(sa, c_path.as_ptr()), 0);
+            suffix_array_free(sa);
+            let sa = suffix_array_load(c_path.as_ptr());
+            std::fs::remove_file(&path).unwrap();
+            prop_assert!(!sa.is_null());
+            let mut out = vec![0; expected.len()];
+            let count = suffix_array_search_all(
+                sa,
+                pat.as_ptr(),
+                pat.len(),
+                out.as_mut_ptr(),
+                out.len(),
+            );
+            prop_assert_eq!(count, expected.len() as isize);
+            prop_assert_eq!(&out, &expected);
+            suffix_array_free(sa);
+
+            let null = std::ptr::null();
+            prop_assert_eq!(suffix_array_contains(null, pat.as_ptr(), 1), -1);
+            prop_assert!(suffix_array_load(null as *const _).is_null());
+        }
+    }
+
+    #[cfg(feature = "python")]
+    #[test]
+    fn python_correctness((s, pat) in bytes_with_pat(0..1024_usize)) {
+        use super::python::PySuffixArray;
+        use pyo3::prelude::*;
+        use pyo3::types::{PyBytes, PyDict};
+
+        let expected = naive_search_all(&s[..], &pat[..]);
+        let path = temp_path("python");
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| -> PyResult<()> {
+            let locals = PyDict::new_bound(py);
+            let class = py.get_type_bound::<PySuffixArray>();
+            locals.set_item("SuffixArray", class)?;
+            locals.set_item("s", PyBytes::new_bound(py, &s[..]))?;
+            locals.set_item("pat", PyBytes::new_bound(py, &pat[..]))?;
+            locals.set_item("path", path.to_str().unwrap())?;
+            py.run_bound(
+                "sa = SuffixArray(s)\n\
+                 found = sa.search_all(pat)\n\
+                 sa.dump(path)\n\
+                 loaded = SuffixArray.load(path)\n\
+                 assert len(loaded) == len(s) and loaded.text() == s\n\
+                 assert (pat in loaded) == loaded.contains(pat)\n\
+                 assert list(loaded.search_all(pat)) == list(found)\n",
+                None,
+                Some(&locals),
+            )?;
+            let found: Vec<u32> = py
+                .eval_bound("list(found)", None, Some(&locals))?
+                .extract()?;
+            assert_eq!(found, expected);
+            let contains: bool =
+                py.eval_bound("pat in sa", None, Some(&locals))?.extract()?;
+            assert_eq!(contains, !expected.is_empty());
+            Ok(())
+        })
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "bench-tools")]
+    #[test]
+    fn bench_tools_correctness(
+        alphabet in any::<u8>(),
+        len in 0..4096_usize,
+        seed in any::<u64>(),
+    ) {
+        use super::bench_tools::*;
+
+        let text = random_text(alphabet, len, seed);
+        prop_assert_eq!(&text, &random_text(alphabet, len, seed));
+        prop_assert!(text.iter().all(|&c| c <= alphabet));
+
+        let mut rng = BenchRng::new(seed);
+        for scheme in PATTERN_SCHEMES.iter() {
+            let pat = scheme.generate(&text[..], &mut rng);
+            let total = scheme.len.calculate(len);
+            prop_assert_eq!(pat.len(), total);
+            let selected = total - scheme.junk.calculate(total);
+            prop_assert!(!naive_search_all(&text[..], &pat[..selected])
+                .is_empty());
+        }
+
+        let config = BenchConfig {
+            queries: 2,
+            seed,
+            ..BenchConfig::default()
+        };
+        let report = run(&text[..], &config);
+        prop_assert_eq!(report.text_len, len);
+        prop_assert_eq!(report.constructions.len(), config.algorithms.len());
+        let applied = PATTERN_SCHEMES.iter().filter(|p| p.applies_to(len));
+        prop_assert_eq!(report.queries.len(), applied.count() * 3);
+        for q in report.queries.iter() {
+            prop_assert_eq!(q.samples, 2);
+            prop_assert!(q.min_nanos <= q.median_nanos);
+            prop_assert!(q.median_nanos <= q.max_nanos);
+        }
+        let memory = SuffixArray::new(&text[..]).memory_usage().sa;
+        prop_assert_eq!(report.memory.sa, memory);
+
+        let mut json = Vec::new();
+        report.write_json(&mut json).unwrap();
+        prop_assert_eq!(String::from_utf8(json).unwrap(), report.to_json());
+    }
+
+    #[cfg(feature = "pack")]
+    #[test]
+    fn posting_lists_correctness(
+        s in prop::collection::vec(0..3_u8, 0..4096_usize),
+        patterns in prop::collection::vec(
+            prop::collection::vec(0..3_u8, 0..4_usize),
+            0..8_usize,
+        ),
+    ) {
+        use super::PostingLists;
+
+        let sa = SuffixArray::new(&s[..]);
+        let postings = sa.posting_lists(&patterns[..]);
+        let mut bytes = Vec::new();
+        postings.dump(&mut bytes).unwrap();
+        let loaded = PostingLists::load(&bytes[..]).unwrap();
+        prop_assert_eq!(&loaded, &postings);
+
+        prop_assert_eq!(loaded.len(), patterns.len());
+        for (i, pat) in patterns.iter().enumerate() {
+            let mut expected = naive_search_all(&s[..], &pat[..]);
+            expected.sort();
+            prop_assert_eq!(loaded.pattern(i), Some(&pat[..]));
+            prop_assert_eq!(loaded.count(i), Some(expected.len()));
+            prop_assert_eq!(loaded.positions(i), Some(expected));
+            prop_assert!(loaded.find(&pat[..]).is_some_and(|j| j <= i));
+        }
+        prop_assert_eq!(loaded.positions(patterns.len()), None);
+
+        if !bytes.is_empty() {
+            bytes.truncate(bytes.len() - 1);
+            prop_assert!(PostingLists::load(&bytes[..]).is_err());
+        }
+    }
+
+    #[cfg(feature = "pack")]
+    #[test]
+    fn pack_owned_correctness(s in bytes!(0..4096_usize)) {
+        let sa = SuffixArray::new(&s[..]);
+        let mut bytes = Vec::new();
+        sa.dump_with_text(&mut bytes).unwrap();
+
+        let owned = OwnedSuffixArray::load_owned(&bytes[..]).unwrap();
+        prop_assert_eq!(owned.text(), &s[..]);
+        prop_assert_eq!(owned.into_parts().1, sa.into_parts().1);
+    }
+}
+
+proptest! {
+    // the inputs are either large or checked thoroughly, so run fewer cases.
+    #![proptest_config(ProptestConfig::with_cases(24))]
+
+    #[test]
+    fn bucket_depth_correctness(
+        depth in 1..=MAX_BUCKET_DEPTH,
+        s in prop::collection::vec(
+            (0..3_u8).prop_map(|c| c * 2),
+            0..256_usize,
+        ),
+        pat in prop::collection::vec(0..6_u8, 0..=MAX_BUCKET_DEPTH + 1),
+    ) {
+        let mut sa = SuffixArray::new(&s[..]);
+        prop_assert_eq!(sa.bucket_depth(), 0);
+        sa.enable_buckets_with_depth(depth);
+        prop_assert_eq!(sa.bucket_depth(), depth);
+        prop_assert!(sa.memory_usage().buckets < 16 << 10);
+
+        // patterns shorter than, equal to and longer than the depth.
+        for len in 0..=pat.len() {
+            let pat = &pat[..len];
+            let mut naive_result = naive_search_all(&s[..], pat);
+            naive_result.sort();
+            let mut sa_result = Vec::from(sa.search_all(pat));
+            sa_result.sort();
+            prop_assert_eq!(sa.contains(pat), !naive_result.is_empty());
+            prop_assert_eq!(&sa_result[..], &naive_result[..]);
+            prop_assert_eq!(&s[sa.search_lcp(pat)], naive_search_lcp(&s[..], pat));
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_buckets_correctness(
+        depth in 1..=MAX_BUCKET_DEPTH,
+        s in prop::collection::vec(0..4_u8, 65536..70000_usize),
+        pat in prop::collection::vec(0..4_u8, 1..=MAX_BUCKET_DEPTH + 1),
+    ) {
+        let mut naive_result = naive_search_all(&s[..], &pat[..]);
+        naive_result.sort();
+
+        let mut sa = SuffixArray::new(&s[..]);
+        sa.enable_buckets_with_depth(depth);
+        let mut sa_result = Vec::from(sa.search_all(&pat[..]));
+        sa_result.sort();
+        prop_assert_eq!(&sa_result[..], &naive_result[..]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_integrity_correctness(
+        s in prop::collection::vec(0..4_u8, 65536..70000_usize),
+        i in any::<prop::sample::Index>(),
+    ) {
+        let (s, mut sa) = SuffixArray::new(&s[..]).into_parts();
+        prop_assert!(SuffixArray::from_parts(s, sa.clone()).is_some());
+
+        // swap two adjacent entries.
+        let i = i.index(sa.len() - 1);
+        sa.swap(i, i + 1);
+        prop_assert!(SuffixArray::from_parts(s, sa).is_none());
     }
 }
 
+#[test]
+fn send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SuffixArray<&[u8]>>();
+    assert_send_sync::<SuffixArray<Vec<u8>>>();
+    assert_send_sync::<SuffixArray<Arc<[u8]>>>();
+    assert_send_sync::<SuffixArray<NormalizedText>>();
+    assert_send_sync::<RlfmIndex>();
+    assert_send_sync::<SlidingWindowIndex>();
+    #[cfg(feature = "pack")]
+    assert_send_sync::<super::Archive<std::fs::File>>();
+}
+
 fn bytes_with_pat(
     len: impl Strategy<Value = usize>,
 ) -> impl Strategy<Value = (Vec<u8>, Vec<u8>)> {
@@ -101,6 +2611,23 @@ fn bytes_with_pat(
     })
 }
 
+fn temp_path(name: &str) -> std::path::PathBuf {
+    let name = format!("suffix_array-{}-{}", std::process::id(), name);
+    std::env::temp_dir().join(name)
+}
+
+fn naive_longest_palindrome(s: &[u8]) -> std::ops::Range<usize> {
+    let mut best = 0..0;
+    for i in 0..s.len() {
+        for j in i + best.len() + 1..=s.len() {
+            if s[i..j].iter().eq(s[i..j].iter().rev()) {
+                best = i..j;
+            }
+        }
+    }
+    best
+}
+
 fn naive_contains(s: &[u8], pat: &[u8]) -> bool {
     for i in 0..=s.len().saturating_sub(pat.len()) {
         if pat == &s[i..Ord::min(s.len(), i + pat.len())] {
@@ -120,6 +2647,21 @@ fn naive_search_all(s: &[u8], pat: &[u8]) -> Vec<u32> {
     result
 }
 
+fn naive_replace(s: &[u8], pat: &[u8], rep: &[u8]) -> Vec<u8> {
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < s.len() {
+        if s[i..].starts_with(pat) {
+            result.extend_from_slice(rep);
+            i += pat.len();
+        } else {
+            result.push(s[i]);
+            i += 1;
+        }
+    }
+    result
+}
+
 fn naive_search_lcp<'s>(s: &[u8], pat: &'s [u8]) -> &'s [u8] {
     let mut matched = &pat[..0];
     for i in 0..=s.len() {
@@ -130,3 +2672,142 @@ fn naive_search_lcp<'s>(s: &[u8], pat: &'s [u8]) -> &'s [u8] {
     }
     matched
 }
+
+#[test]
+fn sais_ints_reduced_alphabet() {
+    // bytes folded into 80 characters, once found by sais_ints_correctness.
+    let s: &[u8] = &[
+        90, 95, 136, 205, 199, 51, 158, 106, 135, 154, 59, 154, 140, 107, 82,
+        85, 17, 227, 142, 31, 90, 18, 61, 160, 220, 29, 6, 8, 211, 108, 131,
+        125, 242, 41, 22, 209, 243, 219, 241, 13, 7, 237, 27, 213, 205, 71, 84,
+        116, 58, 194, 111, 87, 205, 61, 169, 85, 167, 27, 244, 230, 37, 5, 151,
+        28, 159, 173, 192, 182, 216, 4, 98, 113, 161, 223, 81, 80, 188, 186,
+        133, 218, 117, 48, 75, 236, 137, 1, 91, 5, 53, 6, 74, 51, 216, 108,
+        195, 102, 137, 123, 252, 240, 92, 149, 182, 173, 18, 48, 29, 102, 34,
+        138, 18, 77, 160, 79, 136, 96, 23, 157, 154, 131, 190, 25, 87, 13, 211,
+        146, 114, 89, 112, 164, 175, 127, 144, 48, 17, 105, 19, 192, 153, 193,
+        66, 62, 93, 142, 46, 107, 18, 134, 31, 239, 154, 175, 18, 60, 217, 109,
+        150, 19, 15, 19, 255, 104, 39, 217, 250, 19, 2, 221, 94, 255, 240, 199,
+        164, 214, 67, 195, 244, 19, 70, 120, 126, 52, 68, 195, 125, 0, 225,
+        125, 149, 24, 154, 106, 119, 66, 15, 8, 179, 195, 72, 74, 163, 74, 251,
+        109, 57, 113, 240, 192, 202, 13, 43, 62, 219, 80, 0, 73, 91, 64, 153,
+        80, 98, 119, 159, 73, 197, 66, 222, 34, 46, 29, 244, 251, 157, 162, 16,
+        27, 19, 98, 74, 114, 102, 131, 211, 11, 2, 144, 79, 61, 54, 247, 63,
+        43, 177, 55, 208, 122, 225, 201, 191, 227, 17, 98, 41, 46, 187, 188,
+        131, 160, 40, 190, 133, 213, 135, 135, 255, 109, 204, 2, 171, 62, 128,
+        0, 31, 218, 131, 178, 137, 111, 251, 177, 65, 5, 228, 229, 236, 152, 7,
+        32, 208, 39, 221, 102, 60, 100, 60, 222, 12, 35, 183, 241, 85, 232,
+        157, 228, 31, 171, 211, 89, 19, 124, 183, 214, 246, 78, 139, 124, 124,
+        182, 187, 240, 27, 85, 232, 219, 37, 215, 157, 235, 191, 150, 179, 195,
+        46, 218, 200, 214, 154, 235, 85, 82, 159, 220, 234, 84, 192, 189, 173,
+        67, 133, 81, 181, 236, 25, 231, 233, 84, 153, 179, 178, 65, 41, 90,
+        166, 67, 255, 157, 164, 191, 80, 111, 217, 93, 100, 112, 250, 180, 197,
+        225, 178, 240, 175, 248, 135, 203, 10, 23, 147, 84, 39, 225, 157, 136,
+        47, 131, 158, 174, 149, 43, 167, 126, 31, 23, 78, 245, 81, 90, 38, 224,
+        2, 21, 107, 66, 58, 226, 7, 69, 31, 167, 161, 142, 40, 11, 166, 86,
+        212, 227, 89, 192, 62, 180, 158, 160, 129, 104, 67, 151, 132, 47, 27,
+        130, 148, 175, 81, 45, 67, 13, 37, 177, 224, 141, 81, 193, 210, 37,
+        221, 177, 84, 86, 237, 90, 214, 204, 151, 230, 152, 200, 191, 147, 254,
+        147, 94, 33, 160, 14, 53, 217, 164, 226, 202, 174, 6, 162, 226, 64, 22,
+        253, 39, 12, 133, 151, 17, 52, 216, 253, 120, 95, 57, 147, 21, 45, 195,
+        185, 211, 238, 8, 207, 112, 54, 221, 183, 135, 230, 219, 222, 169, 109,
+        14, 231, 85, 246, 224, 144, 225, 216, 233, 169, 183, 165, 191, 40, 23,
+        23, 200, 127, 225, 199, 82, 106, 222, 223, 200, 91, 134, 73, 52, 212,
+        22, 251, 186, 100, 12, 160, 205, 205, 133, 35, 193, 77, 135, 84, 235,
+        107, 82, 94, 38, 213, 232, 236, 38, 11, 176, 254, 23, 162, 17, 71, 3,
+        128, 7, 180, 255, 173, 213, 130, 151, 221, 82, 164, 37, 151, 85, 12,
+        110, 170, 81, 148, 85, 36, 171, 157, 217, 212, 250, 226, 59, 79, 78,
+        166, 54, 14, 198, 120, 226, 108, 149, 249, 170, 134, 22, 19, 249, 160,
+        9, 112, 122, 62, 176, 229, 190, 43, 176, 55, 64, 130, 25, 18, 4, 78,
+        225, 158, 246, 119, 218, 115, 191, 127, 86, 242, 245, 66, 86, 72, 199,
+        43, 237, 140, 10, 98, 138, 138, 63, 165, 151, 2, 103, 137, 167, 122, 4,
+        81, 119, 122, 48, 50, 83, 231, 177, 29, 190, 215, 115, 90, 156, 109,
+        150, 98, 5, 164, 114, 117, 18, 139, 223, 254, 219, 201, 165, 132, 234,
+        96, 86, 249, 1, 122, 207, 76, 117, 211, 19, 33, 209, 83, 90, 235, 250,
+        82, 4, 100, 159, 159, 2, 39, 184, 20, 191, 106, 205, 35, 255, 32, 15,
+        222, 207, 82, 237, 110, 45, 58, 151, 220, 124, 109, 116, 101, 122, 106,
+        139, 148, 199, 182, 223, 36, 70, 91, 97, 170, 119, 11, 130, 174, 248,
+        17, 225, 174, 81, 48, 74, 18, 145, 249, 143, 100, 255, 218, 181, 51,
+        157, 170, 211, 194, 93, 230, 88, 117, 181, 251, 134, 217, 49, 88, 90,
+        131, 59, 49, 93, 52, 14, 96, 184, 232, 250, 3, 220, 210, 140, 121, 212,
+        30, 177, 252, 87, 60, 160, 46, 42, 164, 11, 220, 5, 76, 92, 162, 57,
+        167, 237, 2, 190, 182, 88, 193, 26, 182, 232, 55, 253, 101, 85, 41,
+        247, 128, 11, 59, 222, 50, 111, 181, 13, 179, 172,
+    ];
+    let mut ints: Vec<u32> = s.iter().map(|&c| c as u32 % 80).collect();
+    let bytes: Vec<u8> = ints.iter().map(|&c| c as u8).collect();
+    let mut sa = vec![0; ints.len() + 1];
+    sais_ints_mut(&mut ints[..], 80, &mut sa[..]);
+    assert_eq!(sa, SuffixArray::new(&bytes[..]).into_parts().1);
+}
+
+#[test]
+fn sliding_window_too_long() {
+    assert!(SlidingWindowIndex::new(MAX_LENGTH).is_err());
+}
+
+#[test]
+fn max_length_by_target() {
+    // 16-bit, 32-bit and 64-bit usize respectively.
+    assert_eq!(max_length(u16::MAX as u64), u16::MAX as u64 - 1);
+    assert_eq!(max_length(u32::MAX as u64), i32::MAX as u64);
+    assert_eq!(max_length(u64::MAX), i32::MAX as u64);
+
+    assert_eq!(MAX_LENGTH as u64, max_length(usize::MAX as u64));
+    #[cfg(target_pointer_width = "32")]
+    assert_eq!(MAX_LENGTH, usize::MAX / 2);
+}
+
+#[test]
+fn set_empty_text() {
+    // the structures rebuilt for and from the empty byte string.
+    let mut sa = SuffixArray::new(&b""[..]);
+    sa.enable_buckets();
+    sa.enable_fast_search();
+    sa.enable_lcp_with(LcpKind::Compressed);
+    assert!(sa.memory_usage().extra > 0);
+    assert_eq!(sa.lcp_at(0), Some(0));
+
+    sa.set(&b"abab"[..]);
+    assert!(sa == SuffixArray::new(&b"abab"[..]));
+    sa.set(&b""[..]);
+    assert!(sa == SuffixArray::new(&b""[..]));
+    assert_eq!(sa.lcp_at(0), Some(0));
+    assert_eq!(sa.search_all(b""), &[0]);
+}
+
+#[test]
+fn search_all_partial_past_end() {
+    // once found by search_all_partial_correctness.
+    let sa = SuffixArray::new(&[1, 1][..]);
+    let found: Vec<_> = sa.search_all_partial(&[1, 1, 0]).collect();
+    assert_eq!(found, vec![(0, 2), (1, 1)]);
+}
+
+#[test]
+fn explain_empty_text() {
+    use super::Strategy;
+
+    // once found by explain_correctness.
+    let mut sa = SuffixArray::new(&b""[..]);
+    assert_eq!(sa.explain(b"").strategy, Strategy::Scan);
+    sa.set_naive_search_length(0);
+    let plan = sa.explain(b"");
+    assert_eq!(plan.candidates, 1);
+    assert_eq!(plan.strategy, Strategy::Scan);
+}
+
+#[test]
+fn replace_range_repairs_locally() {
+    let s: Vec<u8> = (0..=255).collect();
+    let mut sa = SuffixArray::new(s.clone());
+    assert!(sa.replace_range(10..12, &[7, 200, 3]));
+    let mut t = s;
+    t.splice(10..12, vec![7, 200, 3]);
+    assert!(sa == SuffixArray::new(t));
+
+    // runs past the re-sorted bytes in a repetitive text, and rebuilt.
+    let mut sa = SuffixArray::new(vec![0; 64]);
+    assert!(!sa.replace_range(32..32, &[0]));
+    assert!(sa == SuffixArray::new(vec![0; 65]));
+}
@@ -0,0 +1,100 @@
+use super::sa::SuffixArray;
+use super::storage::TextStorage;
+
+/// Set of patterns compiled into a trie, so that the patterns sharing a
+/// prefix share the narrowing of the range of suffixes prefixed by it, see
+/// `SuffixArray::search_trie`.
+#[derive(Debug, Clone, Default)]
+pub struct PatternTrie {
+    nodes: Vec<Node>,
+    len: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    // children ordered by byte.
+    children: Vec<(u8, usize)>,
+    // indices of the patterns ending here.
+    patterns: Vec<usize>,
+}
+
+impl PatternTrie {
+    /// Compile the patterns into a trie, keeping their indices.
+    pub fn compile<P: AsRef<[u8]>>(pats: &[P]) -> Self {
+        let mut nodes = vec![Node::default()];
+        for (k, pat) in pats.iter().enumerate() {
+            let mut node = 0;
+            for &c in pat.as_ref() {
+                let children = &nodes[node].children;
+                node = match children.binary_search_by_key(&c, |&(d, _)| d) {
+                    Ok(i) => children[i].1,
+                    Err(i) => {
+                        let child = nodes.len();
+                        nodes[node].children.insert(i, (c, child));
+                        nodes.push(Node::default());
+                        child
+                    }
+                };
+            }
+            nodes[node].patterns.push(k);
+        }
+        PatternTrie {
+            nodes,
+            len: pats.len(),
+        }
+    }
+
+    /// Number of the patterns.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Test if there is no pattern.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of the distinct prefixes of the patterns, including the
+    /// empty one, i.e. the number of narrowings done by a search.
+    pub fn prefix_count(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+/// Search for all the unsorted occurrences of each pattern in the trie by
+/// a depth-first traversal with a cursor, skipping the sub-trees of the
+/// prefixes that do not occur.
+pub fn search_trie<'a, T: TextStorage>(
+    sa: &'a SuffixArray<T>,
+    trie: &PatternTrie,
+) -> Vec<&'a [u32]> {
+    let mut result = vec![&[][..]; trie.len];
+    let nodes = &trie.nodes[..];
+    let mut cursor = sa.cursor();
+    for &k in nodes[0].patterns.iter() {
+        result[k] = cursor.positions();
+    }
+
+    // nodes on the path with the index of their next child to visit.
+    let mut stack = vec![(0, 0)];
+    while let Some(top) = stack.last_mut() {
+        let (node, i) = *top;
+        if let Some(&(c, child)) = nodes[node].children.get(i) {
+            top.1 += 1;
+            if cursor.extend(c) {
+                for &k in nodes[child].patterns.iter() {
+                    result[k] = cursor.positions();
+                }
+                stack.push((child, 0));
+            } else {
+                cursor.pop();
+            }
+        } else {
+            stack.pop();
+            if !stack.is_empty() {
+                cursor.pop();
+            }
+        }
+    }
+    result
+}
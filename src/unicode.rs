@@ -0,0 +1,44 @@
+/// Test if the byte continues a UTF-8 encoded character.
+#[inline]
+fn is_continuation(c: u8) -> bool {
+    c & 0xc0 == 0x80
+}
+
+/// Find the largest character boundary not greater than `pos`, clamped to
+/// the length of the byte string.
+///
+/// At most 3 continuation bytes are skipped, as in a valid UTF-8 encoding.
+pub fn snap_to_char_boundary(s: &[u8], pos: usize) -> usize {
+    let mut pos = Ord::min(pos, s.len());
+    let floor = pos.saturating_sub(3);
+    while pos > floor && pos < s.len() && is_continuation(s[pos]) {
+        pos -= 1;
+    }
+    pos
+}
+
+/// Map the byte positions in ascending order to the pairs of character
+/// index and byte position, counting the characters incrementally.
+///
+/// A character is counted for each byte which does not continue one, which
+/// is exact for a valid UTF-8 encoding.
+pub fn char_positions<'a, I>(
+    s: &'a [u8],
+    positions: I,
+) -> impl Iterator<Item = (usize, usize)> + 'a
+where
+    I: IntoIterator<Item = usize>,
+    I::IntoIter: 'a,
+{
+    let (mut byte, mut chars) = (0, 0);
+    positions.into_iter().map(move |p| {
+        assert!(byte <= p, "positions must be in ascending order");
+        let end = Ord::min(p, s.len());
+        if byte < end {
+            let step = &s[byte..end];
+            chars += step.iter().filter(|&&c| !is_continuation(c)).count();
+            byte = end;
+        }
+        (chars, p)
+    })
+}
@@ -9,3 +9,63 @@ pub fn lcp(xs: &[u8], ys: &[u8]) -> usize {
 pub fn trunc(s: &[u8], max: usize) -> &[u8] {
     &s[..Ord::min(s.len(), max)]
 }
+
+/// Scan for all the occurrences of pattern (can overlap) in text order.
+#[cfg(not(feature = "simd"))]
+pub fn scan<'a>(s: &'a [u8], pat: &'a [u8]) -> impl Iterator<Item = u32> + 'a {
+    (0..(s.len() + 1).saturating_sub(pat.len()))
+        .filter(move |&i| s[i..].starts_with(pat))
+        .map(|i| i as u32)
+}
+
+/// Scan for all the occurrences of pattern (can overlap) in text order,
+/// using the vectorized substring search of memchr.
+#[cfg(feature = "simd")]
+pub fn scan<'a>(s: &'a [u8], pat: &'a [u8]) -> impl Iterator<Item = u32> + 'a {
+    // the finder skips the overlapped occurrences, so restart after each.
+    let finder = memchr::memmem::Finder::new(pat);
+    let mut i = 0;
+    std::iter::from_fn(move || {
+        let p = i + finder.find(s.get(i..)?)?;
+        i = p + 1;
+        Some(p as u32)
+    })
+}
+
+/// Initial state of the 64-bit FNV-1a hash.
+pub const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Feed bytes into the 64-bit FNV-1a hash state.
+#[inline]
+pub fn fnv1a(state: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(state, |h, &b| {
+        (h ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Pack the leading `max` (at most 8) bytes of string into a big-endian word
+/// together with its truncated length, such that comparing the packed pairs
+/// agrees with comparing the truncated strings lexicographically.
+#[inline]
+pub fn pack_prefix(s: &[u8], max: usize) -> (u64, usize) {
+    debug_assert!(max > 0 && max <= 8);
+    if s.len() >= 8 {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(&s[..8]);
+        let mask = !0u64 << (64 - 8 * max);
+        (u64::from_be_bytes(buf) & mask, max)
+    } else {
+        let n = Ord::min(s.len(), max);
+        let mut buf = [0; 8];
+        buf[..n].copy_from_slice(&s[..n]);
+        (u64::from_be_bytes(buf), n)
+    }
+}
+
+/// Convert untrusted suffix array entry to position not exceeding `len`.
+#[inline]
+pub fn checked_pos(p: u32, len: usize) -> Option<usize> {
+    use std::convert::TryFrom;
+
+    usize::try_from(p).ok().filter(|&p| p <= len)
+}
@@ -0,0 +1,43 @@
+use std::hint::black_box;
+
+/// Size of the pages touched, which is the smallest common one.
+const PAGE_SIZE: usize = 4096;
+
+/// How much of a suffix array is touched by `SuffixArray::warm`, before the
+/// first queries after opening a large index, e.g. a mapped byte string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarmLevel {
+    /// Every page of the suffix array, the bucket pointers and the kept LCP
+    /// array, but not the byte string.
+    Index,
+    /// The index, and the leading bytes of the sampled suffixes that the
+    /// first steps of binary search compare with, in rank order.
+    SearchPaths,
+    /// The index, and every page of the byte string in text order.
+    Full,
+}
+
+/// Read one element from each page of the slice, returning the number of
+/// pages touched.
+pub(crate) fn touch<E: Copy>(v: &[E]) -> usize {
+    let step = Ord::max(PAGE_SIZE / std::mem::size_of::<E>().max(1), 1);
+    let mut pages = 0;
+    for i in (0..v.len()).step_by(step) {
+        black_box(v[i]);
+        pages += 1;
+    }
+    pages
+}
+
+/// Read the leading bytes of every `block`-th suffix in rank order,
+/// returning the number of suffixes touched.
+pub(crate) fn touch_samples(s: &[u8], sa: &[u32], block: usize) -> usize {
+    let mut touched = 0;
+    for &p in sa.iter().step_by(block) {
+        if let Some(&c) = s.get(p as usize) {
+            black_box(c);
+            touched += 1;
+        }
+    }
+    touched
+}
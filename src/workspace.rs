@@ -0,0 +1,91 @@
+use std::mem::ManuallyDrop;
+use std::ops::Deref;
+
+use super::sa::SuffixArray;
+use super::saca::{saca_with_buffers, MAX_LENGTH};
+use super::sais::SaisBuffers;
+use super::stats::IndexStats;
+
+/// Reusable buffers for constructing many suffix arrays one after another,
+/// e.g. one for each of millions of small records.
+///
+/// The suffix array and the scratch buffers of SA-IS are kept between the
+/// constructions, so that nothing is allocated once they are large enough,
+/// except by divsufsort and the recursion of SA-IS.
+///
+/// ```rust
+/// use suffix_array::Workspace;
+///
+/// let mut workspace = Workspace::new();
+/// for record in [&b"splendid"[..], b"splendor"] {
+///     let sa = workspace.build(record);
+///     assert!(sa.contains(b"splend"));
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Workspace {
+    sa: Vec<u32>,
+    sais: SaisBuffers,
+}
+
+impl Workspace {
+    /// Create an empty workspace.
+    pub fn new() -> Self {
+        Workspace::default()
+    }
+
+    /// Construct the suffix array of the byte string in the workspace, which
+    /// is returned to the workspace when the borrow ends.
+    ///
+    /// Panics if the byte string is longer than `MAX_LENGTH`.
+    pub fn build<'w, 's>(&'w mut self, s: &'s [u8]) -> SuffixArrayRef<'w, 's> {
+        assert!(s.len() <= MAX_LENGTH);
+        let mut sa = std::mem::take(&mut self.sa);
+        sa.clear();
+        sa.resize(s.len() + 1, 0);
+        let algorithm = IndexStats::sample(s).algorithm;
+        saca_with_buffers(
+            s,
+            &mut sa[..],
+            algorithm,
+            &mut |_| {},
+            &mut self.sais,
+        );
+
+        // the suffix array is just constructed.
+        let sa = unsafe { SuffixArray::unchecked_from_parts(s, sa) };
+        SuffixArrayRef {
+            sa: ManuallyDrop::new(sa),
+            buf: &mut self.sa,
+        }
+    }
+
+    /// Heap memory taken by the buffers, in bytes.
+    pub fn heap_size(&self) -> usize {
+        self.sa.capacity() * 4 + self.sais.heap_size()
+    }
+}
+
+/// Suffix array constructed in a `Workspace`, dereferencing to a
+/// `SuffixArray`.
+pub struct SuffixArrayRef<'w, 's> {
+    sa: ManuallyDrop<SuffixArray<&'s [u8]>>,
+    buf: &'w mut Vec<u32>,
+}
+
+impl<'s> Deref for SuffixArrayRef<'_, 's> {
+    type Target = SuffixArray<&'s [u8]>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.sa
+    }
+}
+
+impl Drop for SuffixArrayRef<'_, '_> {
+    fn drop(&mut self) {
+        // never used again, as this is being dropped.
+        let sa = unsafe { ManuallyDrop::take(&mut self.sa) };
+        let (_, sa) = sa.into_parts();
+        *self.buf = sa;
+    }
+}